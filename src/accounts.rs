@@ -1,7 +1,20 @@
-use crate::clients::{endpoint_url as epu, Client, Endpoints};
-use crate::resp_parser::{mutasi_parser::AccountMutasi, saldo_parser::AccountBalance};
-use crate::states::states::AppState;
-use anyhow::Result;
+use crate::clients::{Client, Endpoints};
+use crate::money::Amount;
+use crate::resp_parser::{
+    beneficiary_parser::{BeneficiaryChallenge, RegisteredBeneficiary},
+    form_parser::{check_refusal, form_amount, TransactionError},
+    interbank_parser::{Bank, InterbankChallenge, InterbankReceipt, NameInquiry, TransferService},
+    login_parser::LoginOutcome,
+    mutasi_parser::AccountMutasi,
+    parse_error::check_maintenance_page,
+    payment_parser::{PaymentChallenge, PaymentReceipt, VaInquiry},
+    saldo_parser::AccountBalance,
+    session_page::{classify_page, PageKind},
+    transfer_parser::{Beneficiary, TransferChallenge, TransferReceipt},
+};
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use std::fmt;
 
 // BcaAccount contains username and password
 #[derive(Copy, Clone, Debug)]
@@ -10,6 +23,233 @@ pub struct BcaAccount {
     password: &'static str,
 }
 
+// DateRange is the (inclusive) statement period requested from klikbca.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    pub fn new(start: NaiveDate, end: NaiveDate) -> Self {
+        DateRange { start, end }
+    }
+
+    // number of days in the period, both ends included
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1
+    }
+
+    // consecutive, non overlapping periods of at most max_days covering the whole range
+    pub fn windows(&self, max_days: i64) -> Vec<DateRange> {
+        let mut windows = vec![];
+        let mut start = self.start;
+        while start <= self.end {
+            let end = std::cmp::min(start + Duration::days(max_days - 1), self.end);
+            windows.push(DateRange::new(start, end));
+            start = end + Duration::days(1);
+        }
+        windows
+    }
+}
+
+// same format as the period of a klikbca statement
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - {}",
+            self.start.format("%d/%m/%Y"),
+            self.end.format("%d/%m/%Y")
+        )
+    }
+}
+
+// klikbca answers statement queries of at most 31 days
+pub const MAX_STATEMENT_DAYS: i64 = 31;
+// and only for the last two months
+pub const STATEMENT_HISTORY_DAYS: i64 = 61;
+
+// StatementLimits are the bounds klikbca puts on statement queries
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StatementLimits {
+    pub max_days: i64,
+    pub earliest: NaiveDate,
+    pub latest: NaiveDate,
+}
+
+impl StatementLimits {
+    // the limits of klikbca as of today
+    pub fn klikbca(today: NaiveDate) -> Self {
+        StatementLimits {
+            max_days: MAX_STATEMENT_DAYS,
+            earliest: today - Duration::days(STATEMENT_HISTORY_DAYS - 1),
+            latest: today,
+        }
+    }
+
+    // a range that can be fetched in windows of max_days
+    pub fn check(&self, range: &DateRange) -> Result<(), RangeError> {
+        if range.start > range.end {
+            return Err(RangeError::Reversed { range: *range });
+        }
+        if range.start < self.earliest {
+            return Err(RangeError::TooOld {
+                start: range.start,
+                earliest: self.earliest,
+            });
+        }
+        if range.end > self.latest {
+            return Err(RangeError::InFuture {
+                end: range.end,
+                latest: self.latest,
+            });
+        }
+        Ok(())
+    }
+}
+
+// RangeError is a statement period klikbca would refuse, found before any request is sent
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RangeError {
+    Reversed {
+        range: DateRange,
+    },
+    TooOld {
+        start: NaiveDate,
+        earliest: NaiveDate,
+    },
+    InFuture {
+        end: NaiveDate,
+        latest: NaiveDate,
+    },
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::Reversed { range } => {
+                write!(f, "invalid period {}: start is after end", range)
+            }
+            RangeError::TooOld { start, earliest } => write!(
+                f,
+                "{} is older than klikbca keeps statements, the earliest date is {}",
+                start.format("%d/%m/%Y"),
+                earliest.format("%d/%m/%Y")
+            ),
+            RangeError::InFuture { end, latest } => write!(
+                f,
+                "{} is in the future, the latest date is {}",
+                end.format("%d/%m/%Y"),
+                latest.format("%d/%m/%Y")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+// TransferRequest is a transfer to another BCA account, klikbca only accepts destinations
+// registered beforehand (daftar rekening tujuan).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferRequest {
+    pub to: String,
+    pub amount: Amount,
+    pub note: String,
+}
+
+impl TransferRequest {
+    pub fn new<T: Into<String>, N: Into<String>>(to: T, amount: Amount, note: N) -> Self {
+        TransferRequest {
+            to: to.into(),
+            amount,
+            note: note.into(),
+        }
+    }
+}
+
+// InterbankTransferRequest is a transfer to an account at another bank, identified by the
+// bank's clearing code. Unlike TransferRequest it needs no registered beneficiary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterbankTransferRequest {
+    pub bank_code: String,
+    pub to: String,
+    pub amount: Amount,
+    pub service: TransferService,
+    pub note: String,
+}
+
+// PaymentRequest pays a BCA Virtual Account, amount is only needed for open amount VAs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentRequest {
+    pub va_number: String,
+    pub amount: Option<Amount>,
+}
+
+// SessionError is returned when klikbca answers with its login form or session expired
+// notice instead of the requested page.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SessionError {
+    Expired,
+    Locked,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Expired => write!(f, "klikbca session has expired"),
+            SessionError::Locked => write!(
+                f,
+                "klikbca session is locked, refusing to log in again automatically"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+// LoginError is a rejected authentication, retrying with the same credentials is pointless
+// (and repeated wrong passwords get the user id blocked by klikbca).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoginError {
+    WrongCredentials,
+    AlreadyLoggedIn,
+    Blocked,
+    Rejected { snippet: String },
+}
+
+impl fmt::Display for LoginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginError::WrongCredentials => write!(
+                f,
+                "login failed: wrong user id or password, not retrying to avoid blocking the user id"
+            ),
+            LoginError::AlreadyLoggedIn => write!(
+                f,
+                "login failed: user is already logged in elsewhere, log out of the other session (or wait for it to time out) first"
+            ),
+            LoginError::Blocked => write!(
+                f,
+                "login failed: user id is blocked by klikbca, contact BCA to unblock it"
+            ),
+            LoginError::Rejected { snippet } => {
+                write!(f, "login failed: klikbca rejected the login: {:?}", snippet)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoginError {}
+
+// errors out when a response is not the page we asked for but a dead session page
+fn check_session(resp: &str) -> Result<()> {
+    match classify_page(resp) {
+        PageKind::LoginForm | PageKind::SessionExpired => Err(SessionError::Expired.into()),
+        PageKind::Content => Ok(()),
+    }
+}
+
 impl BcaAccount {
     pub fn new(user: String, password: String) -> Self {
         let user_static = Box::leak(user.into_boxed_str());
@@ -21,7 +261,7 @@ impl BcaAccount {
     }
 
     fn get_pub_ip(&self, client: &mut Client) -> Result<String> {
-        let pub_ip_url = epu(Endpoints::PubIp)?;
+        let pub_ip_url = client.endpoint_url(Endpoints::PubIp)?;
         let resp = client.simple_get(&pub_ip_url)?;
         let ip = resp
             .chars()
@@ -30,9 +270,9 @@ impl BcaAccount {
         Ok(ip)
     }
 
-    pub fn login(&self, client: &mut Client, state: &mut AppState) -> Result<()> {
+    pub fn login(&self, client: &mut Client) -> Result<()> {
         let ip = self.get_pub_ip(client)?;
-        let loginform_url = epu(Endpoints::Login)?;
+        let loginform_url = client.endpoint_url(Endpoints::Login)?;
         client.get(&loginform_url)?;
         let params: Vec<(&str, &str)> = vec![
             ("value(user_id)", self.user),
@@ -44,52 +284,49 @@ impl BcaAccount {
             ("value(mobile)", "true"),
             ("mobile", "true"),
         ];
-        let login_url = epu(Endpoints::Authentication)?;
-        client.post(&login_url, Some(params))?;
-        state.toggle_login();
-        Ok(())
-    }
-
-    fn check_login_status(&self, client: &mut Client, state: &mut AppState) -> Result<()> {
-        if !state.is_logged_in {
-            self.login(client, state)?;
+        let login_url = client.endpoint_url(Endpoints::Authentication)?;
+        let resp = client.post(&login_url, Some(params))?;
+        check_maintenance_page(&resp)?;
+        match LoginOutcome::from_resp(&resp) {
+            LoginOutcome::Success => Ok(()),
+            LoginOutcome::WrongCredentials => Err(LoginError::WrongCredentials.into()),
+            LoginOutcome::AlreadyLoggedIn => Err(LoginError::AlreadyLoggedIn.into()),
+            LoginOutcome::Blocked => Err(LoginError::Blocked.into()),
+            LoginOutcome::Rejected { snippet } => Err(LoginError::Rejected { snippet }.into()),
         }
-        state.toggle_login();
-        Ok(())
     }
 
-    fn to_menu_page(&self, client: &mut Client) -> Result<()> {
-        let main_menu_url = epu(Endpoints::AccountStatement)?;
+    fn visit_menu_page(&self, client: &mut Client) -> Result<()> {
+        let main_menu_url = client.endpoint_url(Endpoints::AccountStatement)?;
         let params = vec![("value(actions)", "menu")];
-        client.post(&main_menu_url, Some(params))?;
-        Ok(())
+        let resp = client.post(&main_menu_url, Some(params))?;
+        check_session(&resp)
     }
 
-    pub fn get_saldo(&self, client: &mut Client, state: &mut AppState) -> Result<()> {
-        self.check_login_status(client, state)?;
-        self.to_menu_page(client)?;
-        let saldo_url = epu(Endpoints::BalanceInquiry)?;
+    pub fn get_saldo(&self, client: &mut Client) -> Result<AccountBalance<'static>> {
+        self.visit_menu_page(client)?;
+        let saldo_url = client.endpoint_url(Endpoints::BalanceInquiry)?;
         let resp = client.post(&saldo_url, None::<Vec<(&str, &str)>>)?;
-        let saldo = AccountBalance::new(resp)?;
-
-        state.update_balance(saldo);
-
-        Ok(())
+        check_session(&resp)?;
+        Ok(AccountBalance::new(resp)?)
     }
 
-    pub fn get_mutasi(&self, client: &mut Client, state: &mut AppState) -> Result<()> {
-        self.check_login_status(client, state)?;
-        self.to_menu_page(client)?;
-        let main_menu_url = epu(Endpoints::AccountStatement)?;
+    pub fn get_mutasi(
+        &self,
+        client: &mut Client,
+        range: &DateRange,
+    ) -> Result<AccountMutasi<'static>> {
+        self.visit_menu_page(client)?;
+        let main_menu_url = client.endpoint_url(Endpoints::AccountStatement)?;
         let mut params = vec![("value(actions)", "acct_stmt")];
-        client.post(&main_menu_url, Some(params))?;
+        check_session(&client.post(&main_menu_url, Some(params))?)?;
 
-        let start_dt = state.start_date.format("%d").to_string();
-        let start_mt = state.start_date.format("%m").to_string();
-        let start_yr = state.start_date.format("%Y").to_string();
-        let end_dt = state.end_date.format("%d").to_string();
-        let end_mt = state.end_date.format("%m").to_string();
-        let end_yr = state.end_date.format("%Y").to_string();
+        let start_dt = range.start.format("%d").to_string();
+        let start_mt = range.start.format("%m").to_string();
+        let start_yr = range.start.format("%Y").to_string();
+        let end_dt = range.end.format("%d").to_string();
+        let end_mt = range.end.format("%m").to_string();
+        let end_yr = range.end.format("%Y").to_string();
 
         params = vec![
             ("r1", "1"),
@@ -104,16 +341,279 @@ impl BcaAccount {
         ];
 
         let resp = client.post(&main_menu_url, Some(params))?;
-        let acc_mut = AccountMutasi::new(resp)?;
-        state.update_mutations(acc_mut);
+        check_session(&resp)?;
+        Ok(AccountMutasi::new(resp)?)
+    }
 
-        Ok(())
+    pub fn get_beneficiaries(&self, client: &mut Client) -> Result<Vec<Beneficiary>> {
+        self.visit_menu_page(client)?;
+        let transfer_url = client.endpoint_url(Endpoints::FundTransfer)?;
+        let params = vec![("value(actions)", "transfer_form")];
+        let resp = client.post(&transfer_url, Some(params))?;
+        check_session(&resp)?;
+        Ok(Beneficiary::list_from_resp(&resp)?)
+    }
+
+    // submits the transfer form, klikbca answers with the KeyBCA challenge to confirm it
+    pub fn prepare_transfer(
+        &self,
+        client: &mut Client,
+        request: &TransferRequest,
+    ) -> Result<TransferChallenge> {
+        if request.amount.minor() <= 0 {
+            return Err(anyhow!("invalid transfer amount: {}", request.amount));
+        }
+        let beneficiaries = self.get_beneficiaries(client)?;
+        if !beneficiaries
+            .iter()
+            .any(|b| b.account_number() == request.to)
+        {
+            return Err(TransactionError::UnknownBeneficiary.into());
+        }
+        let amount = form_amount(request.amount);
+        let params = vec![
+            ("value(acctTo)", request.to.as_str()),
+            ("value(amount)", amount.as_str()),
+            ("value(remark)", request.note.as_str()),
+            ("value(actions)", "transfer_validate"),
+        ];
+        let transfer_url = client.endpoint_url(Endpoints::FundTransfer)?;
+        let resp = client.post(&transfer_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(TransferChallenge::from_resp(&resp)?)
+    }
+
+    // executes the transfer prepared in this session with the response read off the KeyBCA token
+    pub fn confirm_transfer(
+        &self,
+        client: &mut Client,
+        keybca_response: &str,
+    ) -> Result<TransferReceipt> {
+        let params = vec![
+            ("value(respondAppli)", keybca_response.trim()),
+            ("value(actions)", "transfer_execute"),
+        ];
+        let transfer_url = client.endpoint_url(Endpoints::FundTransfer)?;
+        let resp = client.post(&transfer_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(TransferReceipt::from_resp(&resp)?)
+    }
+
+    pub fn get_banks(&self, client: &mut Client) -> Result<Vec<Bank>> {
+        self.visit_menu_page(client)?;
+        let interbank_url = client.endpoint_url(Endpoints::InterbankTransfer)?;
+        let params = vec![("value(actions)", "interbank_form")];
+        let resp = client.post(&interbank_url, Some(params))?;
+        check_session(&resp)?;
+        Ok(Bank::list_from_resp(&resp)?)
+    }
+
+    // asks the destination bank for the name of the account holder
+    pub fn inquire_account(
+        &self,
+        client: &mut Client,
+        bank_code: &str,
+        account_number: &str,
+    ) -> Result<NameInquiry> {
+        self.visit_menu_page(client)?;
+        let params = vec![
+            ("value(bankCode)", bank_code),
+            ("value(acctTo)", account_number),
+            ("value(actions)", "name_inquiry"),
+        ];
+        let interbank_url = client.endpoint_url(Endpoints::InterbankTransfer)?;
+        let resp = client.post(&interbank_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(NameInquiry::from_resp(&resp)?)
+    }
+
+    // submits the interbank transfer form, klikbca answers with the fee and the KeyBCA challenge
+    pub fn prepare_interbank_transfer(
+        &self,
+        client: &mut Client,
+        request: &InterbankTransferRequest,
+    ) -> Result<InterbankChallenge> {
+        if request.amount.minor() <= 0 {
+            return Err(anyhow!("invalid transfer amount: {}", request.amount));
+        }
+        self.inquire_account(client, &request.bank_code, &request.to)?;
+        let amount = form_amount(request.amount);
+        let params = vec![
+            ("value(bankCode)", request.bank_code.as_str()),
+            ("value(acctTo)", request.to.as_str()),
+            ("value(amount)", amount.as_str()),
+            ("value(service)", request.service.code()),
+            ("value(remark)", request.note.as_str()),
+            ("value(actions)", "interbank_validate"),
+        ];
+        let interbank_url = client.endpoint_url(Endpoints::InterbankTransfer)?;
+        let resp = client.post(&interbank_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(InterbankChallenge::from_resp(&resp)?)
+    }
+
+    pub fn confirm_interbank_transfer(
+        &self,
+        client: &mut Client,
+        keybca_response: &str,
+    ) -> Result<InterbankReceipt> {
+        let params = vec![
+            ("value(respondAppli)", keybca_response.trim()),
+            ("value(actions)", "interbank_execute"),
+        ];
+        let interbank_url = client.endpoint_url(Endpoints::InterbankTransfer)?;
+        let resp = client.post(&interbank_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(InterbankReceipt::from_resp(&resp)?)
+    }
+
+    // looks up the bill behind a virtual account number
+    pub fn inquire_va(&self, client: &mut Client, va_number: &str) -> Result<VaInquiry> {
+        self.visit_menu_page(client)?;
+        let params = vec![
+            ("value(vaNumber)", va_number),
+            ("value(actions)", "va_inquiry"),
+        ];
+        let payment_url = client.endpoint_url(Endpoints::Payment)?;
+        let resp = client.post(&payment_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(VaInquiry::from_resp(&resp)?)
+    }
+
+    // inquires the virtual account and submits the payment, klikbca answers with the KeyBCA
+    // challenge. The billed amount is paid unless the VA is an open amount one.
+    pub fn prepare_payment(
+        &self,
+        client: &mut Client,
+        request: &PaymentRequest,
+    ) -> Result<PaymentChallenge> {
+        let inquiry = self.inquire_va(client, &request.va_number)?;
+        let amount = match (inquiry.amount(), request.amount) {
+            (Some(billed), Some(amount)) if billed.amount() != amount => {
+                return Err(anyhow!(
+                    "virtual account {} bills {}, not {}",
+                    inquiry.va_number(),
+                    billed,
+                    amount
+                ))
+            }
+            (Some(billed), _) => billed.amount(),
+            (None, Some(amount)) if amount.minor() > 0 => amount,
+            (None, Some(amount)) => return Err(anyhow!("invalid payment amount: {}", amount)),
+            (None, None) => {
+                return Err(anyhow!(
+                    "virtual account {} has an open amount, an amount is required",
+                    inquiry.va_number()
+                ))
+            }
+        };
+        let amount = form_amount(amount);
+        let params = vec![
+            ("value(vaNumber)", request.va_number.as_str()),
+            ("value(amount)", amount.as_str()),
+            ("value(actions)", "va_validate"),
+        ];
+        let payment_url = client.endpoint_url(Endpoints::Payment)?;
+        let resp = client.post(&payment_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(PaymentChallenge::from_resp(&resp)?)
+    }
+
+    pub fn confirm_payment(
+        &self,
+        client: &mut Client,
+        keybca_response: &str,
+    ) -> Result<PaymentReceipt> {
+        let params = vec![
+            ("value(respondAppli)", keybca_response.trim()),
+            ("value(actions)", "va_execute"),
+        ];
+        let payment_url = client.endpoint_url(Endpoints::Payment)?;
+        let resp = client.post(&payment_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(PaymentReceipt::from_resp(&resp)?)
+    }
+
+    // the whole daftar transfer, BCA and other banks
+    pub fn get_registered_beneficiaries(
+        &self,
+        client: &mut Client,
+    ) -> Result<Vec<RegisteredBeneficiary>> {
+        self.visit_menu_page(client)?;
+        let beneficiary_url = client.endpoint_url(Endpoints::Beneficiaries)?;
+        let params = vec![("value(actions)", "list")];
+        let resp = client.post(&beneficiary_url, Some(params))?;
+        check_session(&resp)?;
+        Ok(RegisteredBeneficiary::list_from_resp(&resp)?)
+    }
+
+    // submits a new beneficiary, klikbca looks up the name and asks for KeyBCA
+    pub fn prepare_add_beneficiary(
+        &self,
+        client: &mut Client,
+        bank_code: &str,
+        account_number: &str,
+    ) -> Result<BeneficiaryChallenge> {
+        self.visit_menu_page(client)?;
+        let params = vec![
+            ("value(bankCode)", bank_code),
+            ("value(acctNo)", account_number),
+            ("value(actions)", "add_validate"),
+        ];
+        let beneficiary_url = client.endpoint_url(Endpoints::Beneficiaries)?;
+        let resp = client.post(&beneficiary_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(BeneficiaryChallenge::from_resp(&resp)?)
+    }
+
+    pub fn confirm_add_beneficiary(
+        &self,
+        client: &mut Client,
+        keybca_response: &str,
+    ) -> Result<RegisteredBeneficiary> {
+        let params = vec![
+            ("value(respondAppli)", keybca_response.trim()),
+            ("value(actions)", "add_execute"),
+        ];
+        let beneficiary_url = client.endpoint_url(Endpoints::Beneficiaries)?;
+        let resp = client.post(&beneficiary_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(RegisteredBeneficiary::added_from_resp(&resp)?)
+    }
+
+    // removes an entry, klikbca answers with the remaining list
+    pub fn remove_beneficiary(
+        &self,
+        client: &mut Client,
+        bank_code: &str,
+        account_number: &str,
+    ) -> Result<Vec<RegisteredBeneficiary>> {
+        self.visit_menu_page(client)?;
+        let params = vec![
+            ("value(bankCode)", bank_code),
+            ("value(acctNo)", account_number),
+            ("value(actions)", "delete"),
+        ];
+        let beneficiary_url = client.endpoint_url(Endpoints::Beneficiaries)?;
+        let resp = client.post(&beneficiary_url, Some(params))?;
+        check_session(&resp)?;
+        check_refusal(&resp)?;
+        Ok(RegisteredBeneficiary::list_from_resp(&resp)?)
     }
 
-    pub fn logout(&self, client: &mut Client, state: &mut AppState) -> Result<()> {
-        let logout_url = epu(Endpoints::Authentication)?;
+    pub fn logout(&self, client: &mut Client) -> Result<()> {
+        let logout_url = client.endpoint_url(Endpoints::Authentication)?;
         client.get(&logout_url)?;
-        state.is_logged_in = false;
         Ok(())
     }
 }
@@ -0,0 +1,371 @@
+use crate::accounts::{SessionError, TransferRequest};
+use crate::money::Amount;
+use crate::policy::PolicyError;
+use crate::resp_parser::form_parser::TransactionError;
+use crate::resp_parser::transfer_parser::{Beneficiary, TransferChallenge};
+use crate::session::BcaSession;
+use crate::storage::receipts::{ReceiptStore, StoredReceipt};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// klikbca cuts the berita of a transfer at 36 characters
+const MAX_NOTE_LEN: usize = 36;
+
+// BatchEntry is one transfer of a batch file: a row of "beneficiary,amount,note"
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchEntry {
+    // line in the batch file, the header is line 1
+    pub line: usize,
+    pub to: String,
+    pub amount: Amount,
+    pub note: String,
+}
+
+impl BatchEntry {
+    pub fn request(&self) -> TransferRequest {
+        TransferRequest::new(self.to.as_str(), self.amount, self.note.as_str())
+    }
+}
+
+// BatchIssue is a row that can't be transferred as written
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for BatchIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchLine {
+    beneficiary: String,
+    amount: String,
+    #[serde(default)]
+    note: String,
+}
+
+// TransferBatch is a csv file of transfers to registered BCA beneficiaries, e.g. the payroll
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferBatch {
+    path: PathBuf,
+    entries: Vec<BatchEntry>,
+    // rows that did not even parse
+    issues: Vec<BatchIssue>,
+}
+
+impl TransferBatch {
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        let mut entries = vec![];
+        let mut issues = vec![];
+        for (i, row) in reader.deserialize::<BatchLine>().enumerate() {
+            let line = i + 2;
+            let row = match row {
+                Ok(row) => row,
+                Err(e) => {
+                    issues.push(BatchIssue {
+                        line,
+                        message: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            match row.amount.parse::<Amount>() {
+                Ok(amount) => entries.push(BatchEntry {
+                    line,
+                    to: row.beneficiary,
+                    amount,
+                    note: row.note,
+                }),
+                Err(e) => issues.push(BatchIssue {
+                    line,
+                    message: e.to_string(),
+                }),
+            }
+        }
+        Ok(TransferBatch {
+            path: path.to_path_buf(),
+            entries,
+            issues,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &[BatchEntry] {
+        &self.entries
+    }
+
+    // every row that would be refused, checked before anything is transferred. Identical rows
+    // are reported too, paying someone twice in one batch is almost always a copy-paste error.
+    pub fn validate(&self, beneficiaries: &[Beneficiary]) -> Vec<BatchIssue> {
+        let mut issues = self.issues.clone();
+        let mut seen = HashSet::new();
+        for entry in &self.entries {
+            let mut issue = |message: String| {
+                issues.push(BatchIssue {
+                    line: entry.line,
+                    message,
+                })
+            };
+            if entry.amount.minor() <= 0 {
+                issue(format!("invalid transfer amount: {}", entry.amount));
+            }
+            if !beneficiaries.iter().any(|b| b.account_number() == entry.to) {
+                issue(format!("{} is not a registered beneficiary", entry.to));
+            }
+            if entry.note.chars().count() > MAX_NOTE_LEN {
+                issue(format!("note longer than {} characters", MAX_NOTE_LEN));
+            }
+            if !seen.insert((&entry.to, entry.amount, &entry.note)) {
+                issue("same beneficiary, amount and note as an earlier row".to_string());
+            }
+        }
+        issues.sort_by_key(|i| i.line);
+        issues
+    }
+
+    pub fn total(&self) -> Amount {
+        self.entries.iter().map(|e| e.amount).sum()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    Pending,
+    // handed to klikbca without learning the outcome. Never retried automatically: check the
+    // statement and set it to done or failed in the report.
+    Submitted,
+    Done,
+    // refused before any money moved, retried on the next run
+    Failed,
+}
+
+impl fmt::Display for EntryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            EntryStatus::Pending => "pending",
+            EntryStatus::Submitted => "submitted",
+            EntryStatus::Done => "done",
+            EntryStatus::Failed => "failed",
+        })
+    }
+}
+
+// EntryResult is the outcome of a batch entry, one row of the report
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryResult {
+    pub entry: BatchEntry,
+    // beneficiary name as klikbca showed it
+    pub name: String,
+    pub status: EntryStatus,
+    pub reference: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReportLine {
+    line: usize,
+    beneficiary: String,
+    name: String,
+    amount: String,
+    note: String,
+    status: EntryStatus,
+    reference: String,
+    message: String,
+}
+
+// BatchReport is the per row result of a batch, written after every change of a row. It is
+// the state a run resumes from: done and submitted rows are never transferred again.
+#[derive(Clone, Debug)]
+pub struct BatchReport {
+    path: PathBuf,
+    results: Vec<EntryResult>,
+}
+
+impl BatchReport {
+    // default report location, next to the batch file ("payroll.csv" -> "payroll.report.csv")
+    pub fn default_path(batch: &Path) -> PathBuf {
+        let stem = batch
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        batch.with_file_name(format!("{}.report.csv", stem))
+    }
+
+    // picks up the report of an earlier run, as long as it was made for the same rows
+    pub fn open(path: &Path, batch: &TransferBatch) -> Result<Self> {
+        let mut results = batch
+            .entries()
+            .iter()
+            .map(|entry| EntryResult {
+                entry: entry.clone(),
+                name: String::new(),
+                status: EntryStatus::Pending,
+                reference: String::new(),
+                message: String::new(),
+            })
+            .collect::<Vec<EntryResult>>();
+        if path.exists() {
+            let previous = read_report(path)?;
+            if previous.len() != results.len()
+                || previous
+                    .iter()
+                    .zip(&results)
+                    .any(|(prev, cur)| prev.entry != cur.entry)
+            {
+                return Err(anyhow!(
+                    "{} was written for a different batch, move it away to start over",
+                    path.display()
+                ));
+            }
+            results = previous;
+        }
+        Ok(BatchReport {
+            path: path.to_path_buf(),
+            results,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn results(&self) -> &[EntryResult] {
+        &self.results
+    }
+
+    // rows a run still has to transfer
+    pub fn to_run(&self) -> Vec<usize> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| matches!(r.status, EntryStatus::Pending | EntryStatus::Failed))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn count(&self, status: EntryStatus) -> usize {
+        self.results.iter().filter(|r| r.status == status).count()
+    }
+
+    // transfers row i, asking keybca for the response to its challenge. Returning an error from
+    // keybca stops before anything is submitted and leaves the row as it was.
+    pub fn execute<F>(
+        &mut self,
+        i: usize,
+        session: &mut BcaSession,
+        store: &ReceiptStore,
+        keybca: F,
+    ) -> Result<&EntryResult>
+    where
+        F: FnOnce(&TransferChallenge) -> Result<String>,
+    {
+        let request = self.results[i].entry.request();
+        let challenge = match session.prepare_transfer(&request) {
+            Ok(challenge) => challenge,
+            Err(e) => {
+                self.update(i, EntryStatus::Failed, "", &e.to_string())?;
+                return Ok(&self.results[i]);
+            }
+        };
+        self.results[i].name = challenge.beneficiary().name().to_string();
+        let response = keybca(&challenge)?;
+        // written before the request goes out, a crash from here on can't lead to a retry
+        self.update(i, EntryStatus::Submitted, "", "")?;
+        match session.confirm_transfer(&response) {
+            Ok(receipt) => {
+                self.update(i, EntryStatus::Done, receipt.reference(), "")?;
+                store.append(&StoredReceipt::from(&receipt))?;
+            }
+            // refused locally, or klikbca answered with a refusal or its login page: nothing was
+            // transferred
+            Err(e) if is_refusal(&e) => self.update(i, EntryStatus::Failed, "", &e.to_string())?,
+            Err(e) => self.update(
+                i,
+                EntryStatus::Submitted,
+                "",
+                &format!("outcome unknown, check the statement: {}", e),
+            )?,
+        }
+        Ok(&self.results[i])
+    }
+
+    fn update(
+        &mut self,
+        i: usize,
+        status: EntryStatus,
+        reference: &str,
+        message: &str,
+    ) -> Result<()> {
+        let result = &mut self.results[i];
+        result.status = status;
+        result.reference = reference.to_string();
+        result.message = message.to_string();
+        self.save()
+    }
+
+    // written to a temporary file first so a crash can't truncate the report
+    fn save(&self) -> Result<()> {
+        let tmp = self.path.with_extension("csv.tmp");
+        let mut writer = csv::Writer::from_path(&tmp)?;
+        for r in &self.results {
+            writer.serialize(ReportLine {
+                line: r.entry.line,
+                beneficiary: r.entry.to.clone(),
+                name: r.name.clone(),
+                amount: r.entry.amount.to_string(),
+                note: r.entry.note.clone(),
+                status: r.status,
+                reference: r.reference.clone(),
+                message: r.message.clone(),
+            })?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn read_report(path: &Path) -> Result<Vec<EntryResult>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    reader
+        .deserialize::<ReportLine>()
+        .map(|line| {
+            let line = line.map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+            Ok(EntryResult {
+                entry: BatchEntry {
+                    line: line.line,
+                    to: line.beneficiary,
+                    amount: line.amount.parse()?,
+                    note: line.note,
+                },
+                name: line.name,
+                status: line.status,
+                reference: line.reference,
+                message: line.message,
+            })
+        })
+        .collect()
+}
+
+fn is_refusal(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<TransactionError>().is_some()
+        || e.downcast_ref::<SessionError>().is_some()
+        || e.downcast_ref::<PolicyError>().is_some()
+}
@@ -2,14 +2,66 @@ use anyhow::{anyhow, Result};
 use isahc::prelude::*;
 use url::{form_urlencoded, Url};
 
+pub const DEFAULT_BASE_URL: &str = "https://m.klikbca.com/";
+pub const DEFAULT_PUB_IP_URL: &str = "http://icanhazip.com/";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Endpoints {
     Login,
     Authentication,
     AccountStatement,
     BalanceInquiry,
+    FundTransfer,
+    InterbankTransfer,
+    Payment,
+    Beneficiaries,
     PubIp,
 }
 
+impl Endpoints {
+    // path relative to the klikbca base url, None for endpoints hosted elsewhere
+    pub fn path(self) -> Option<&'static str> {
+        match self {
+            Endpoints::Login => Some("login.jsp"),
+            Endpoints::Authentication => Some("authentication.do"),
+            Endpoints::AccountStatement => Some("accountstmt.do"),
+            Endpoints::BalanceInquiry => Some("balanceinquiry.do"),
+            Endpoints::FundTransfer => Some("fundtransfer.do"),
+            Endpoints::InterbankTransfer => Some("interbanktransfer.do"),
+            Endpoints::Payment => Some("payment.do"),
+            Endpoints::Beneficiaries => Some("beneficiary.do"),
+            Endpoints::PubIp => None,
+        }
+    }
+}
+
+// ClientConfig holds the hosts the client talks to, so the whole flow can be pointed at a
+// local stand-in server instead of the real bank.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientConfig {
+    pub base_url: Url,
+    pub pub_ip_url: Url,
+}
+
+impl ClientConfig {
+    pub fn new(base_url: &str, pub_ip_url: &str) -> Result<Self> {
+        let base_url = Url::parse(base_url)?;
+        if base_url.cannot_be_a_base() || base_url.host_str().is_none() {
+            return Err(anyhow!("invalid base url: {}", base_url));
+        }
+        Ok(ClientConfig {
+            base_url,
+            pub_ip_url: Url::parse(pub_ip_url)?,
+        })
+    }
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig::new(DEFAULT_BASE_URL, DEFAULT_PUB_IP_URL).expect("invalid default urls")
+    }
+}
+
 // creates & validates url from string literal
 pub fn build_url<I>(base: &str, path: I) -> Result<Url>
 where
@@ -19,24 +71,21 @@ where
     let mut url = Url::parse(base)?;
     url.path_segments_mut()
         .map_err(|_| anyhow!("cannot be base"))?
+        .pop_if_empty()
         .extend(path);
     Ok(url)
 }
 
 // get endpoint url from Endpoints enum
-pub fn endpoint_url(e: Endpoints) -> Result<Url> {
-    let base_url = "https://m.klikbca.com/";
-    match e {
-        Endpoints::Login => build_url(base_url, &["login.jsp"]),
-        Endpoints::Authentication => build_url(base_url, &["authentication.do"]),
-        Endpoints::AccountStatement => build_url(base_url, &["accountstmt.do"]),
-        Endpoints::BalanceInquiry => build_url(base_url, &["balanceinquiry.do"]),
-        Endpoints::PubIp => build_url("http://icanhazip.com", &[""]),
+pub fn endpoint_url(config: &ClientConfig, e: Endpoints) -> Result<Url> {
+    match e.path() {
+        Some(path) => build_url(config.base_url.as_str(), &[path]),
+        None => Ok(config.pub_ip_url.clone()),
     }
 }
 
 // produces default headers for the reusable http client.
-fn default_headermap() -> http::HeaderMap {
+fn default_headermap(config: &ClientConfig) -> Result<http::HeaderMap> {
     let mut new_headers = http::HeaderMap::new();
     new_headers
         .insert(
@@ -51,10 +100,18 @@ fn default_headermap() -> http::HeaderMap {
 
     new_headers.insert(
         http::header::ORIGIN,
-        "https://m.klikbca.com".parse().unwrap(),
+        config.base_url.origin().ascii_serialization().parse()?,
     );
 
-    new_headers.insert(http::header::HOST, "m.klikbca.com".parse().unwrap());
+    let host = match config.base_url.port() {
+        Some(port) => format!(
+            "{}:{}",
+            config.base_url.host_str().unwrap_or_default(),
+            port
+        ),
+        None => config.base_url.host_str().unwrap_or_default().to_string(),
+    };
+    new_headers.insert(http::header::HOST, host.parse()?);
 
     new_headers.insert(
         http::header::ACCEPT,
@@ -73,7 +130,7 @@ fn default_headermap() -> http::HeaderMap {
         "gzip, deflate, br".parse().unwrap(),
     );
 
-    new_headers
+    Ok(new_headers)
 }
 
 // Req is the reusable http client for the entire program.
@@ -82,19 +139,28 @@ fn default_headermap() -> http::HeaderMap {
 #[derive(Debug)]
 pub struct Client {
     c: HttpClient,
+    config: ClientConfig,
 }
 
 impl Client {
-    // Create default Client struct
-    pub fn new() -> Result<Self> {
+    // Create Client struct talking to the hosts in config
+    pub fn new(config: ClientConfig) -> Result<Self> {
         let c = HttpClient::builder()
             .redirect_policy(isahc::config::RedirectPolicy::Limit(10))
-            .default_headers(&default_headermap())
+            .default_headers(&default_headermap(&config)?)
             .cookies()
             .tcp_keepalive(std::time::Duration::from_secs(300))
             .auto_referer()
             .build()?;
-        Ok(Client { c })
+        Ok(Client { c, config })
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    pub fn endpoint_url(&self, e: Endpoints) -> Result<Url> {
+        endpoint_url(&self.config, e)
     }
 
     pub fn simple_get(&self, u: &Url) -> Result<String> {
@@ -119,24 +185,21 @@ impl Client {
         K: AsRef<str>,
         V: AsRef<str>,
     {
-        let req: Request<Body>;
-        match form {
+        let req: Request<Body> = match form {
             Some(f) => {
                 let mut form_data = form_urlencoded::Serializer::new(String::new());
                 form_data.extend_pairs(f);
-                let form_string: String = form_data.finish().into();
-                req = Request::post(u.as_str())
+                let form_string: String = form_data.finish();
+                Request::post(u.as_str())
                     .redirect_policy(isahc::config::RedirectPolicy::Limit(5))
                     .tcp_keepalive(std::time::Duration::from_secs(3600))
-                    .body(Body::from_bytes(form_string.into_bytes()))?;
+                    .body(Body::from_bytes(form_string.into_bytes()))?
             }
-            None => {
-                req = Request::post(u.as_str())
-                    .redirect_policy(isahc::config::RedirectPolicy::Limit(5))
-                    .tcp_keepalive(std::time::Duration::from_secs(3600))
-                    .body(Body::empty())?;
-            }
-        }
+            None => Request::post(u.as_str())
+                .redirect_policy(isahc::config::RedirectPolicy::Limit(5))
+                .tcp_keepalive(std::time::Duration::from_secs(3600))
+                .body(Body::empty())?,
+        };
         let mut resp = self.c.send(req)?;
         Ok(resp.text()?)
     }
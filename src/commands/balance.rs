@@ -0,0 +1,42 @@
+use crate::commands::OutputFormat;
+use anyhow::Result;
+use bca_rust::{export, AccountBalance, BcaSession, DecimalStyle, Ledger};
+use std::io;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct BalanceOpt {
+    /// json, table or csv
+    #[structopt(long = "format", default_value = "table")]
+    format: OutputFormat,
+}
+
+// runs the balance subcommand, the balance is kept in the ledger too
+pub(crate) fn run(session: &mut BcaSession, ledger: &mut Ledger, opt: BalanceOpt) -> Result<()> {
+    session.login()?;
+    let result = session.balance();
+    let logout = session.logout();
+    let balance = result?;
+    logout?;
+    ledger.insert_balance(&balance)?;
+    match opt.format {
+        OutputFormat::Table => {
+            println!("{}  {}", balance.account_number(), balance.balance());
+            Ok(())
+        }
+        OutputFormat::Json => export::json::write_balance(&balance, io::stdout()),
+        OutputFormat::Csv => write_csv(&balance),
+    }
+}
+
+fn write_csv(balance: &AccountBalance) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    writer.write_record(["account", "currency", "balance"])?;
+    writer.write_record([
+        balance.account_number(),
+        balance.currency(),
+        &DecimalStyle::Point.format(balance.balance().amount()),
+    ])?;
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use crate::commands::{confirm_beneficiary, read_keybca_response};
+use anyhow::Result;
+use bca_rust::{
+    resp_parser::beneficiary_parser::BCA_BANK_CODE, BcaSession, BeneficiaryCache,
+    RegisteredBeneficiary,
+};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) enum BeneficiariesOpt {
+    /// list the registered beneficiaries
+    List {
+        /// show the locally cached list without logging in
+        #[structopt(long = "cached")]
+        cached: bool,
+    },
+    /// register a beneficiary, confirmed with KeyBCA
+    Add {
+        /// 3 digit bank code, 014 for BCA
+        #[structopt(long = "bank", default_value = BCA_BANK_CODE)]
+        bank: String,
+        #[structopt(long = "account")]
+        account: String,
+    },
+    /// remove a beneficiary
+    Remove {
+        /// 3 digit bank code, 014 for BCA
+        #[structopt(long = "bank", default_value = BCA_BANK_CODE)]
+        bank: String,
+        #[structopt(long = "account")]
+        account: String,
+    },
+}
+
+// runs the beneficiaries subcommand, the cache is refreshed after every online action
+pub(crate) fn run(
+    session: &mut BcaSession,
+    cache: &BeneficiaryCache,
+    opt: BeneficiariesOpt,
+) -> Result<()> {
+    if let BeneficiariesOpt::List { cached: true } = opt {
+        return list_cached(cache);
+    }
+    session.login()?;
+    let result = match opt {
+        BeneficiariesOpt::List { .. } => session.registered_beneficiaries(),
+        BeneficiariesOpt::Add { bank, account } => add(session, &bank, &account),
+        BeneficiariesOpt::Remove { bank, account } => session.remove_beneficiary(&bank, &account),
+    }
+    .and_then(|entries| {
+        print_entries(&entries);
+        cache.save(&entries)
+    });
+    let logout = session.logout();
+    result.and(logout)
+}
+
+fn list_cached(cache: &BeneficiaryCache) -> Result<()> {
+    match cache.load()? {
+        Some(cached) => {
+            println!("cached at {}", cached.fetched_at.format("%d/%m/%Y %H:%M"));
+            print_entries(&cached.entries);
+        }
+        None => println!("no cached beneficiaries, run `beneficiaries list` first"),
+    }
+    Ok(())
+}
+
+// registers the beneficiary and returns the updated list
+fn add(session: &mut BcaSession, bank: &str, account: &str) -> Result<Vec<RegisteredBeneficiary>> {
+    let challenge = session.prepare_add_beneficiary(bank, account)?;
+    println!("{}", challenge);
+    if !confirm_beneficiary(session, challenge.entry().beneficiary().name())? {
+        return session.registered_beneficiaries();
+    }
+    let response = read_keybca_response()?;
+    let added = session.confirm_add_beneficiary(&response)?;
+    println!("added {}", added);
+    session.registered_beneficiaries()
+}
+
+fn print_entries(entries: &[RegisteredBeneficiary]) {
+    for entry in entries {
+        println!("{}", entry);
+    }
+}
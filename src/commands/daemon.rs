@@ -0,0 +1,145 @@
+use crate::commands::is_fatal;
+use anyhow::{anyhow, Result};
+use bca_rust::{
+    Backoff, BcaSession, DateRange, DeliveryQueue, ParseError, Watcher, WebhookSender,
+    MAX_STATEMENT_DAYS,
+};
+use chrono::{Duration, Local, Utc};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+use structopt::StructOpt;
+
+// the daemon's seen ids, apart from those of the watch subcommand
+const DAEMON_FILE: &str = "daemon.json";
+// how often due deliveries are retried between polls
+const TICK: std::time::Duration = std::time::Duration::from_secs(5);
+// how soon a sleeping daemon notices a stop signal
+const STOP_CHECK: std::time::Duration = std::time::Duration::from_millis(200);
+// klikbca blocks accounts polled too often
+const MIN_INTERVAL: u64 = 60;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct DaemonOpt {
+    /// url every new transaction is posted to, may be given more than once
+    #[structopt(long = "webhook", required = true)]
+    webhooks: Vec<String>,
+    /// key of the X-Bca-Signature HMAC, shared with the receivers
+    #[structopt(long = "secret", env = "BCA_WEBHOOK_SECRET", hide_env_values = true)]
+    secret: String,
+    /// seconds between polls, at least 60
+    #[structopt(long = "interval", default_value = "300")]
+    interval: u64,
+    /// days every poll fetches, klikbca books some transactions back dated
+    #[structopt(long = "days", default_value = "3")]
+    days: i64,
+    /// attempts before a delivery is given up
+    #[structopt(long = "max-attempts", default_value = "30")]
+    max_attempts: u32,
+    /// poll and deliver once and exit, for cron
+    #[structopt(long = "once")]
+    once: bool,
+}
+
+// runs the daemon subcommand: the session is kept between polls and every new transaction is
+// queued for each webhook in the data dir, so deliveries survive a restart. Failed polls and
+// deliveries are reported on stderr and retried, a refused login stops the daemon. SIGINT and
+// SIGTERM stop it between polls and deliveries, logged out so the next login isn't refused as
+// already logged in.
+pub(crate) fn run(session: &mut BcaSession, data_dir: &Path, opt: DaemonOpt) -> Result<()> {
+    if opt.days < 1 || opt.days > MAX_STATEMENT_DAYS {
+        return Err(anyhow!(
+            "--days must be between 1 and {}",
+            MAX_STATEMENT_DAYS
+        ));
+    }
+    if opt.interval < MIN_INTERVAL {
+        return Err(anyhow!(
+            "--interval must be at least {} seconds",
+            MIN_INTERVAL
+        ));
+    }
+    if opt.max_attempts < 1 {
+        return Err(anyhow!("--max-attempts must be at least 1"));
+    }
+    let sender = WebhookSender::new(
+        opt.secret.as_bytes(),
+        Backoff {
+            max_attempts: opt.max_attempts,
+            ..Backoff::default()
+        },
+    )?;
+    let mut queue = DeliveryQueue::open(data_dir)?;
+    let mut watcher = Watcher::open_named(data_dir, DAEMON_FILE)?;
+    let interval = std::time::Duration::from_secs(opt.interval);
+    let mut next_poll = Instant::now();
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register(signal, Arc::clone(&stop))?;
+    }
+    let stopped = || stop.load(Ordering::Relaxed);
+    loop {
+        if stopped() {
+            eprintln!("stopping");
+            return session.logout();
+        }
+        if Instant::now() >= next_poll {
+            next_poll = Instant::now() + interval;
+            match poll(session, &mut watcher, &mut queue, &opt) {
+                Ok(()) => {}
+                Err(e) if is_fatal(&e) => return Err(e),
+                Err(e) if opt.once => {
+                    let _ = session.logout();
+                    return Err(e);
+                }
+                Err(e) => eprintln!("poll failed: {}", e),
+            }
+        }
+        if stopped() {
+            continue;
+        }
+        let run = sender.deliver_due(&mut queue, Utc::now())?;
+        if run.delivered + run.retrying + run.given_up > 0 {
+            eprintln!(
+                "webhooks: {} delivered, {} to retry, {} given up",
+                run.delivered, run.retrying, run.given_up
+            );
+        }
+        if opt.once {
+            return session.logout();
+        }
+        let until = std::cmp::min(Instant::now() + TICK, next_poll);
+        while !stopped() && Instant::now() < until {
+            thread::sleep(STOP_CHECK);
+        }
+    }
+}
+
+// fetches the last days and queues what is new. The session stays open, an expired one is
+// logged into again by the session itself.
+fn poll(
+    session: &mut BcaSession,
+    watcher: &mut Watcher,
+    queue: &mut DeliveryQueue,
+    opt: &DaemonOpt,
+) -> Result<()> {
+    let today = Local::now().naive_local().date();
+    let range = DateRange::new(today - Duration::days(opt.days - 1), today);
+    match session.statement(range) {
+        Ok(statement) => {
+            let now = Utc::now();
+            for tx in watcher.observe(&statement)? {
+                let queued = queue.enqueue(&tx, &opt.webhooks, now)?;
+                eprintln!("{} {} queued for {} webhooks", tx.date, tx.id, queued);
+            }
+        }
+        Err(e) if ParseError::is_empty_statement(&e) => watcher.mark_started(),
+        Err(e) => return Err(e),
+    }
+    // saved after queueing: the queue ignores a transaction it already has, so a crash in
+    // between can't deliver it twice
+    watcher.save()
+}
@@ -0,0 +1,192 @@
+use crate::commands::parse_date;
+use anyhow::{anyhow, Result};
+use bca_rust::{
+    export, AccountMutasi, BcaSession, CsvOptions, DateRange, DecimalStyle, JournalFlavor,
+    JournalOptions, JournalState, MappingRule, StatementLimits,
+};
+use chrono::{Local, NaiveDate};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) enum ExportOpt {
+    /// statement as csv for spreadsheets
+    Csv {
+        #[structopt(flatten)]
+        period: ExportPeriod,
+        /// field delimiter, a single character or "tab"
+        #[structopt(long = "delimiter", default_value = ",", parse(try_from_str = parse_delimiter))]
+        delimiter: u8,
+        /// decimal separator of amounts: point or comma
+        #[structopt(long = "decimal", default_value = "point")]
+        decimal: DecimalStyle,
+    },
+    /// statement as OFX for GnuCash and other personal finance software
+    Ofx {
+        #[structopt(flatten)]
+        period: ExportPeriod,
+    },
+    /// statement as QIF, for software without OFX import
+    Qif {
+        #[structopt(flatten)]
+        period: ExportPeriod,
+    },
+    /// statement as plain text accounting entries with balance assertions. An existing output
+    /// file only gets the entries it doesn't have yet.
+    Journal {
+        #[structopt(flatten)]
+        period: ExportPeriod,
+        /// ledger, hledger or beancount
+        #[structopt(long = "format", default_value = "ledger")]
+        flavor: JournalFlavor,
+        /// name of the bank account in the journal
+        #[structopt(long = "account", default_value = "Assets:Bank:BCA")]
+        account: String,
+        /// counter account of credits no rule matches
+        #[structopt(long = "income", default_value = "Income:Uncategorized")]
+        income: String,
+        /// counter account of debits no rule matches
+        #[structopt(long = "expenses", default_value = "Expenses:Uncategorized")]
+        expenses: String,
+        /// counter account of the opening balance [default: Equity:Opening Balances, or
+        /// Equity:Opening-Balances for beancount]
+        #[structopt(long = "opening")]
+        opening: Option<String>,
+        /// csv file with the columns pattern,account: descriptions matching the pattern are
+        /// booked against the account, the first match wins
+        #[structopt(long = "rules", parse(from_os_str))]
+        rules: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct ExportPeriod {
+    /// first day of the period, dd/mm/yyyy
+    #[structopt(long = "from", parse(try_from_str = parse_date))]
+    from: NaiveDate,
+    /// last day of the period, dd/mm/yyyy [default: today]
+    #[structopt(long = "to", parse(try_from_str = parse_date))]
+    to: Option<NaiveDate>,
+    /// file to write to [default: stdout]
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+fn parse_delimiter(s: &str) -> Result<u8> {
+    match s {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => Err(anyhow!("delimiter must be a single character, got {:?}", s)),
+    }
+}
+
+// runs the export subcommand on a statement fetched like the statement subcommand does
+pub(crate) fn run(session: &mut BcaSession, opt: ExportOpt) -> Result<()> {
+    let period = match &opt {
+        ExportOpt::Csv { period, .. }
+        | ExportOpt::Ofx { period }
+        | ExportOpt::Qif { period }
+        | ExportOpt::Journal { period, .. } => period,
+    };
+    let statement = fetch(session, period)?;
+    match opt {
+        ExportOpt::Csv {
+            period,
+            delimiter,
+            decimal,
+        } => export::csv::write_statement(
+            &statement,
+            &CsvOptions { delimiter, decimal },
+            create(period.output)?,
+        ),
+        ExportOpt::Ofx { period } => {
+            export::ofx::write_statement(&statement, create(period.output)?)
+        }
+        ExportOpt::Qif { period } => {
+            export::qif::write_statement(&statement, create(period.output)?)
+        }
+        ExportOpt::Journal {
+            period,
+            flavor,
+            account,
+            income,
+            expenses,
+            opening,
+            rules,
+        } => {
+            let defaults = JournalOptions::new(flavor);
+            let options = JournalOptions {
+                account,
+                income,
+                expenses,
+                opening: opening.unwrap_or(defaults.opening),
+                rules: match rules {
+                    Some(path) => MappingRule::read(&path)?,
+                    None => vec![],
+                },
+                ..defaults
+            };
+            write_journal(&statement, &options, period.output)
+        }
+    }
+}
+
+// the output file, replacing an existing one, or stdout
+fn create(output: Option<PathBuf>) -> Result<Box<dyn Write>> {
+    Ok(match output {
+        Some(path) => {
+            Box::new(File::create(&path).map_err(|e| anyhow!("{}: {}", path.display(), e))?)
+        }
+        None => Box::new(io::stdout()),
+    })
+}
+
+// appends the entries the journal doesn't have yet, stdout gets all of them
+fn write_journal(
+    statement: &AccountMutasi,
+    options: &JournalOptions,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let path = match output {
+        Some(path) => path,
+        None => {
+            export::journal::write_statement(
+                statement,
+                options,
+                &JournalState::default(),
+                io::stdout(),
+            )?;
+            return Ok(());
+        }
+    };
+    let journal = match fs::read_to_string(&path) {
+        Ok(journal) => JournalState::read(&journal),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => JournalState::default(),
+        Err(e) => return Err(anyhow!("{}: {}", path.display(), e)),
+    };
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    let written = export::journal::write_statement(statement, options, &journal, file)?;
+    eprintln!("{} new entries in {}", written, path.display());
+    Ok(())
+}
+
+fn fetch(session: &mut BcaSession, period: &ExportPeriod) -> Result<AccountMutasi<'static>> {
+    let today = Local::now().naive_local().date();
+    let range = DateRange::new(period.from, period.to.unwrap_or(today));
+    session.login()?;
+    let statement = session.statement_range_or_empty(
+        range,
+        &StatementLimits::klikbca(today),
+        |i, n, window| eprintln!("fetching {} ({}/{})", window, i, n),
+    );
+    let logout = session.logout();
+    let statement = statement?;
+    logout?;
+    Ok(statement)
+}
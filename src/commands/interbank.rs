@@ -0,0 +1,79 @@
+use crate::commands::{confirm_beneficiary, read_keybca_response};
+use anyhow::Result;
+use bca_rust::{
+    Amount, BcaSession, InterbankTransferRequest, ReceiptStore, StoredReceipt, TransferService,
+};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct InterbankOpt {
+    /// list the destination banks and their codes instead of transferring
+    #[structopt(long = "banks")]
+    banks: bool,
+    /// only look up the name of the account holder
+    #[structopt(long = "inquiry")]
+    inquiry: bool,
+    /// 3 digit code of the destination bank, see --banks
+    #[structopt(long = "bank", required_unless = "banks")]
+    bank: Option<String>,
+    /// destination account number
+    #[structopt(long = "to", required_unless = "banks")]
+    to: Option<String>,
+    /// amount in IDR, e.g. 150000 or 150,000.00
+    #[structopt(long = "amount", required_unless_one = &["banks", "inquiry"])]
+    amount: Option<Amount>,
+    /// clearing service: online or bifast
+    #[structopt(long = "service", default_value = "bifast")]
+    service: TransferService,
+    /// note (berita) shown to the beneficiary
+    #[structopt(long = "note", default_value = "")]
+    note: String,
+}
+
+// runs the interbank subcommand, logging out whatever the outcome
+pub(crate) fn run(session: &mut BcaSession, store: &ReceiptStore, opt: InterbankOpt) -> Result<()> {
+    session.login()?;
+    let result = if opt.banks {
+        list_banks(session)
+    } else if opt.inquiry {
+        inquiry(session, opt)
+    } else {
+        transfer(session, store, opt)
+    };
+    let logout = session.logout();
+    result.and(logout)
+}
+
+fn list_banks(session: &mut BcaSession) -> Result<()> {
+    for bank in session.banks()? {
+        println!("{}", bank);
+    }
+    Ok(())
+}
+
+fn inquiry(session: &mut BcaSession, opt: InterbankOpt) -> Result<()> {
+    let found =
+        session.inquire_account(&opt.bank.unwrap_or_default(), &opt.to.unwrap_or_default())?;
+    println!("{}", found);
+    Ok(())
+}
+
+fn transfer(session: &mut BcaSession, store: &ReceiptStore, opt: InterbankOpt) -> Result<()> {
+    let request = InterbankTransferRequest {
+        bank_code: opt.bank.unwrap_or_default(),
+        to: opt.to.unwrap_or_default(),
+        amount: opt.amount.unwrap_or_default(),
+        service: opt.service,
+        note: opt.note,
+    };
+    let challenge = session.prepare_interbank_transfer(&request)?;
+    println!("{}", challenge);
+    if !confirm_beneficiary(session, challenge.beneficiary().name())? {
+        return Ok(());
+    }
+    let response = read_keybca_response()?;
+    let receipt = session.confirm_interbank_transfer(&response)?;
+    println!("{}", receipt);
+    store.append(&StoredReceipt::from(&receipt))?;
+    Ok(())
+}
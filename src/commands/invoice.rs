@@ -0,0 +1,201 @@
+use crate::commands::{parse_date, OutputFormat};
+use anyhow::{anyhow, Result};
+use bca_rust::{
+    export, Amount, BcaSession, DateRange, DecimalStyle, Invoice, InvoiceBook, MatchOutcome,
+    PaymentMatch, StatementLimits,
+};
+use chrono::{Duration, Local, NaiveDate};
+use std::io;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) enum InvoiceOpt {
+    /// expect a payment, prints the unique amount to ask the customer for
+    Add {
+        /// invoice number, customers may write it in the transfer note
+        reference: String,
+        /// billed amount in IDR, e.g. 150000 or 150,000.00
+        amount: Amount,
+        /// payer name expected in the transfer note
+        #[structopt(long = "name")]
+        name: Option<String>,
+        /// days the customer has to pay, the unique amount is reserved that long
+        #[structopt(long = "days", default_value = "7")]
+        days: i64,
+    },
+    /// list the open invoices
+    List {
+        /// paid and cancelled invoices too
+        #[structopt(long = "all")]
+        all: bool,
+        /// json, table or csv
+        #[structopt(long = "format", default_value = "table")]
+        format: OutputFormat,
+    },
+    /// match the credit transfers of a statement to the open invoices
+    Match {
+        /// first day of the period, dd/mm/yyyy [default: 7 days ago]
+        #[structopt(long = "from", parse(try_from_str = parse_date))]
+        from: Option<NaiveDate>,
+        /// last day of the period, dd/mm/yyyy [default: today]
+        #[structopt(long = "to", parse(try_from_str = parse_date))]
+        to: Option<NaiveDate>,
+    },
+    /// transfers that fit several invoices and partly paid invoices
+    Flagged,
+    /// take an ambiguous transfer for the invoice it was meant for
+    Resolve {
+        /// transaction id shown by match and flagged
+        tx_id: String,
+        reference: String,
+    },
+    /// cancel an open invoice, freeing its unique amount
+    Cancel { reference: String },
+}
+
+// runs the invoice subcommand, only match logs in
+pub(crate) fn run(session: &mut BcaSession, book: &mut InvoiceBook, opt: InvoiceOpt) -> Result<()> {
+    let today = Local::now().naive_local().date();
+    match opt {
+        InvoiceOpt::Add {
+            reference,
+            amount,
+            name,
+            days,
+        } => {
+            if days < 1 {
+                return Err(anyhow!("--days must be at least 1"));
+            }
+            let invoice = book.issue(
+                &reference,
+                amount,
+                name.as_deref(),
+                today,
+                today + Duration::days(days),
+            )?;
+            println!(
+                "{}: ask for {} IDR by {}",
+                invoice.reference,
+                invoice.unique_amount,
+                invoice.expires.format("%d/%m/%Y")
+            );
+            Ok(())
+        }
+        InvoiceOpt::List { all, format } => {
+            let invoices = book.invoices(all)?;
+            match format {
+                OutputFormat::Table => {
+                    invoices.iter().for_each(print_invoice);
+                    Ok(())
+                }
+                OutputFormat::Json => export::json::write_invoices(&invoices, io::stdout()),
+                OutputFormat::Csv => write_csv(&invoices),
+            }
+        }
+        InvoiceOpt::Match { from, to } => {
+            let to = to.unwrap_or(today);
+            let from = from.unwrap_or(to - Duration::days(6));
+            let range = DateRange::new(from, to);
+            session.login()?;
+            let result = session
+                .statement_range_or_empty(
+                    range,
+                    &StatementLimits::klikbca(today),
+                    |i, n, window| eprintln!("fetching {} ({}/{})", window, i, n),
+                )
+                .and_then(|statement| book.match_statement(&statement));
+            let logout = session.logout();
+            let matches = result?;
+            logout?;
+            matches.iter().for_each(print_payment);
+            eprintln!("{} transfers matched", matches.len());
+            Ok(())
+        }
+        InvoiceOpt::Flagged => {
+            book.ambiguous()?.iter().for_each(print_payment);
+            book.invoices(false)?
+                .iter()
+                .filter(|invoice| !invoice.received.is_zero())
+                .for_each(print_invoice);
+            Ok(())
+        }
+        InvoiceOpt::Resolve { tx_id, reference } => {
+            print_payment(&book.resolve(&tx_id, &reference)?);
+            Ok(())
+        }
+        InvoiceOpt::Cancel { reference } => {
+            let invoice = book.cancel(&reference)?;
+            println!("{}: cancelled", invoice.reference);
+            Ok(())
+        }
+    }
+}
+
+fn print_invoice(invoice: &Invoice) {
+    println!(
+        "{:<16} {:>16} {:>16} {:<9} {} - {} {}",
+        invoice.reference,
+        invoice.unique_amount.to_string(),
+        invoice.received.to_string(),
+        invoice.status.code(),
+        invoice.issued.format("%d/%m/%Y"),
+        invoice.expires.format("%d/%m/%Y"),
+        invoice.name.as_deref().unwrap_or_default()
+    );
+}
+
+fn print_payment(payment: &PaymentMatch) {
+    let outcome = match &payment.outcome {
+        MatchOutcome::Paid { reference } => format!("paid {}", reference),
+        MatchOutcome::Partial {
+            reference,
+            outstanding,
+        } => format!("partial {}, {} outstanding", reference, outstanding),
+        MatchOutcome::Ambiguous { candidates } => format!(
+            "ambiguous {}: invoice resolve {} <reference>",
+            candidates.join(", "),
+            payment.tx_id
+        ),
+    };
+    println!(
+        "{} {:>16} {}  ({})",
+        payment.date.format("%d/%m/%Y"),
+        payment.amount.to_string(),
+        outcome,
+        payment.description
+    );
+}
+
+fn write_csv(invoices: &[Invoice]) -> Result<()> {
+    let amount = |a| DecimalStyle::Point.format(a);
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    writer.write_record([
+        "reference",
+        "amount",
+        "unique_amount",
+        "name",
+        "issued",
+        "expires",
+        "status",
+        "received",
+        "paid_on",
+    ])?;
+    for invoice in invoices {
+        writer.write_record([
+            invoice.reference.clone(),
+            amount(invoice.amount),
+            amount(invoice.unique_amount),
+            invoice.name.clone().unwrap_or_default(),
+            invoice.issued.format("%Y-%m-%d").to_string(),
+            invoice.expires.format("%Y-%m-%d").to_string(),
+            invoice.status.code().to_string(),
+            amount(invoice.received),
+            invoice
+                .paid_on
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
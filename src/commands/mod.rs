@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Result};
+use bca_rust::{check_typed_name, BcaSession, LoginError, PolicyError, SessionError};
+use chrono::NaiveDate;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+pub(crate) mod balance;
+pub(crate) mod beneficiaries;
+pub(crate) mod daemon;
+pub(crate) mod export;
+pub(crate) mod interbank;
+pub(crate) mod invoice;
+pub(crate) mod pay;
+pub(crate) mod statement;
+pub(crate) mod sync;
+pub(crate) mod transfer;
+pub(crate) mod transfer_batch;
+pub(crate) mod watch;
+
+// how the non interactive subcommands print their result
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    // for people
+    Table,
+    // one versioned document, for scripts and jq
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(anyhow!("unknown format {:?}, use json, table or csv", s)),
+        }
+    }
+}
+
+// dates on the command line are written like klikbca shows them
+pub(crate) fn parse_date(s: &str) -> Result<NaiveDate> {
+    Ok(NaiveDate::parse_from_str(s, "%d/%m/%Y")?)
+}
+
+// retrying a refused login would lock the klikbca account
+pub(crate) fn is_fatal(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<LoginError>().is_some()
+        || e.downcast_ref::<SessionError>() == Some(&SessionError::Locked)
+}
+
+// one trimmed line from stdin
+fn prompt(question: &str) -> Result<String> {
+    print!("{}", question);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+// asks for the response shown by the KeyBCA token, an empty answer cancels the transaction
+pub(crate) fn read_keybca_response() -> Result<String> {
+    let response = prompt("KeyBCA response: ")?;
+    if response.is_empty() {
+        return Err(anyhow!("no KeyBCA response given, transaction cancelled"));
+    }
+    Ok(response)
+}
+
+// yes/no question on stdin, anything but y or yes is a no
+pub(crate) fn confirm(question: &str) -> Result<bool> {
+    let answer = prompt(&format!("{} [y/N] ", question))?.to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+// stops a dry run after the prepared transaction was shown, otherwise has the account holder's
+// name typed out before the KeyBCA response is asked for
+pub(crate) fn confirm_beneficiary(session: &BcaSession, name: &str) -> Result<bool> {
+    if session.policy().dry_run {
+        println!("{}", PolicyError::DryRun);
+        return Ok(false);
+    }
+    let typed = prompt("Type the beneficiary name to confirm: ")?;
+    check_typed_name(name, &typed)?;
+    Ok(true)
+}
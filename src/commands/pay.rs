@@ -0,0 +1,51 @@
+use crate::commands::{confirm_beneficiary, read_keybca_response};
+use anyhow::Result;
+use bca_rust::{Amount, BcaSession, PaymentRequest, ReceiptStore, StoredReceipt};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct PayOpt {
+    /// BCA Virtual Account number from the invoice
+    #[structopt(long = "va")]
+    va_number: String,
+    /// only show the bill behind the virtual account
+    #[structopt(long = "inquiry")]
+    inquiry: bool,
+    /// amount in IDR, only for open amount virtual accounts
+    #[structopt(long = "amount")]
+    amount: Option<Amount>,
+}
+
+// runs the pay subcommand, logging out whatever the outcome
+pub(crate) fn run(session: &mut BcaSession, store: &ReceiptStore, opt: PayOpt) -> Result<()> {
+    session.login()?;
+    let result = if opt.inquiry {
+        inquiry(session, opt)
+    } else {
+        pay(session, store, opt)
+    };
+    let logout = session.logout();
+    result.and(logout)
+}
+
+fn inquiry(session: &mut BcaSession, opt: PayOpt) -> Result<()> {
+    println!("{}", session.inquire_va(&opt.va_number)?);
+    Ok(())
+}
+
+fn pay(session: &mut BcaSession, store: &ReceiptStore, opt: PayOpt) -> Result<()> {
+    let request = PaymentRequest {
+        va_number: opt.va_number,
+        amount: opt.amount,
+    };
+    let challenge = session.prepare_payment(&request)?;
+    println!("{}", challenge);
+    if !confirm_beneficiary(session, challenge.name())? {
+        return Ok(());
+    }
+    let response = read_keybca_response()?;
+    let receipt = session.confirm_payment(&response)?;
+    println!("{}", receipt);
+    store.append(&StoredReceipt::from(&receipt))?;
+    Ok(())
+}
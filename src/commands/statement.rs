@@ -0,0 +1,70 @@
+use crate::commands::{parse_date, OutputFormat};
+use anyhow::Result;
+use bca_rust::{export, AccountMutasi, BcaSession, CsvOptions, DateRange, Ledger, StatementLimits};
+use chrono::{Local, NaiveDate};
+use std::io;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct StatementOpt {
+    /// first day of the period, dd/mm/yyyy
+    #[structopt(long = "from", parse(try_from_str = parse_date))]
+    from: NaiveDate,
+    /// last day of the period, dd/mm/yyyy [default: today]
+    #[structopt(long = "to", parse(try_from_str = parse_date))]
+    to: Option<NaiveDate>,
+    /// json, table or csv
+    #[structopt(long = "format", default_value = "table")]
+    format: OutputFormat,
+}
+
+// runs the statement subcommand, periods over 31 days are fetched in several requests. The
+// transactions are added to the ledger.
+pub(crate) fn run(session: &mut BcaSession, ledger: &mut Ledger, opt: StatementOpt) -> Result<()> {
+    let today = Local::now().naive_local().date();
+    let range = DateRange::new(opt.from, opt.to.unwrap_or(today));
+    session.login()?;
+    let result = session
+        .statement_range_or_empty(range, &StatementLimits::klikbca(today), |i, n, window| {
+            eprintln!("fetching {} ({}/{})", window, i, n)
+        })
+        .and_then(|statement| {
+            match opt.format {
+                OutputFormat::Table => print_statement(&statement),
+                OutputFormat::Json => export::json::write_statement(&statement, io::stdout())?,
+                OutputFormat::Csv => {
+                    export::csv::write_statement(&statement, &CsvOptions::default(), io::stdout())?
+                }
+            }
+            let added = ledger.insert_statement(&statement)?;
+            eprintln!("{} new transactions in the ledger", added.inserted);
+            Ok(())
+        });
+    let logout = session.logout();
+    result.and(logout)
+}
+
+fn print_statement(statement: &AccountMutasi) {
+    let info = statement.info();
+    println!("{} {}", info.account_number(), info.owner_name());
+    println!("Period: {} ({})", info.period(), info.currency());
+    for tx in statement.transactions() {
+        let date = if tx.is_pending() {
+            "PEND".to_string()
+        } else {
+            tx.date().format("%d/%m/%Y").to_string()
+        };
+        println!(
+            "{:<10} {:>18} {} {}",
+            date,
+            tx.amount().to_string(),
+            tx.direction(),
+            tx.description()
+        );
+    }
+    let summary = statement.summary();
+    println!("Starting balance: {}", summary.balance_begin());
+    println!("Credits:          {}", summary.total_credits());
+    println!("Debits:           {}", summary.total_debits());
+    println!("Ending balance:   {}", summary.balance_end());
+}
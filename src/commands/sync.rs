@@ -0,0 +1,85 @@
+use crate::commands::OutputFormat;
+use anyhow::Result;
+use bca_rust::{export, sync, BcaSession, DecimalStyle, Ledger, StatementLimits, SyncReport};
+use chrono::Local;
+use std::io;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct SyncOpt {
+    /// only print errors, for cron
+    #[structopt(short = "q", long = "quiet")]
+    quiet: bool,
+    /// json, table or csv
+    #[structopt(long = "format", default_value = "table")]
+    format: OutputFormat,
+}
+
+// runs the sync subcommand. Any failure is returned, so the process exits non zero and cron
+// reports it.
+pub(crate) fn run(session: &mut BcaSession, ledger: &mut Ledger, opt: SyncOpt) -> Result<()> {
+    let today = Local::now().naive_local().date();
+    let quiet = opt.quiet;
+    session.login()?;
+    let result = sync(
+        session,
+        ledger,
+        &StatementLimits::klikbca(today),
+        |i, n, window| {
+            if !quiet {
+                eprintln!("fetching {} ({}/{})", window, i, n)
+            }
+        },
+    );
+    let logout = session.logout();
+    let report = result?;
+    logout?;
+    if let Some(gap) = report.gap {
+        eprintln!(
+            "warning: {} is no longer kept by klikbca, the ledger has no transactions of it",
+            gap
+        );
+    }
+    if quiet {
+        return Ok(());
+    }
+    match opt.format {
+        OutputFormat::Table => println!(
+            "{}: {} new transactions ({} already in the ledger, {} pending), balance {}",
+            report.account,
+            report.added.inserted,
+            report.added.duplicates,
+            report.added.pending,
+            report.balance
+        ),
+        OutputFormat::Json => export::json::write_sync(&report, io::stdout())?,
+        OutputFormat::Csv => write_csv(&report)?,
+    }
+    Ok(())
+}
+
+fn write_csv(report: &SyncReport) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    writer.write_record([
+        "account",
+        "start",
+        "end",
+        "inserted",
+        "duplicates",
+        "pending",
+        "currency",
+        "balance",
+    ])?;
+    writer.write_record([
+        report.account.clone(),
+        report.range.start.format("%Y-%m-%d").to_string(),
+        report.range.end.format("%Y-%m-%d").to_string(),
+        report.added.inserted.to_string(),
+        report.added.duplicates.to_string(),
+        report.added.pending.to_string(),
+        report.balance.currency().to_string(),
+        DecimalStyle::Point.format(report.balance.amount()),
+    ])?;
+    writer.flush()?;
+    Ok(())
+}
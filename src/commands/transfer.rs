@@ -0,0 +1,57 @@
+use crate::commands::{confirm_beneficiary, read_keybca_response};
+use anyhow::Result;
+use bca_rust::{Amount, BcaSession, ReceiptStore, StoredReceipt, TransferRequest};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct TransferOpt {
+    /// list the registered beneficiaries instead of transferring
+    #[structopt(long = "list")]
+    list: bool,
+    /// destination BCA account number, has to be a registered beneficiary
+    #[structopt(long = "to", required_unless = "list")]
+    to: Option<String>,
+    /// amount in IDR, e.g. 150000 or 150,000.00
+    #[structopt(long = "amount", required_unless = "list")]
+    amount: Option<Amount>,
+    /// note (berita) shown to the beneficiary
+    #[structopt(long = "note", default_value = "")]
+    note: String,
+}
+
+// runs the transfer subcommand, logging out whatever the outcome
+pub(crate) fn run(session: &mut BcaSession, store: &ReceiptStore, opt: TransferOpt) -> Result<()> {
+    session.login()?;
+    let result = if opt.list {
+        list(session)
+    } else {
+        transfer(session, store, opt)
+    };
+    let logout = session.logout();
+    result.and(logout)
+}
+
+fn list(session: &mut BcaSession) -> Result<()> {
+    for beneficiary in session.beneficiaries()? {
+        println!("{}", beneficiary);
+    }
+    Ok(())
+}
+
+fn transfer(session: &mut BcaSession, store: &ReceiptStore, opt: TransferOpt) -> Result<()> {
+    let request = TransferRequest::new(
+        opt.to.unwrap_or_default(),
+        opt.amount.unwrap_or_default(),
+        opt.note,
+    );
+    let challenge = session.prepare_transfer(&request)?;
+    println!("{}", challenge);
+    if !confirm_beneficiary(session, challenge.beneficiary().name())? {
+        return Ok(());
+    }
+    let response = read_keybca_response()?;
+    let receipt = session.confirm_transfer(&response)?;
+    println!("{}", receipt);
+    store.append(&StoredReceipt::from(&receipt))?;
+    Ok(())
+}
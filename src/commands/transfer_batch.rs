@@ -0,0 +1,146 @@
+use crate::commands::{confirm, read_keybca_response};
+use anyhow::{anyhow, Result};
+use bca_rust::{
+    Amount, BatchReport, BcaSession, Beneficiary, EntryStatus, ReceiptStore, TransferBatch,
+};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct TransferBatchOpt {
+    /// csv file with the columns beneficiary,amount,note (one transfer per row)
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+    /// per row results, a run resumes from it [default: <file>.report.csv]
+    #[structopt(long = "report", parse(from_os_str))]
+    report: Option<PathBuf>,
+}
+
+// runs the transfer-batch subcommand, logging out whatever the outcome
+pub(crate) fn run(
+    session: &mut BcaSession,
+    store: &ReceiptStore,
+    opt: TransferBatchOpt,
+) -> Result<()> {
+    let batch = TransferBatch::read(&opt.file)?;
+    let file = opt.file;
+    let report_path = opt
+        .report
+        .unwrap_or_else(|| BatchReport::default_path(&file));
+    let mut report = BatchReport::open(&report_path, &batch)?;
+    session.login()?;
+    let result = run_batch(session, store, &batch, &mut report);
+    let logout = session.logout();
+    result.and(logout)
+}
+
+fn run_batch(
+    session: &mut BcaSession,
+    store: &ReceiptStore,
+    batch: &TransferBatch,
+    report: &mut BatchReport,
+) -> Result<()> {
+    let beneficiaries = session.beneficiaries()?;
+    let rows = report.to_run();
+    // rows transferred by an earlier run don't have to be valid anymore
+    let settled = report
+        .results()
+        .iter()
+        .filter(|r| matches!(r.status, EntryStatus::Done | EntryStatus::Submitted))
+        .map(|r| r.entry.line)
+        .collect::<Vec<usize>>();
+    let issues = batch
+        .validate(&beneficiaries)
+        .into_iter()
+        .filter(|issue| !settled.contains(&issue.line))
+        .collect::<Vec<_>>();
+    if !issues.is_empty() {
+        for issue in &issues {
+            eprintln!("{}", issue);
+        }
+        return Err(anyhow!(
+            "{} invalid rows in {}, nothing was transferred",
+            issues.len(),
+            batch.path().display()
+        ));
+    }
+
+    print_summary(report, &beneficiaries);
+    if session.policy().dry_run {
+        println!("dry run: {} transfers would be made", rows.len());
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!("nothing left to transfer");
+        return check_submitted(report);
+    }
+    let total = rows
+        .iter()
+        .map(|&i| report.results()[i].entry.amount)
+        .sum::<Amount>();
+    if !confirm(&format!("Transfer {} in {} transfers?", total, rows.len()))? {
+        return Err(anyhow!("batch cancelled, nothing was transferred"));
+    }
+
+    for i in rows {
+        let result = report.execute(i, session, store, |challenge| {
+            println!("{}", challenge);
+            read_keybca_response()
+        })?;
+        println!(
+            "line {}: {} {} {}",
+            result.entry.line, result.status, result.reference, result.message
+        );
+        // the session may be gone, every further transfer would end the same way
+        if result.status == EntryStatus::Submitted {
+            break;
+        }
+    }
+    println!(
+        "{} done, {} failed, {} pending - report in {}",
+        report.count(EntryStatus::Done),
+        report.count(EntryStatus::Failed),
+        report.count(EntryStatus::Pending),
+        report.path().display()
+    );
+    check_submitted(report)?;
+    match report.count(EntryStatus::Failed) + report.count(EntryStatus::Pending) {
+        0 => Ok(()),
+        left => Err(anyhow!(
+            "{} transfers not made, run the batch again to retry them",
+            left
+        )),
+    }
+}
+
+fn print_summary(report: &BatchReport, beneficiaries: &[Beneficiary]) {
+    for r in report.results() {
+        let name = beneficiaries
+            .iter()
+            .find(|b| b.account_number() == r.entry.to)
+            .map(Beneficiary::name)
+            .unwrap_or(&r.name);
+        println!(
+            "{:>4} {:<10} {:<12} {:<30} {:>18} {}",
+            r.entry.line,
+            r.status,
+            r.entry.to,
+            name,
+            r.entry.amount.to_string(),
+            r.entry.note
+        );
+    }
+}
+
+// rows without a known outcome block the batch until they are resolved by hand
+fn check_submitted(report: &BatchReport) -> Result<()> {
+    match report.count(EntryStatus::Submitted) {
+        0 => Ok(()),
+        unknown => Err(anyhow!(
+            "{} transfers were submitted without a known outcome, check the statement and set \
+             their status to done or failed in {}",
+            unknown,
+            report.path().display()
+        )),
+    }
+}
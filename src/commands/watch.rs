@@ -0,0 +1,66 @@
+use crate::commands::is_fatal;
+use anyhow::{anyhow, Result};
+use bca_rust::{export, BcaSession, DateRange, ParseError, Watcher, MAX_STATEMENT_DAYS};
+use chrono::{Duration, Local};
+use std::io;
+use std::path::Path;
+use std::thread;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct WatchOpt {
+    /// seconds between polls, keep it polite
+    #[structopt(long = "interval", default_value = "300")]
+    interval: u64,
+    /// days every poll fetches, klikbca books some transactions back dated
+    #[structopt(long = "days", default_value = "3")]
+    days: i64,
+    /// poll once and exit
+    #[structopt(long = "once")]
+    once: bool,
+}
+
+// runs the watch subcommand: every new transaction is printed to stdout as a json line. A
+// failed poll is reported on stderr and retried at the next interval, unless the login itself
+// was refused.
+pub(crate) fn run(session: &mut BcaSession, data_dir: &Path, opt: WatchOpt) -> Result<()> {
+    if opt.days < 1 || opt.days > MAX_STATEMENT_DAYS {
+        return Err(anyhow!(
+            "--days must be between 1 and {}",
+            MAX_STATEMENT_DAYS
+        ));
+    }
+    let mut watcher = Watcher::open(data_dir)?;
+    loop {
+        match poll(session, &mut watcher, opt.days) {
+            Ok(()) => {}
+            Err(e) if opt.once || is_fatal(&e) => return Err(e),
+            Err(e) => eprintln!("poll failed: {}", e),
+        }
+        if opt.once {
+            return Ok(());
+        }
+        thread::sleep(std::time::Duration::from_secs(opt.interval));
+    }
+}
+
+// one login, statement and logout: no session is left behind between polls
+fn poll(session: &mut BcaSession, watcher: &mut Watcher, days: i64) -> Result<()> {
+    let today = Local::now().naive_local().date();
+    let range = DateRange::new(today - Duration::days(days - 1), today);
+    session.login()?;
+    let statement = session.statement(range);
+    let logout = session.logout();
+    match statement {
+        Ok(statement) => {
+            for tx in watcher.observe(&statement)? {
+                export::json::write_transaction(&tx, io::stdout())?;
+            }
+        }
+        Err(e) if ParseError::is_empty_statement(&e) => watcher.mark_started(),
+        Err(e) => return Err(e),
+    }
+    // saved after printing: a crash in between prints a transaction twice rather than never
+    watcher.save()?;
+    logout
+}
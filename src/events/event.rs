@@ -16,12 +16,11 @@ pub enum Event<I> {
 }
 
 /// A small event handler that wrap termion input and tick events. Each event
-/// type is handled in its own thread and returned to a common `Receiver`
+/// type is handled in its own thread and returned to a common `Receiver`.
+/// The threads are detached, they end once the receiver is dropped.
 pub struct Events {
     rx: mpsc::Receiver<Event<Key>>,
-    input_handle: thread::JoinHandle<()>,
     ignore_exit_key: Arc<AtomicBool>,
-    tick_handle: thread::JoinHandle<()>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -40,42 +39,33 @@ impl Default for Config {
 }
 
 impl Events {
-    pub fn new() -> Events {
-        Events::with_config(Config::default())
-    }
-
     pub fn with_config(config: Config) -> Events {
         let (tx, rx) = mpsc::channel();
         let ignore_exit_key = Arc::new(AtomicBool::new(false));
-        let input_handle = {
+        {
             let tx = tx.clone();
             let ignore_exit_key = ignore_exit_key.clone();
             thread::spawn(move || {
                 let stdin = io::stdin();
-                for evt in stdin.keys() {
-                    if let Ok(key) = evt {
-                        if let Err(err) = tx.send(Event::Input(key)) {
-                            eprintln!("{}", err);
-                            return;
-                        }
-                        if !ignore_exit_key.load(Ordering::Relaxed) && key == config.exit_key {
-                            return;
-                        }
+                for key in stdin.keys().flatten() {
+                    if let Err(err) = tx.send(Event::Input(key)) {
+                        eprintln!("{}", err);
+                        return;
+                    }
+                    if !ignore_exit_key.load(Ordering::Relaxed) && key == config.exit_key {
+                        return;
                     }
                 }
-            })
-        };
-        let tick_handle = {
-            thread::spawn(move || loop {
-                tx.send(Event::Tick).unwrap();
+            });
+        }
+        thread::spawn(move || {
+            while tx.send(Event::Tick).is_ok() {
                 thread::sleep(config.tick_rate);
-            })
-        };
+            }
+        });
         Events {
             rx,
             ignore_exit_key,
-            input_handle,
-            tick_handle,
         }
     }
 
@@ -91,4 +81,3 @@ impl Events {
         self.ignore_exit_key.store(false, Ordering::Relaxed);
     }
 }
-
@@ -0,0 +1,75 @@
+use crate::export::{running_balances, DecimalStyle};
+use crate::money::Amount;
+use crate::resp_parser::mutasi_parser::AccountMutasi;
+use anyhow::Result;
+use std::io::Write;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub decimal: DecimalStyle,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            decimal: DecimalStyle::Point,
+        }
+    }
+}
+
+// writes the statement as csv: a few "key,value" rows describing the account and period,
+// then one row per transaction with the columns
+// date,description,amount,direction,balance,pending. Dates are yyyy-mm-dd, amounts are
+// unsigned with the direction (CR/DB) next to them and balance is the running balance.
+pub fn write_statement<W: Write>(
+    statement: &AccountMutasi,
+    options: &CsvOptions,
+    out: W,
+) -> Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_writer(out);
+    let info = statement.info();
+    let period = info.period_range()?;
+    let summary = statement.summary();
+    let amount = |a: Amount| options.decimal.format(a);
+    writer.write_record(["account", info.account_number()])?;
+    writer.write_record(["name", info.owner_name()])?;
+    writer.write_record([
+        "period".to_string(),
+        period.start.format("%Y-%m-%d").to_string(),
+        period.end.format("%Y-%m-%d").to_string(),
+    ])?;
+    writer.write_record(["currency", info.currency()])?;
+    writer.write_record([
+        "starting_balance".to_string(),
+        amount(summary.balance_begin().amount()),
+    ])?;
+    writer.write_record([
+        "ending_balance".to_string(),
+        amount(summary.balance_end().amount()),
+    ])?;
+    writer.write_record([
+        "date",
+        "description",
+        "amount",
+        "direction",
+        "balance",
+        "pending",
+    ])?;
+    for (tx, balance) in running_balances(statement) {
+        writer.write_record([
+            tx.date().format("%Y-%m-%d").to_string(),
+            tx.description().to_string(),
+            amount(tx.amount()),
+            tx.direction().code().to_string(),
+            amount(balance),
+            tx.is_pending().to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,371 @@
+use crate::export::DecimalStyle;
+use crate::money::Amount;
+use crate::resp_parser::mutasi_parser::{AccountMutasi, AccountTx, Direction};
+use crate::storage::ledger::{booked_transactions, transaction_id};
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+// plain text accounting format of the journal
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum JournalFlavor {
+    Ledger,
+    Hledger,
+    Beancount,
+}
+
+impl FromStr for JournalFlavor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ledger" => Ok(JournalFlavor::Ledger),
+            "hledger" => Ok(JournalFlavor::Hledger),
+            "beancount" => Ok(JournalFlavor::Beancount),
+            _ => Err(anyhow!(
+                "unknown journal format {:?}, use ledger, hledger or beancount",
+                s
+            )),
+        }
+    }
+}
+
+// MappingRule books transactions whose description matches pattern (case insensitive) against
+// account instead of the default income or expenses account
+#[derive(Clone, Debug)]
+pub struct MappingRule {
+    pub pattern: Regex,
+    pub account: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleLine {
+    pattern: String,
+    account: String,
+}
+
+impl MappingRule {
+    pub fn new(pattern: &str, account: &str) -> Result<Self> {
+        let pattern = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| anyhow!("invalid pattern {:?}: {}", pattern, e))?;
+        Ok(MappingRule {
+            pattern,
+            account: account.to_string(),
+        })
+    }
+
+    // rules from a csv file with the columns pattern,account, the first matching rule wins
+    pub fn read(path: &Path) -> Result<Vec<MappingRule>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        reader
+            .deserialize::<RuleLine>()
+            .enumerate()
+            .map(|(i, line)| {
+                let line = line.map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+                MappingRule::new(&line.pattern, &line.account)
+                    .map_err(|e| anyhow!("{}: line {}: {}", path.display(), i + 2, e))
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct JournalOptions {
+    pub flavor: JournalFlavor,
+    // the bank account itself, e.g. Assets:Bank:BCA
+    pub account: String,
+    // counter account of credits no rule matched
+    pub income: String,
+    // counter account of debits no rule matched
+    pub expenses: String,
+    // counter account of the opening balance
+    pub opening: String,
+    pub rules: Vec<MappingRule>,
+}
+
+impl JournalOptions {
+    pub fn new(flavor: JournalFlavor) -> Self {
+        JournalOptions {
+            flavor,
+            account: "Assets:Bank:BCA".to_string(),
+            income: "Income:Uncategorized".to_string(),
+            expenses: "Expenses:Uncategorized".to_string(),
+            // beancount account names can't have spaces
+            opening: match flavor {
+                JournalFlavor::Beancount => "Equity:Opening-Balances",
+                JournalFlavor::Ledger | JournalFlavor::Hledger => "Equity:Opening Balances",
+            }
+            .to_string(),
+            rules: vec![],
+        }
+    }
+
+    // every account the entries post to, for beancount's open directives
+    fn accounts(&self) -> Vec<&str> {
+        let mut accounts = vec![
+            self.account.as_str(),
+            self.opening.as_str(),
+            self.income.as_str(),
+            self.expenses.as_str(),
+        ];
+        for rule in &self.rules {
+            if !accounts.contains(&rule.account.as_str()) {
+                accounts.push(&rule.account);
+            }
+        }
+        accounts
+    }
+
+    fn counter_account(&self, tx: &AccountTx) -> &str {
+        self.rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(tx.description()))
+            .map(|rule| rule.account.as_str())
+            .unwrap_or(match tx.direction() {
+                Direction::Credit => &self.income,
+                Direction::Debit => &self.expenses,
+            })
+    }
+}
+
+// JournalState is what a journal already holds: the ids of its entries, the day each account
+// was opened at and its last day, read back from the journal itself
+#[derive(Clone, Debug, Default)]
+pub struct JournalState {
+    ids: HashSet<String>,
+    // end of day balance the opening entry assigned
+    openings: HashMap<String, NaiveDate>,
+    last: Option<NaiveDate>,
+}
+
+impl JournalState {
+    pub fn read(journal: &str) -> Self {
+        let id =
+            Regex::new(r#"(?m)^\s*;?\s*id:\s*"?([0-9a-z-]+)"?\s*$"#).expect("invalid id regex");
+        let opening = Regex::new(r"^opening-(.+)-(\d{8})$").expect("invalid opening regex");
+        let date = Regex::new(r"(?m)^(\d{4}-\d{2}-\d{2})\s").expect("invalid date regex");
+        // beancount accounts opened by hand count as opened too
+        let open =
+            Regex::new(r"(?m)^\d{4}-\d{2}-\d{2}\s+open\s+(\S+)").expect("invalid open regex");
+        let ids: HashSet<String> = id
+            .captures_iter(journal)
+            .map(|c| c[1].to_string())
+            .chain(open.captures_iter(journal).map(|c| open_id(&c[1])))
+            .collect();
+        let openings = ids
+            .iter()
+            .filter_map(|id| opening.captures(id))
+            .filter_map(|c| {
+                let day = NaiveDate::parse_from_str(&c[2], "%Y%m%d").ok()?;
+                Some((c[1].to_string(), day))
+            })
+            .collect();
+        let last = date
+            .captures_iter(journal)
+            .filter_map(|c| NaiveDate::parse_from_str(&c[1], "%Y-%m-%d").ok())
+            .max();
+        JournalState {
+            ids,
+            openings,
+            last,
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+}
+
+// writes the booked transactions of the statement as journal entries. A journal without the
+// account gets an opening entry setting its starting balance first, transactions up to the
+// opening day are part of that balance and left out. Beancount needs its accounts opened, they
+// are opened on the opening day unless the journal has them. The ending balance is asserted, unless
+// ledger, which checks assertions in file order, has entries after it already. Entries the
+// journal has are skipped, so exporting overlapping periods into the same journal adds every
+// transaction once. Returns the number of entries written.
+pub fn write_statement<W: Write>(
+    statement: &AccountMutasi,
+    options: &JournalOptions,
+    journal: &JournalState,
+    mut out: W,
+) -> Result<usize> {
+    let info = statement.info();
+    let number = info.account_number();
+    let period = info.period_range()?;
+    let summary = statement.summary();
+    let currency = info.currency();
+    let mut written = 0;
+    let mut entry = |id: String, text: &dyn Fn(&str) -> String| -> Result<()> {
+        if !journal.contains(&id) {
+            write!(out, "{}", text(&id))?;
+            written += 1;
+        }
+        Ok(())
+    };
+    // the balance at the end of the day before the period is its starting balance
+    let opened = journal
+        .openings
+        .get(number)
+        .copied()
+        .unwrap_or(period.start - Duration::days(1));
+    if options.flavor == JournalFlavor::Beancount {
+        for account in options.accounts() {
+            entry(open_id(account), &|id| {
+                format!(
+                    "{} open {}\n  id: \"{}\"\n\n",
+                    opened.format("%Y-%m-%d"),
+                    account,
+                    id
+                )
+            })?;
+        }
+    }
+    if !journal.openings.contains_key(number) {
+        let begin = summary.balance_begin().amount();
+        entry(opening_id(number, opened), &|id| {
+            opening_entry(options, id, opened, begin, currency)
+        })?;
+    }
+    for (tx, position) in booked_transactions(statement) {
+        if tx.date() <= opened {
+            continue;
+        }
+        entry(transaction_id(number, tx, position), &|id| {
+            transaction_entry(options, id, tx, currency)
+        })?;
+    }
+    // klikbca's ending balance includes pending transactions, which aren't in the journal yet
+    let pending = statement.transactions().iter().any(|tx| tx.is_pending());
+    let in_order = options.flavor != JournalFlavor::Ledger
+        || journal.last.is_none_or(|last| last <= period.end);
+    if !pending && in_order && period.end > opened {
+        let end = summary.balance_end().amount();
+        entry(balance_id(number, period.end), &|id| {
+            balance_entry(options, id, period.end, end, currency)
+        })?;
+    }
+    out.flush()?;
+    Ok(written)
+}
+
+// the account name in id characters, e.g. open-assets-bank-bca
+fn open_id(account: &str) -> String {
+    let name: String = account
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("open-{}", name)
+}
+
+fn opening_id(account_number: &str, day: NaiveDate) -> String {
+    format!("opening-{}-{}", account_number, day.format("%Y%m%d"))
+}
+
+// sets the balance at the end of day, against the opening account
+fn opening_entry(
+    options: &JournalOptions,
+    id: &str,
+    day: NaiveDate,
+    balance: Amount,
+    currency: &str,
+) -> String {
+    let amount = DecimalStyle::Point.format(balance);
+    match options.flavor {
+        // a balance assignment, the posting gets whatever amount reaches the balance
+        JournalFlavor::Ledger | JournalFlavor::Hledger => format!(
+            "{} * Opening balance per klikbca\n    ; id: {}\n    {}  = {} {}\n    {}\n\n",
+            day.format("%Y-%m-%d"),
+            id,
+            options.account,
+            amount,
+            currency,
+            options.opening
+        ),
+        // pad fills the account up to the balance checked at the start of the next day
+        JournalFlavor::Beancount => format!(
+            "{} pad {} {}\n  id: \"{}\"\n{} balance {}  {} {}\n\n",
+            day.format("%Y-%m-%d"),
+            options.account,
+            options.opening,
+            id,
+            (day + Duration::days(1)).format("%Y-%m-%d"),
+            options.account,
+            amount,
+            currency
+        ),
+    }
+}
+
+fn balance_id(account_number: &str, day: NaiveDate) -> String {
+    format!("balance-{}-{}", account_number, day.format("%Y%m%d"))
+}
+
+// assertion of the balance at the end of day
+fn balance_entry(
+    options: &JournalOptions,
+    id: &str,
+    day: NaiveDate,
+    balance: Amount,
+    currency: &str,
+) -> String {
+    let amount = DecimalStyle::Point.format(balance);
+    match options.flavor {
+        JournalFlavor::Ledger | JournalFlavor::Hledger => format!(
+            "{} * Balance per klikbca\n    ; id: {}\n    {}  0 {} = {} {}\n\n",
+            day.format("%Y-%m-%d"),
+            id,
+            options.account,
+            currency,
+            amount,
+            currency
+        ),
+        // beancount checks a balance at the start of the day
+        JournalFlavor::Beancount => format!(
+            "{} balance {}  {} {}\n  id: \"{}\"\n\n",
+            (day + Duration::days(1)).format("%Y-%m-%d"),
+            options.account,
+            amount,
+            currency,
+            id
+        ),
+    }
+}
+
+fn transaction_entry(options: &JournalOptions, id: &str, tx: &AccountTx, currency: &str) -> String {
+    let date = tx.date().format("%Y-%m-%d");
+    let amount = |a: Amount| format!("{} {}", DecimalStyle::Point.format(a), currency);
+    let counter = options.counter_account(tx);
+    match options.flavor {
+        JournalFlavor::Ledger | JournalFlavor::Hledger => format!(
+            "{} * {}\n    ; id: {}\n    {}  {}\n    {}  {}\n\n",
+            date,
+            tx.description(),
+            id,
+            options.account,
+            amount(tx.signed_amount()),
+            counter,
+            amount(-tx.signed_amount())
+        ),
+        JournalFlavor::Beancount => format!(
+            "{} * \"{}\"\n  id: \"{}\"\n  {}  {}\n  {}  {}\n\n",
+            date,
+            tx.description().replace('\\', "\\\\").replace('"', "\\\""),
+            id,
+            options.account,
+            amount(tx.signed_amount()),
+            counter,
+            amount(-tx.signed_amount())
+        ),
+    }
+}
@@ -0,0 +1,253 @@
+use crate::accounts::DateRange;
+use crate::export::{running_balances, DecimalStyle};
+use crate::invoices::Invoice;
+use crate::money::{Amount, Money};
+use crate::resp_parser::mutasi_parser::AccountMutasi;
+use crate::resp_parser::saldo_parser::AccountBalance;
+use crate::storage::ledger::{booked_transactions, transaction_id, LedgerTx};
+use crate::sync::SyncReport;
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+
+// version of the json documents, raised on every change that could break a consumer. Fields
+// may be added without raising it.
+pub const JSON_VERSION: u32 = 1;
+
+// amounts are exact decimal strings ("1234567.89"), jq's tonumber turns them into numbers
+#[derive(Serialize)]
+struct JsonMoney {
+    amount: String,
+    currency: String,
+}
+
+impl JsonMoney {
+    fn new(amount: Amount, currency: &str) -> Self {
+        JsonMoney {
+            amount: DecimalStyle::Point.format(amount),
+            currency: currency.to_string(),
+        }
+    }
+}
+
+impl From<&Money> for JsonMoney {
+    fn from(money: &Money) -> Self {
+        JsonMoney::new(money.amount(), money.currency())
+    }
+}
+
+// dates are yyyy-mm-dd
+#[derive(Serialize)]
+struct JsonPeriod {
+    start: String,
+    end: String,
+}
+
+impl From<DateRange> for JsonPeriod {
+    fn from(range: DateRange) -> Self {
+        JsonPeriod {
+            start: range.start.format("%Y-%m-%d").to_string(),
+            end: range.end.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BalanceDocument<'a> {
+    version: u32,
+    kind: &'static str,
+    account: &'a str,
+    balance: JsonMoney,
+}
+
+#[derive(Serialize)]
+struct StatementDocument<'a> {
+    version: u32,
+    kind: &'static str,
+    account: &'a str,
+    owner: &'a str,
+    currency: &'a str,
+    period: JsonPeriod,
+    starting_balance: JsonMoney,
+    total_credits: JsonMoney,
+    total_debits: JsonMoney,
+    ending_balance: JsonMoney,
+    transactions: Vec<JsonTx<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonTx<'a> {
+    // the ledger's transaction id, null while pending
+    id: Option<String>,
+    date: String,
+    pending: bool,
+    description: &'a str,
+    amount: JsonMoney,
+    // CR or DB
+    direction: &'static str,
+    // running balance after the transaction
+    balance: JsonMoney,
+}
+
+#[derive(Serialize)]
+struct SyncDocument<'a> {
+    version: u32,
+    kind: &'static str,
+    account: &'a str,
+    period: JsonPeriod,
+    inserted: usize,
+    duplicates: usize,
+    pending: usize,
+    gap: Option<JsonPeriod>,
+    balance: JsonMoney,
+}
+
+#[derive(Serialize)]
+struct TransactionDocument<'a> {
+    version: u32,
+    kind: &'static str,
+    id: &'a str,
+    account: &'a str,
+    date: String,
+    description: &'a str,
+    amount: JsonMoney,
+    direction: &'static str,
+}
+
+#[derive(Serialize)]
+struct InvoicesDocument<'a> {
+    version: u32,
+    kind: &'static str,
+    invoices: Vec<JsonInvoice<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonInvoice<'a> {
+    reference: &'a str,
+    amount: JsonMoney,
+    // what the customer is asked to transfer
+    unique_amount: JsonMoney,
+    name: Option<&'a str>,
+    issued: String,
+    expires: String,
+    status: &'static str,
+    received: JsonMoney,
+    paid_on: Option<String>,
+}
+
+pub fn write_balance<W: Write>(balance: &AccountBalance, out: W) -> Result<()> {
+    write(
+        &BalanceDocument {
+            version: JSON_VERSION,
+            kind: "balance",
+            account: balance.account_number(),
+            balance: balance.balance().into(),
+        },
+        out,
+    )
+}
+
+pub fn write_statement<W: Write>(statement: &AccountMutasi, out: W) -> Result<()> {
+    let info = statement.info();
+    let currency = info.currency();
+    let summary = statement.summary();
+    let mut ids = booked_transactions(statement)
+        .into_iter()
+        .map(|(tx, position)| transaction_id(info.account_number(), tx, position));
+    let transactions = running_balances(statement)
+        .into_iter()
+        .map(|(tx, balance)| JsonTx {
+            id: if tx.is_pending() { None } else { ids.next() },
+            date: tx.date().format("%Y-%m-%d").to_string(),
+            pending: tx.is_pending(),
+            description: tx.description(),
+            amount: JsonMoney::new(tx.amount(), currency),
+            direction: tx.direction().code(),
+            balance: JsonMoney::new(balance, currency),
+        })
+        .collect();
+    write(
+        &StatementDocument {
+            version: JSON_VERSION,
+            kind: "statement",
+            account: info.account_number(),
+            owner: info.owner_name(),
+            currency,
+            period: info.period_range()?.into(),
+            starting_balance: summary.balance_begin().into(),
+            total_credits: summary.total_credits().into(),
+            total_debits: summary.total_debits().into(),
+            ending_balance: summary.balance_end().into(),
+            transactions,
+        },
+        out,
+    )
+}
+
+pub fn write_sync<W: Write>(report: &SyncReport, out: W) -> Result<()> {
+    write(
+        &SyncDocument {
+            version: JSON_VERSION,
+            kind: "sync",
+            account: &report.account,
+            period: report.range.into(),
+            inserted: report.added.inserted,
+            duplicates: report.added.duplicates,
+            pending: report.added.pending,
+            gap: report.gap.map(JsonPeriod::from),
+            balance: (&report.balance).into(),
+        },
+        out,
+    )
+}
+
+// a single transaction, one line of the watch's ndjson stream
+pub fn write_transaction<W: Write>(tx: &LedgerTx, out: W) -> Result<()> {
+    write(
+        &TransactionDocument {
+            version: JSON_VERSION,
+            kind: "transaction",
+            id: &tx.id,
+            account: &tx.account,
+            date: tx.date.format("%Y-%m-%d").to_string(),
+            description: &tx.description,
+            amount: JsonMoney::new(tx.amount, &tx.currency),
+            direction: tx.direction.code(),
+        },
+        out,
+    )
+}
+
+// invoices are asked for in rupiah, the unique codes are whole rupiah
+pub fn write_invoices<W: Write>(invoices: &[Invoice], out: W) -> Result<()> {
+    let money = |amount| JsonMoney::new(amount, "IDR");
+    write(
+        &InvoicesDocument {
+            version: JSON_VERSION,
+            kind: "invoices",
+            invoices: invoices
+                .iter()
+                .map(|invoice| JsonInvoice {
+                    reference: &invoice.reference,
+                    amount: money(invoice.amount),
+                    unique_amount: money(invoice.unique_amount),
+                    name: invoice.name.as_deref(),
+                    issued: invoice.issued.format("%Y-%m-%d").to_string(),
+                    expires: invoice.expires.format("%Y-%m-%d").to_string(),
+                    status: invoice.status.code(),
+                    received: money(invoice.received),
+                    paid_on: invoice.paid_on.map(|d| d.format("%Y-%m-%d").to_string()),
+                })
+                .collect(),
+        },
+        out,
+    )
+}
+
+// one document per line
+fn write<W: Write, T: Serialize>(document: &T, mut out: W) -> Result<()> {
+    serde_json::to_writer(&mut out, document)?;
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::money::Amount;
+use crate::resp_parser::mutasi_parser::{AccountMutasi, AccountTx};
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+pub mod csv;
+pub mod journal;
+pub mod json;
+pub mod ofx;
+pub mod qif;
+
+// decimal separator of the amounts, spreadsheets in an Indonesian locale expect a comma
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecimalStyle {
+    // 1234567.89
+    Point,
+    // 1234567,89
+    Comma,
+}
+
+impl DecimalStyle {
+    // amount without thousands separators, so spreadsheets read it as a number
+    pub fn format(self, amount: Amount) -> String {
+        let minor = amount.minor();
+        let sign = if minor < 0 { "-" } else { "" };
+        let separator = match self {
+            DecimalStyle::Point => '.',
+            DecimalStyle::Comma => ',',
+        };
+        format!(
+            "{}{}{}{:02}",
+            sign,
+            minor.unsigned_abs() / 100,
+            separator,
+            minor.unsigned_abs() % 100
+        )
+    }
+}
+
+impl FromStr for DecimalStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "point" | "." => Ok(DecimalStyle::Point),
+            "comma" | "," => Ok(DecimalStyle::Comma),
+            _ => Err(anyhow!("unknown decimal style {:?}, use point or comma", s)),
+        }
+    }
+}
+
+// pairs every transaction of a statement with the balance after it, starting from the
+// statement's starting balance
+pub fn running_balances<'s>(statement: &'s AccountMutasi) -> Vec<(&'s AccountTx, Amount)> {
+    let mut balance = statement.summary().balance_begin().amount();
+    statement
+        .transactions()
+        .iter()
+        .map(|tx| {
+            balance = balance + tx.signed_amount();
+            (tx, balance)
+        })
+        .collect()
+}
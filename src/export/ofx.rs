@@ -0,0 +1,98 @@
+use crate::export::DecimalStyle;
+use crate::money::Amount;
+use crate::resp_parser::beneficiary_parser::BCA_BANK_CODE;
+use crate::resp_parser::mutasi_parser::{AccountMutasi, Direction};
+use crate::storage::ledger::{booked_transactions, transaction_id};
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::io::Write;
+
+// OFX limits NAME to 32 characters, the whole description goes into MEMO
+const MAX_NAME_LEN: usize = 32;
+
+const HEADER: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+SECURITY:NONE
+ENCODING:USASCII
+CHARSET:1252
+COMPRESSION:NONE
+OLDFILEUID:NONE
+NEWFILEUID:NONE
+";
+
+// writes the statement as an OFX 1.0.2 bank statement, the SGML flavour GnuCash and most
+// personal finance software import. FITIDs are the ledger's transaction ids, so importing an
+// overlapping period again is recognized as the same transactions. Pending transactions are
+// left out until they are booked.
+pub fn write_statement<W: Write>(statement: &AccountMutasi, mut out: W) -> Result<()> {
+    let info = statement.info();
+    let period = info.period_range()?;
+    let account = info.account_number();
+    let amount = |a: Amount| DecimalStyle::Point.format(a);
+    write!(out, "{}", HEADER)?;
+    writeln!(out)?;
+    writeln!(out, "<OFX>")?;
+    writeln!(out, "<SIGNONMSGSRSV1><SONRS>")?;
+    writeln!(out, "<STATUS><CODE>0<SEVERITY>INFO</STATUS>")?;
+    writeln!(out, "<DTSERVER>{}", date(period.end))?;
+    writeln!(out, "<LANGUAGE>ENG")?;
+    writeln!(out, "</SONRS></SIGNONMSGSRSV1>")?;
+    writeln!(out, "<BANKMSGSRSV1><STMTTRNRS>")?;
+    writeln!(out, "<TRNUID>0")?;
+    writeln!(out, "<STATUS><CODE>0<SEVERITY>INFO</STATUS>")?;
+    writeln!(out, "<STMTRS>")?;
+    writeln!(out, "<CURDEF>{}", escape(info.currency()))?;
+    writeln!(
+        out,
+        "<BANKACCTFROM><BANKID>{}<ACCTID>{}<ACCTTYPE>CHECKING</BANKACCTFROM>",
+        BCA_BANK_CODE,
+        escape(account)
+    )?;
+    writeln!(out, "<BANKTRANLIST>")?;
+    writeln!(out, "<DTSTART>{}", date(period.start))?;
+    writeln!(out, "<DTEND>{}", date(period.end))?;
+    for (tx, position) in booked_transactions(statement) {
+        let kind = match tx.direction() {
+            Direction::Credit => "CREDIT",
+            Direction::Debit => "DEBIT",
+        };
+        let name = tx
+            .description()
+            .chars()
+            .take(MAX_NAME_LEN)
+            .collect::<String>();
+        writeln!(out, "<STMTTRN>")?;
+        writeln!(out, "<TRNTYPE>{}", kind)?;
+        writeln!(out, "<DTPOSTED>{}", date(tx.date()))?;
+        writeln!(out, "<TRNAMT>{}", amount(tx.signed_amount()))?;
+        writeln!(out, "<FITID>{}", transaction_id(account, tx, position))?;
+        writeln!(out, "<NAME>{}", escape(&name))?;
+        writeln!(out, "<MEMO>{}", escape(tx.description()))?;
+        writeln!(out, "</STMTTRN>")?;
+    }
+    writeln!(out, "</BANKTRANLIST>")?;
+    writeln!(out, "<LEDGERBAL>")?;
+    writeln!(
+        out,
+        "<BALAMT>{}",
+        amount(statement.summary().balance_end().amount())
+    )?;
+    writeln!(out, "<DTASOF>{}", date(period.end))?;
+    writeln!(out, "</LEDGERBAL>")?;
+    writeln!(out, "</STMTRS>")?;
+    writeln!(out, "</STMTTRNRS></BANKMSGSRSV1>")?;
+    writeln!(out, "</OFX>")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn date(d: NaiveDate) -> String {
+    d.format("%Y%m%d").to_string()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,25 @@
+use crate::export::DecimalStyle;
+use crate::resp_parser::mutasi_parser::AccountMutasi;
+use crate::storage::ledger::booked_transactions;
+use anyhow::Result;
+use std::io::Write;
+
+// writes the booked transactions of the statement as a QIF bank register. QIF has no
+// transaction ids or balances, importers have to match duplicates themselves; prefer OFX where
+// the software reads it. Dates are written mm/dd/yyyy, the order Quicken and GnuCash assume.
+pub fn write_statement<W: Write>(statement: &AccountMutasi, mut out: W) -> Result<()> {
+    writeln!(out, "!Type:Bank")?;
+    for (tx, _) in booked_transactions(statement) {
+        writeln!(out, "D{}", tx.date().format("%m/%d/%Y"))?;
+        writeln!(out, "T{}", DecimalStyle::Point.format(tx.signed_amount()))?;
+        writeln!(out, "P{}", single_line(tx.description()))?;
+        writeln!(out, "^")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// a line starting with a field code ends the field, so line breaks can't be kept
+fn single_line(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
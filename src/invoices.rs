@@ -0,0 +1,609 @@
+use crate::money::Amount;
+use crate::resp_parser::mutasi_parser::{AccountMutasi, AccountTx, Direction};
+use crate::storage::ledger::{booked_transactions, transaction_id};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const INVOICES_FILE: &str = "invoices.sqlite3";
+const SCHEMA_VERSION: i64 = 1;
+
+// highest code added to an invoice, in whole rupiah: 150,000 is asked as 150,001 to 150,999
+pub const MAX_UNIQUE_CODE: i64 = 999;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS invoices (
+    reference TEXT PRIMARY KEY,
+    amount INTEGER NOT NULL,
+    unique_amount INTEGER NOT NULL,
+    name TEXT,
+    issued TEXT NOT NULL,
+    expires TEXT NOT NULL,
+    status TEXT NOT NULL,
+    received INTEGER NOT NULL DEFAULT 0,
+    paid_on TEXT
+);
+CREATE INDEX IF NOT EXISTS invoices_by_status ON invoices (status, expires);
+CREATE TABLE IF NOT EXISTS payments (
+    tx_id TEXT PRIMARY KEY,
+    date TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    reference TEXT,
+    outstanding INTEGER,
+    candidates TEXT
+);
+";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InvoiceStatus {
+    // nothing received yet
+    Pending,
+    // less than the billed amount received
+    Partial,
+    Paid,
+    Cancelled,
+}
+
+impl InvoiceStatus {
+    pub fn code(self) -> &'static str {
+        match self {
+            InvoiceStatus::Pending => "pending",
+            InvoiceStatus::Partial => "partial",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Cancelled => "cancelled",
+        }
+    }
+
+    // still waiting for money
+    pub fn is_open(self) -> bool {
+        matches!(self, InvoiceStatus::Pending | InvoiceStatus::Partial)
+    }
+}
+
+impl FromStr for InvoiceStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(InvoiceStatus::Pending),
+            "partial" => Ok(InvoiceStatus::Partial),
+            "paid" => Ok(InvoiceStatus::Paid),
+            "cancelled" => Ok(InvoiceStatus::Cancelled),
+            _ => Err(anyhow!("unknown invoice status {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+// Invoice is a payment expected by transfer. The customer is asked for unique_amount, the
+// billed amount plus a small code no other open invoice uses at the same time, so the
+// transfer tells which invoice it pays.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invoice {
+    pub reference: String,
+    // billed amount
+    pub amount: Amount,
+    pub unique_amount: Amount,
+    // payer name expected in the transfer note
+    pub name: Option<String>,
+    pub issued: NaiveDate,
+    // last day a transfer is matched to the invoice
+    pub expires: NaiveDate,
+    pub status: InvoiceStatus,
+    pub received: Amount,
+    pub paid_on: Option<NaiveDate>,
+}
+
+impl Invoice {
+    // what is still to be paid of the billed amount
+    pub fn outstanding(&self) -> Amount {
+        std::cmp::max(self.amount - self.received, Amount::default())
+    }
+
+    fn in_window(&self, day: NaiveDate) -> bool {
+        self.status.is_open() && self.issued <= day && day <= self.expires
+    }
+
+    // the expected name is in the note, or no name is expected
+    fn named_in(&self, note: &str) -> bool {
+        match &self.name {
+            Some(name) => squash(note).contains(&squash(name)),
+            None => true,
+        }
+    }
+
+    // the note carries the reference or the expected name
+    fn cued_by(&self, note: &str) -> bool {
+        let note = squash(note);
+        note.contains(&squash(&self.reference))
+            || self
+                .name
+                .as_ref()
+                .is_some_and(|name| note.contains(&squash(name)))
+    }
+
+    // books a transfer, the invoice is paid once the billed amount is in. The unique code is
+    // only there to tell invoices apart, a transfer without it still pays.
+    fn receive(&mut self, amount: Amount, day: NaiveDate) -> MatchOutcome {
+        self.received = self.received + amount;
+        if self.received >= self.amount {
+            self.status = InvoiceStatus::Paid;
+            self.paid_on = Some(day);
+            MatchOutcome::Paid {
+                reference: self.reference.clone(),
+            }
+        } else {
+            self.status = InvoiceStatus::Partial;
+            MatchOutcome::Partial {
+                reference: self.reference.clone(),
+                outstanding: self.outstanding(),
+            }
+        }
+    }
+}
+
+// notes and names compared in upper case letters and digits only, klikbca drops punctuation
+// from some notes
+fn squash(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+// what a credit transfer was taken for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MatchOutcome {
+    Paid {
+        reference: String,
+    },
+    // less than the billed amount arrived so far
+    Partial {
+        reference: String,
+        outstanding: Amount,
+    },
+    // fits several invoices, or only one by amount but without its expected name in the
+    // note. Left for InvoiceBook::resolve.
+    Ambiguous {
+        candidates: Vec<String>,
+    },
+}
+
+impl MatchOutcome {
+    fn code(&self) -> &'static str {
+        match self {
+            MatchOutcome::Paid { .. } => "paid",
+            MatchOutcome::Partial { .. } => "partial",
+            MatchOutcome::Ambiguous { .. } => "ambiguous",
+        }
+    }
+}
+
+// PaymentMatch is a credit transfer taken for one or more invoices
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentMatch {
+    // the ledger's transaction id
+    pub tx_id: String,
+    pub date: NaiveDate,
+    pub amount: Amount,
+    pub description: String,
+    pub outcome: MatchOutcome,
+}
+
+// InvoiceError is an invoice operation refused by the InvoiceBook
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvoiceError {
+    DuplicateReference(String),
+    UnknownInvoice(String),
+    NotOpen {
+        reference: String,
+        status: InvoiceStatus,
+    },
+    // every code is taken by an open invoice with an overlapping window
+    NoUniqueAmount {
+        amount: Amount,
+    },
+    NotAmbiguous(String),
+}
+
+impl fmt::Display for InvoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvoiceError::DuplicateReference(reference) => {
+                write!(f, "invoice {} already exists", reference)
+            }
+            InvoiceError::UnknownInvoice(reference) => write!(f, "no invoice {}", reference),
+            InvoiceError::NotOpen { reference, status } => {
+                write!(f, "invoice {} is {}", reference, status)
+            }
+            InvoiceError::NoUniqueAmount { amount } => write!(
+                f,
+                "no unique amount left for {}: all {} codes are taken by open invoices",
+                amount, MAX_UNIQUE_CODE
+            ),
+            InvoiceError::NotAmbiguous(tx_id) => {
+                write!(f, "transaction {} isn't an ambiguous payment", tx_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvoiceError {}
+
+// InvoiceBook keeps the expected payments in sqlite and matches credit transfers of
+// statements to them. Every transfer is taken once, so overlapping statements can be matched
+// again and again.
+pub struct InvoiceBook {
+    conn: Connection,
+}
+
+impl InvoiceBook {
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(data_dir)?;
+        let path = data_dir.join(INVOICES_FILE);
+        let conn = Connection::open(&path)
+            .map_err(|e| anyhow!("{}: can't open invoices: {}", path.display(), e))?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(anyhow!(
+                "{}: invoices were written by a newer version (schema {})",
+                path.display(),
+                version
+            ));
+        }
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(InvoiceBook { conn })
+    }
+
+    // adds an invoice payable from issued through expires. Its unique amount is the lowest
+    // code not asked for by another open invoice whose window overlaps.
+    pub fn issue(
+        &mut self,
+        reference: &str,
+        amount: Amount,
+        name: Option<&str>,
+        issued: NaiveDate,
+        expires: NaiveDate,
+    ) -> Result<Invoice> {
+        if reference.trim().is_empty() {
+            return Err(anyhow!("an invoice needs a reference"));
+        }
+        if amount <= Amount::default() {
+            return Err(anyhow!("invoice amount must be positive, got {}", amount));
+        }
+        if expires < issued {
+            return Err(anyhow!("invoice expires before it is issued"));
+        }
+        let db = self.conn.transaction()?;
+        if find(&db, reference)?.is_some() {
+            return Err(InvoiceError::DuplicateReference(reference.to_string()).into());
+        }
+        let taken = {
+            let mut query = db.prepare(
+                "SELECT unique_amount FROM invoices
+                 WHERE status IN ('pending', 'partial') AND expires >= ?1 AND issued <= ?2",
+            )?;
+            let taken = query
+                .query_map(params![day(issued), day(expires)], |row| row.get(0))?
+                .collect::<Result<HashSet<i64>, _>>()?;
+            taken
+        };
+        let unique_amount = (1..=MAX_UNIQUE_CODE)
+            .map(|code| amount + Amount::from_major(code))
+            .find(|unique| !taken.contains(&unique.minor()))
+            .ok_or(InvoiceError::NoUniqueAmount { amount })?;
+        let invoice = Invoice {
+            reference: reference.trim().to_string(),
+            amount,
+            unique_amount,
+            name: name
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .map(str::to_string),
+            issued,
+            expires,
+            status: InvoiceStatus::Pending,
+            received: Amount::default(),
+            paid_on: None,
+        };
+        db.execute(
+            "INSERT INTO invoices (reference, amount, unique_amount, name, issued, expires, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                invoice.reference,
+                invoice.amount.minor(),
+                invoice.unique_amount.minor(),
+                invoice.name,
+                day(invoice.issued),
+                day(invoice.expires),
+                invoice.status.code(),
+            ],
+        )?;
+        db.commit()?;
+        Ok(invoice)
+    }
+
+    pub fn get(&self, reference: &str) -> Result<Invoice> {
+        find(&self.conn, reference)?
+            .ok_or_else(|| InvoiceError::UnknownInvoice(reference.to_string()).into())
+    }
+
+    // open invoices, or all of them, oldest first
+    pub fn invoices(&self, all: bool) -> Result<Vec<Invoice>> {
+        let filter = if all {
+            ""
+        } else {
+            "WHERE status IN ('pending', 'partial')"
+        };
+        load(
+            &self.conn,
+            &format!("{} ORDER BY issued, reference", filter),
+            params![],
+        )
+    }
+
+    // frees the unique amount of an open invoice
+    pub fn cancel(&mut self, reference: &str) -> Result<Invoice> {
+        let mut invoice = self.get(reference)?;
+        if !invoice.status.is_open() {
+            return Err(InvoiceError::NotOpen {
+                reference: invoice.reference,
+                status: invoice.status,
+            }
+            .into());
+        }
+        invoice.status = InvoiceStatus::Cancelled;
+        update(&self.conn, &invoice)?;
+        Ok(invoice)
+    }
+
+    // takes the booked credit transfers of the statement not taken before. A transfer pays
+    // the open invoice asking for exactly its amount on its day, and naming the payer if the
+    // invoice expects one. Failing that, a transfer whose note carries the reference or name
+    // of a single open invoice is booked against it, in part or in full. Transfers matching
+    // nothing are left alone. Returns what the new transfers were taken for.
+    pub fn match_statement(&mut self, statement: &AccountMutasi) -> Result<Vec<PaymentMatch>> {
+        let account = statement.info().account_number();
+        let db = self.conn.transaction()?;
+        let mut open = load(&db, "WHERE status IN ('pending', 'partial')", params![])?;
+        let mut matches = vec![];
+        for (tx, position) in booked_transactions(statement) {
+            if tx.direction() != Direction::Credit {
+                continue;
+            }
+            let tx_id = transaction_id(account, tx, position);
+            let taken: Option<i64> = db
+                .query_row(
+                    "SELECT 1 FROM payments WHERE tx_id = ?1",
+                    params![tx_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if taken.is_some() {
+                continue;
+            }
+            let outcome = match match_transfer(&mut open, tx) {
+                Some(outcome) => outcome,
+                None => continue,
+            };
+            let payment = PaymentMatch {
+                tx_id,
+                date: tx.date(),
+                amount: tx.amount(),
+                description: tx.description().to_string(),
+                outcome,
+            };
+            insert_payment(&db, &payment)?;
+            matches.push(payment);
+        }
+        for invoice in &open {
+            update(&db, invoice)?;
+        }
+        db.commit()?;
+        Ok(matches)
+    }
+
+    // ambiguous transfers not resolved yet
+    pub fn ambiguous(&self) -> Result<Vec<PaymentMatch>> {
+        let mut query = self.conn.prepare(
+            "SELECT tx_id, date, amount, description, candidates FROM payments
+             WHERE outcome = 'ambiguous' ORDER BY date, tx_id",
+        )?;
+        let rows = query.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (tx_id, date, amount, description, candidates) = row?;
+            Ok(PaymentMatch {
+                tx_id,
+                date: parse_day(&date)?,
+                amount: Amount::from_minor(amount),
+                description,
+                outcome: MatchOutcome::Ambiguous {
+                    candidates: serde_json::from_str(&candidates.unwrap_or_default())?,
+                },
+            })
+        })
+        .collect()
+    }
+
+    // books an ambiguous transfer against the invoice it was meant for
+    pub fn resolve(&mut self, tx_id: &str, reference: &str) -> Result<PaymentMatch> {
+        let mut payment = self
+            .ambiguous()?
+            .into_iter()
+            .find(|p| p.tx_id == tx_id)
+            .ok_or_else(|| InvoiceError::NotAmbiguous(tx_id.to_string()))?;
+        let mut invoice = self.get(reference)?;
+        if !invoice.status.is_open() {
+            return Err(InvoiceError::NotOpen {
+                reference: invoice.reference,
+                status: invoice.status,
+            }
+            .into());
+        }
+        payment.outcome = invoice.receive(payment.amount, payment.date);
+        let db = self.conn.transaction()?;
+        db.execute("DELETE FROM payments WHERE tx_id = ?1", params![tx_id])?;
+        insert_payment(&db, &payment)?;
+        update(&db, &invoice)?;
+        db.commit()?;
+        Ok(payment)
+    }
+}
+
+// the open invoice a credit transfer is for, see InvoiceBook::match_statement
+fn match_transfer(open: &mut [Invoice], tx: &AccountTx) -> Option<MatchOutcome> {
+    let note = tx.description();
+    let candidates = |filter: &dyn Fn(&Invoice) -> bool| -> Vec<usize> {
+        (0..open.len())
+            .filter(|&i| open[i].in_window(tx.date()) && filter(&open[i]))
+            .collect()
+    };
+    let references = |found: &[usize]| MatchOutcome::Ambiguous {
+        candidates: found.iter().map(|&i| open[i].reference.clone()).collect(),
+    };
+    let exact = candidates(&|invoice| {
+        invoice.status == InvoiceStatus::Pending && invoice.unique_amount == tx.amount()
+    });
+    let named: Vec<usize> = exact
+        .iter()
+        .copied()
+        .filter(|&i| open[i].named_in(note))
+        .collect();
+    let found = match named.as_slice() {
+        [i] => *i,
+        [] if exact.is_empty() => match candidates(&|invoice| invoice.cued_by(note)).as_slice() {
+            [i] => *i,
+            [] => return None,
+            cued => return Some(references(cued)),
+        },
+        // the amount fits, but not the payer
+        [] => return Some(references(&exact)),
+        _ => {
+            let by_reference: Vec<usize> = named
+                .iter()
+                .copied()
+                .filter(|&i| squash(note).contains(&squash(&open[i].reference)))
+                .collect();
+            match by_reference.as_slice() {
+                [i] => *i,
+                _ => return Some(references(&named)),
+            }
+        }
+    };
+    Some(open[found].receive(tx.amount(), tx.date()))
+}
+
+fn day(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn parse_day(s: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| anyhow!("invalid date {:?}: {}", s, e))
+}
+
+fn find(conn: &Connection, reference: &str) -> Result<Option<Invoice>> {
+    Ok(
+        load(conn, "WHERE reference = ?1", params![reference.trim()])?
+            .into_iter()
+            .next(),
+    )
+}
+
+fn load(conn: &Connection, filter: &str, params: impl rusqlite::Params) -> Result<Vec<Invoice>> {
+    let mut query = conn.prepare(&format!(
+        "SELECT reference, amount, unique_amount, name, issued, expires, status, received, paid_on
+         FROM invoices {}",
+        filter
+    ))?;
+    let rows = query.query_map(params, |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, String>(6)?,
+            row.get::<_, i64>(7)?,
+            row.get::<_, Option<String>>(8)?,
+        ))
+    })?;
+    rows.map(|row| {
+        let (reference, amount, unique, name, issued, expires, status, received, paid_on) = row?;
+        Ok(Invoice {
+            reference,
+            amount: Amount::from_minor(amount),
+            unique_amount: Amount::from_minor(unique),
+            name,
+            issued: parse_day(&issued)?,
+            expires: parse_day(&expires)?,
+            status: status.parse()?,
+            received: Amount::from_minor(received),
+            paid_on: paid_on.map(|d| parse_day(&d)).transpose()?,
+        })
+    })
+    .collect()
+}
+
+fn update(conn: &Connection, invoice: &Invoice) -> Result<()> {
+    conn.execute(
+        "UPDATE invoices SET status = ?2, received = ?3, paid_on = ?4 WHERE reference = ?1",
+        params![
+            invoice.reference,
+            invoice.status.code(),
+            invoice.received.minor(),
+            invoice.paid_on.map(day),
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_payment(conn: &Connection, payment: &PaymentMatch) -> Result<()> {
+    let (reference, outstanding, candidates) = match &payment.outcome {
+        MatchOutcome::Paid { reference } => (Some(reference), None, None),
+        MatchOutcome::Partial {
+            reference,
+            outstanding,
+        } => (Some(reference), Some(outstanding.minor()), None),
+        MatchOutcome::Ambiguous { candidates } => {
+            (None, None, Some(serde_json::to_string(candidates)?))
+        }
+    };
+    conn.execute(
+        "INSERT INTO payments
+         (tx_id, date, amount, description, outcome, reference, outstanding, candidates)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            payment.tx_id,
+            day(payment.date),
+            payment.amount.minor(),
+            payment.description,
+            payment.outcome.code(),
+            reference,
+            outstanding,
+            candidates,
+        ],
+    )?;
+    Ok(())
+}
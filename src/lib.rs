@@ -0,0 +1,60 @@
+// Unofficial KlikBCA Individu client library.
+// The TUI binary (src/main.rs) is one consumer of this API.
+
+// modules
+pub mod accounts;
+pub mod batch;
+pub mod clients;
+pub mod export;
+pub mod invoices;
+// the klikbca stand-in behind --demo and the integration tests, not a stable API
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod money;
+pub mod policy;
+pub mod resp_parser;
+pub mod session;
+pub mod storage;
+pub mod sync;
+pub mod watch;
+pub mod webhook;
+
+// re-exports
+pub use accounts::{
+    BcaAccount, DateRange, InterbankTransferRequest, LoginError, PaymentRequest, RangeError,
+    SessionError, StatementLimits, TransferRequest, MAX_STATEMENT_DAYS, STATEMENT_HISTORY_DAYS,
+};
+pub use batch::{BatchEntry, BatchIssue, BatchReport, EntryResult, EntryStatus, TransferBatch};
+pub use clients::{ClientConfig, Endpoints};
+pub use export::{
+    csv::CsvOptions,
+    journal::{JournalFlavor, JournalOptions, JournalState, MappingRule},
+    json::JSON_VERSION,
+    DecimalStyle,
+};
+pub use invoices::{
+    Invoice, InvoiceBook, InvoiceError, InvoiceStatus, MatchOutcome, PaymentMatch, MAX_UNIQUE_CODE,
+};
+pub use money::{Amount, Money};
+pub use policy::{check_typed_name, PolicyError, TransferPolicy};
+pub use resp_parser::{
+    beneficiary_parser::{BeneficiaryChallenge, RegisteredBeneficiary},
+    form_parser::{KeyBcaChallenge, Receipt, TransactionError},
+    interbank_parser::{Bank, InterbankChallenge, InterbankReceipt, NameInquiry, TransferService},
+    mutasi_parser::{AccountInfo, AccountMutasi, AccountTx, Direction, MutationSummary},
+    parse_error::ParseError,
+    payment_parser::{PaymentChallenge, PaymentReceipt, VaInquiry},
+    saldo_parser::AccountBalance,
+    transfer_parser::{Beneficiary, TransferChallenge, TransferReceipt},
+};
+pub use session::{BcaSession, SessionState};
+pub use storage::{
+    beneficiaries::{BeneficiaryCache, CachedBeneficiaries},
+    ledger::{
+        booked_transactions, transaction_id, BalanceSnapshot, Ledger, LedgerInsert, LedgerTx,
+    },
+    receipts::{ReceiptKind, ReceiptStore, StoredReceipt},
+};
+pub use sync::{sync, SyncReport};
+pub use watch::Watcher;
+pub use webhook::{Backoff, Delivery, DeliveryQueue, DeliveryRun, WebhookSender};
@@ -1,20 +1,32 @@
 // externs
 
 // modules
-mod accounts;
-mod clients;
+mod commands;
 mod events;
-mod resp_parser;
 mod states;
 mod ui;
 
 // use
+use crate::commands::{
+    balance::BalanceOpt, beneficiaries::BeneficiariesOpt, daemon::DaemonOpt, export::ExportOpt,
+    interbank::InterbankOpt, invoice::InvoiceOpt, pay::PayOpt, statement::StatementOpt,
+    sync::SyncOpt, transfer::TransferOpt, transfer_batch::TransferBatchOpt, watch::WatchOpt,
+};
 use crate::events::event::{Config, Event, Events};
-use accounts::BcaAccount;
-use anyhow::Result;
-use clients::Client;
-use states::states::{AppState, InputMode};
+use anyhow::{anyhow, Result};
+#[cfg(feature = "mock")]
+use bca_rust::mock::{MockServer, KEYBCA_RESPONSE};
+use bca_rust::{
+    clients::{DEFAULT_BASE_URL, DEFAULT_PUB_IP_URL},
+    resp_parser::beneficiary_parser::BCA_BANK_CODE,
+    storage::default_data_dir,
+    Amount, BcaSession, BeneficiaryCache, ClientConfig, CsvOptions, InvoiceBook, Ledger,
+    ReceiptStore, TransferPolicy,
+};
+use states::states::{AppState, InputMode, Screen};
+use std::fs::{self, File};
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use structopt::StructOpt;
 use termion::{
@@ -23,28 +35,194 @@ use termion::{
 use tui::{backend::TermionBackend, Terminal};
 use unicode_width::UnicodeWidthStr;
 
+// without the mock feature there is no demo server to keep alive
+#[cfg(not(feature = "mock"))]
+type MockServer = ();
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "env")]
 struct ReqOpt {
-    #[structopt(short = "u", env = "BCA_ACCOUNT")]
-    user: String,
-    #[structopt(short = "p", env = "BCA_PASSWORD")]
-    password: String,
+    #[structopt(short = "u", env = "BCA_ACCOUNT", required_unless = "demo")]
+    user: Option<String>,
+    #[structopt(short = "p", env = "BCA_PASSWORD", required_unless = "demo")]
+    password: Option<String>,
+    /// klikbca base url, point it at a local stand-in server for testing
+    #[structopt(long = "base-url", env = "BCA_BASE_URL", default_value = DEFAULT_BASE_URL)]
+    base_url: String,
+    /// service answering with the caller's public ip
+    #[structopt(long = "pub-ip-url", env = "BCA_PUB_IP_URL", default_value = DEFAULT_PUB_IP_URL)]
+    pub_ip_url: String,
+    /// run against the bundled mock klikbca server with recorded pages
+    #[structopt(long = "demo")]
+    demo: bool,
+    /// refuse every transaction and beneficiary change
+    #[structopt(long = "read-only")]
+    read_only: bool,
+    /// prepare transactions and show them, but stop before the KeyBCA step
+    #[structopt(long = "dry-run")]
+    dry_run: bool,
+    /// largest single transaction in IDR, refused locally above it
+    #[structopt(long = "max-transfer", env = "BCA_MAX_TRANSFER")]
+    max_transfer: Option<Amount>,
+    /// largest total of the transactions of a day in IDR, counted from the stored receipts
+    #[structopt(long = "max-daily", env = "BCA_MAX_DAILY")]
+    max_daily: Option<Amount>,
+    /// where receipts and caches are kept [default: $XDG_DATA_HOME/bca-rust]
+    #[structopt(long = "data-dir", env = "BCA_DATA_DIR", parse(from_os_str))]
+    data_dir: Option<PathBuf>,
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+// without a subcommand the dashboard is started
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// current balance of the account
+    Balance(BalanceOpt),
+    /// account statement over any period klikbca still keeps
+    Statement(StatementOpt),
+    /// export a statement for spreadsheets and accounting software
+    Export(ExportOpt),
+    /// the full screen dashboard, also started without a subcommand
+    Tui,
+    /// fetch what is new since the last sync into the ledger, for cron
+    Sync(SyncOpt),
+    /// print new transactions as json lines, polling klikbca
+    Watch(WatchOpt),
+    /// keep polling klikbca and post new transactions to signed webhooks
+    Daemon(DaemonOpt),
+    /// expected payments told apart by unique amounts, matched to incoming transfers
+    Invoice(InvoiceOpt),
+    /// transfer to a registered BCA account, confirmed with KeyBCA
+    Transfer(TransferOpt),
+    /// transfers to registered BCA accounts from a csv file, resumable after a failure
+    TransferBatch(TransferBatchOpt),
+    /// transfer to an account at another bank (online or BI-FAST), confirmed with KeyBCA
+    Interbank(InterbankOpt),
+    /// pay a BCA Virtual Account bill, confirmed with KeyBCA
+    Pay(PayOpt),
+    /// manage the registered transfer destinations (daftar transfer)
+    Beneficiaries(BeneficiariesOpt),
+}
+
+// where the dashboard writes exported statements, inside the data dir
+const EXPORTS_DIR: &str = "exports";
+
+#[cfg(feature = "mock")]
+const DEMO_USER: &str = "demo";
+#[cfg(feature = "mock")]
+const DEMO_PASSWORD: &str = "123456";
+
+// local state directory, demo mode keeps its (fake) receipts apart
+fn data_dir(opt: &ReqOpt) -> Result<PathBuf> {
+    let dir = match &opt.data_dir {
+        Some(dir) => dir.clone(),
+        None => default_data_dir()?,
+    };
+    Ok(if opt.demo { dir.join("demo") } else { dir })
+}
+
+// creates the session, starting the mock server first in demo mode. The server has to
+// outlive the session, so it is handed back to the caller.
+fn connect(opt: ReqOpt) -> Result<(Option<MockServer>, BcaSession)> {
+    if opt.demo {
+        return demo();
+    }
+    let config = ClientConfig::new(&opt.base_url, &opt.pub_ip_url)?;
+    let session = BcaSession::with_config(
+        opt.user.unwrap_or_default(),
+        opt.password.unwrap_or_default(),
+        config,
+    )?;
+    Ok((None, session))
+}
+
+#[cfg(feature = "mock")]
+fn demo() -> Result<(Option<MockServer>, BcaSession)> {
+    let server = MockServer::start(DEMO_USER, DEMO_PASSWORD)?;
+    eprintln!("demo mode: the KeyBCA response is {}", KEYBCA_RESPONSE);
+    let session = BcaSession::with_config(
+        DEMO_USER.to_string(),
+        DEMO_PASSWORD.to_string(),
+        server.client_config(),
+    )?;
+    Ok((Some(server), session))
+}
+
+#[cfg(not(feature = "mock"))]
+fn demo() -> Result<(Option<MockServer>, BcaSession)> {
+    Err(anyhow!("--demo needs bca-rust built with the mock feature"))
 }
 
 fn main() -> Result<()> {
-    let opt = ReqOpt::from_args();
+    let mut opt = ReqOpt::from_args();
+    let cmd = opt.cmd.take();
+    let data_dir = data_dir(&opt)?;
+    let store = ReceiptStore::new(&data_dir);
+    let cache = BeneficiaryCache::new(&data_dir);
+    let policy = TransferPolicy {
+        read_only: opt.read_only,
+        dry_run: opt.dry_run,
+        max_per_transfer: opt.max_transfer,
+        max_per_day: opt.max_daily,
+        receipts: Some(store.clone()),
+    };
+    let (_mock_server, mut session) = connect(opt)?;
+    session.set_policy(policy);
+    match cmd {
+        Some(Command::Balance(balance)) => {
+            commands::balance::run(&mut session, &mut Ledger::open(&data_dir)?, balance)
+        }
+        Some(Command::Statement(statement)) => {
+            commands::statement::run(&mut session, &mut Ledger::open(&data_dir)?, statement)
+        }
+        Some(Command::Export(export)) => commands::export::run(&mut session, export),
+        Some(Command::Sync(sync)) => {
+            commands::sync::run(&mut session, &mut Ledger::open(&data_dir)?, sync)
+        }
+        Some(Command::Watch(watch)) => commands::watch::run(&mut session, &data_dir, watch),
+        Some(Command::Daemon(daemon)) => commands::daemon::run(&mut session, &data_dir, daemon),
+        Some(Command::Invoice(invoice)) => {
+            commands::invoice::run(&mut session, &mut InvoiceBook::open(&data_dir)?, invoice)
+        }
+        Some(Command::Transfer(transfer)) => {
+            commands::transfer::run(&mut session, &store, transfer)
+        }
+        Some(Command::TransferBatch(batch)) => {
+            commands::transfer_batch::run(&mut session, &store, batch)
+        }
+        Some(Command::Interbank(interbank)) => {
+            commands::interbank::run(&mut session, &store, interbank)
+        }
+        Some(Command::Pay(pay)) => commands::pay::run(&mut session, &store, pay),
+        Some(Command::Beneficiaries(beneficiaries)) => {
+            commands::beneficiaries::run(&mut session, &cache, beneficiaries)
+        }
+        Some(Command::Tui) | None => dashboard(
+            session,
+            &store,
+            &cache,
+            Ledger::open(&data_dir)?,
+            &data_dir.join(EXPORTS_DIR),
+        ),
+    }
+}
+
+fn dashboard(
+    mut session: BcaSession,
+    store: &ReceiptStore,
+    cache: &BeneficiaryCache,
+    mut ledger: Ledger,
+    exports: &Path,
+) -> Result<()> {
     let mut app_state = AppState::new();
-    let acc = BcaAccount::new(opt.user, opt.password);
-    let mut new_client = Client::new()?;
-    acc.login(&mut new_client, &mut app_state)?;
+    session.login()?;
     let events = Events::with_config(Config {
         tick_rate: Duration::from_millis(2000),
         ..Config::default()
     });
 
-    acc.get_saldo(&mut new_client, &mut app_state)?;
-    acc.get_mutasi(&mut new_client, &mut app_state)?;
+    refresh(&mut session, &mut ledger, &mut app_state)?;
 
     let stdout = std::io::stdout().into_raw_mode()?;
     let stdout = MouseTerminal::from(stdout);
@@ -71,17 +249,75 @@ fn main() -> Result<()> {
                         events.disable_exit_key();
                     }
                     Key::Char('q') => {
-                        acc.logout(&mut new_client, &mut app_state)?;
+                        session.logout()?;
                         break;
                     }
+                    Key::Char('r') => {
+                        app_state.toggle_screen(Screen::Receipts);
+                        if app_state.screen == Screen::Receipts {
+                            app_state.last_error = store
+                                .load()
+                                .map(|receipts| app_state.update_receipts(&receipts))
+                                .err()
+                                .map(|e| e.to_string());
+                        }
+                    }
+                    Key::Char('b') => {
+                        app_state.toggle_screen(Screen::Beneficiaries);
+                        if app_state.screen == Screen::Beneficiaries {
+                            if let Ok(Some(cached)) = cache.load() {
+                                app_state.update_beneficiaries(&cached.entries);
+                            }
+                            app_state.last_error =
+                                refresh_beneficiaries(&mut session, cache, &mut app_state)
+                                    .err()
+                                    .map(|e| e.to_string());
+                        }
+                    }
+                    Key::Char('a') if app_state.screen == Screen::Beneficiaries => {
+                        app_state.input_mode = InputMode::AddBeneficiary;
+                        app_state.status =
+                            Some("bank code and account number, e.g. 014 1234567890".into());
+                        events.disable_exit_key();
+                    }
+                    Key::Char('d') if app_state.screen == Screen::Beneficiaries => {
+                        app_state.select_removal();
+                    }
+                    Key::Char('y') if app_state.pending_removal.is_some() => {
+                        app_state.last_error =
+                            remove_beneficiary(&mut session, cache, &mut app_state)
+                                .err()
+                                .map(|e| e.to_string());
+                    }
+                    Key::Char('x') if app_state.screen == Screen::Statements => {
+                        app_state.last_error =
+                            export_statement(&mut session, exports, &mut app_state)
+                                .err()
+                                .map(|e| e.to_string());
+                    }
                     Key::Up => app_state.on_up(),
                     Key::Down => app_state.on_down(),
-                    _ => {}
+                    _ => app_state.cancel(),
                 },
-                InputMode::Editing => match input {
-                    Key::Char('\n') => {
-                        app_state.update_dates()?;
-                    }
+                InputMode::Editing | InputMode::AddBeneficiary | InputMode::KeyBca => match input {
+                    Key::Char('\n') => match app_state.input_mode {
+                        InputMode::AddBeneficiary => {
+                            app_state.last_error =
+                                prepare_beneficiary(&mut session, &mut app_state)
+                                    .err()
+                                    .map(|e| e.to_string());
+                        }
+                        InputMode::KeyBca => {
+                            app_state.last_error =
+                                confirm_beneficiary(&mut session, cache, &mut app_state)
+                                    .err()
+                                    .map(|e| e.to_string());
+                        }
+                        _ => {
+                            app_state.last_error =
+                                app_state.update_dates().err().map(|e| e.to_string());
+                        }
+                    },
                     Key::Char(c) => {
                         app_state.input_string.push(c);
                     }
@@ -89,6 +325,7 @@ fn main() -> Result<()> {
                         app_state.input_string.pop();
                     }
                     Key::Esc => {
+                        app_state.cancel();
                         app_state.input_mode = InputMode::Normal;
                         events.enable_exit_key();
                     }
@@ -96,10 +333,115 @@ fn main() -> Result<()> {
                 },
             }
         }
+        if app_state.input_mode == InputMode::Normal {
+            events.enable_exit_key();
+        }
+        // a pending KeyBCA confirmation is not interrupted by the periodic refresh
         if let Event::Tick = events.next()? {
-            acc.get_saldo(&mut new_client, &mut app_state)?;
-            acc.get_mutasi(&mut new_client, &mut app_state)?;
+            if app_state.input_mode == InputMode::KeyBca {
+                continue;
+            }
+            // keep the dashboard alive (and the terminal sane) when a page can't be parsed
+            app_state.last_error = refresh(&mut session, &mut ledger, &mut app_state)
+                .err()
+                .map(|e| e.to_string());
         }
     }
     Ok(())
 }
+
+// fetches balance and statement from the session, keeps them in the ledger and updates the
+// dashboard state
+fn refresh(session: &mut BcaSession, ledger: &mut Ledger, app_state: &mut AppState) -> Result<()> {
+    let saldo = session.balance()?;
+    app_state.update_balance(&saldo);
+    let mutasi = session.statement(app_state.date_range())?;
+    app_state.update_mutations(&mutasi);
+    ledger.insert_balance(&saldo)?;
+    ledger.insert_statement(&mutasi)?;
+    Ok(())
+}
+
+// writes the statement of the shown period as csv to the exports directory
+fn export_statement(session: &mut BcaSession, dir: &Path, app_state: &mut AppState) -> Result<()> {
+    let range = app_state.date_range();
+    let statement = session.statement(range)?;
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "{}_{}_{}.csv",
+        statement.info().account_number(),
+        range.start.format("%Y%m%d"),
+        range.end.format("%Y%m%d")
+    ));
+    bca_rust::export::csv::write_statement(
+        &statement,
+        &CsvOptions::default(),
+        File::create(&path)?,
+    )?;
+    app_state.status = Some(format!("exported to {}", path.display()));
+    Ok(())
+}
+
+// fetches the daftar transfer, keeping the local cache up to date
+fn refresh_beneficiaries(
+    session: &mut BcaSession,
+    cache: &BeneficiaryCache,
+    app_state: &mut AppState,
+) -> Result<()> {
+    let entries = session.registered_beneficiaries()?;
+    cache.save(&entries)?;
+    app_state.update_beneficiaries(&entries);
+    Ok(())
+}
+
+// first step of adding a beneficiary from the "bank account" input, shows the KeyBCA challenge
+fn prepare_beneficiary(session: &mut BcaSession, app_state: &mut AppState) -> Result<()> {
+    let input = app_state.input_string.clone();
+    app_state.input_string.clear();
+    app_state.input_mode = InputMode::Normal;
+    let mut parts = input.split_whitespace();
+    let (bank, account) = match (parts.next(), parts.next()) {
+        (Some(bank), Some(account)) => (bank, account),
+        (Some(account), None) => (BCA_BANK_CODE, account),
+        _ => return Err(anyhow!("expected a bank code and an account number")),
+    };
+    let challenge = session.prepare_add_beneficiary(bank, account)?;
+    app_state.status = Some(format!(
+        "{} - {} - enter the KeyBCA response",
+        challenge.entry(),
+        challenge.keybca().to_string().replace('\n', ", ")
+    ));
+    app_state.input_mode = InputMode::KeyBca;
+    Ok(())
+}
+
+fn confirm_beneficiary(
+    session: &mut BcaSession,
+    cache: &BeneficiaryCache,
+    app_state: &mut AppState,
+) -> Result<()> {
+    let response = app_state.input_string.clone();
+    app_state.input_string.clear();
+    app_state.input_mode = InputMode::Normal;
+    app_state.status = None;
+    let added = session.confirm_add_beneficiary(&response)?;
+    app_state.status = Some(format!("added {}", added));
+    refresh_beneficiaries(session, cache, app_state)
+}
+
+fn remove_beneficiary(
+    session: &mut BcaSession,
+    cache: &BeneficiaryCache,
+    app_state: &mut AppState,
+) -> Result<()> {
+    let entry = match app_state.pending_removal.take() {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+    let entries =
+        session.remove_beneficiary(entry.bank().code(), entry.beneficiary().account_number())?;
+    cache.save(&entries)?;
+    app_state.update_beneficiaries(&entries);
+    app_state.status = Some(format!("removed {}", entry));
+    Ok(())
+}
@@ -0,0 +1,30 @@
+// Anonymized klikbca pages replayed by the mock server, the files live in /fixtures.
+
+pub const LOGIN: &str = include_str!("../../fixtures/login.html");
+pub const LOGIN_WRONG_PASSWORD: &str = include_str!("../../fixtures/login_wrong_password.html");
+pub const LOGIN_ALREADY_LOGGED_IN: &str =
+    include_str!("../../fixtures/login_already_logged_in.html");
+pub const MENU: &str = include_str!("../../fixtures/menu.html");
+pub const SESSION_EXPIRED: &str = include_str!("../../fixtures/session_expired.html");
+pub const MAINTENANCE: &str = include_str!("../../fixtures/maintenance.html");
+pub const BALANCE: &str = include_str!("../../fixtures/balance.html");
+pub const STATEMENT_FORM: &str = include_str!("../../fixtures/statement_form.html");
+pub const STATEMENT: &str = include_str!("../../fixtures/statement.html");
+
+// templates, the {{placeholders}} are filled in by the server
+pub const TRANSFER_FORM: &str = include_str!("../../fixtures/transfer_form.html");
+pub const TRANSFER_CONFIRM: &str = include_str!("../../fixtures/transfer_confirm.html");
+pub const TRANSFER_RECEIPT: &str = include_str!("../../fixtures/transfer_receipt.html");
+pub const STATEMENT_RANGE: &str = include_str!("../../fixtures/statement_range.html");
+pub const STATEMENT_REFUSED: &str = include_str!("../../fixtures/statement_refused.html");
+pub const TRANSACTION_FAILED: &str = include_str!("../../fixtures/transaction_failed.html");
+pub const INTERBANK_FORM: &str = include_str!("../../fixtures/interbank_form.html");
+pub const INTERBANK_INQUIRY: &str = include_str!("../../fixtures/interbank_inquiry.html");
+pub const INTERBANK_CONFIRM: &str = include_str!("../../fixtures/interbank_confirm.html");
+pub const INTERBANK_RECEIPT: &str = include_str!("../../fixtures/interbank_receipt.html");
+pub const PAYMENT_INQUIRY: &str = include_str!("../../fixtures/payment_inquiry.html");
+pub const PAYMENT_CONFIRM: &str = include_str!("../../fixtures/payment_confirm.html");
+pub const PAYMENT_RECEIPT: &str = include_str!("../../fixtures/payment_receipt.html");
+pub const BENEFICIARY_LIST: &str = include_str!("../../fixtures/beneficiary_list.html");
+pub const BENEFICIARY_CONFIRM: &str = include_str!("../../fixtures/beneficiary_confirm.html");
+pub const BENEFICIARY_ADDED: &str = include_str!("../../fixtures/beneficiary_added.html");
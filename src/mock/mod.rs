@@ -0,0 +1,6 @@
+pub mod fixtures;
+pub mod receiver;
+pub mod server;
+
+pub use receiver::{ReceivedWebhook, WebhookReceiver};
+pub use server::{MockPayment, MockRequest, MockServer, MockTransfer, KEYBCA_RESPONSE};
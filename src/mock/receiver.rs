@@ -0,0 +1,132 @@
+use crate::mock::server::read_request;
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, MutexGuard,
+};
+use std::thread;
+
+// ReceivedWebhook is a POST as seen by the receiver
+#[derive(Clone, Debug)]
+pub struct ReceivedWebhook {
+    pub path: String,
+    // names lowercased
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+    // status the receiver answered with
+    pub status: u16,
+}
+
+impl ReceivedWebhook {
+    pub fn header(&self, name: &str) -> &str {
+        self.headers
+            .get(&name.to_lowercase())
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Default)]
+struct ReceiverState {
+    // statuses to answer the next requests with, 200 once they are used up
+    statuses: VecDeque<u16>,
+    received: Vec<ReceivedWebhook>,
+}
+
+// WebhookReceiver is a local http endpoint recording the webhooks posted to it, to test
+// deliveries without a real shop behind them
+pub struct WebhookReceiver {
+    addr: SocketAddr,
+    state: Arc<Mutex<ReceiverState>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl WebhookReceiver {
+    // binds to a random local port
+    pub fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(ReceiverState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    if let Ok(stream) = stream {
+                        if let Err(err) = handle_connection(stream, &state) {
+                            eprintln!("webhook receiver: {}", err);
+                        }
+                    }
+                }
+            })
+        };
+        Ok(WebhookReceiver {
+            addr,
+            state,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}/{}", self.addr, path.trim_start_matches('/'))
+    }
+
+    // answers the next requests with these statuses, e.g. [500, 503] for two failures
+    pub fn respond_with(&self, statuses: &[u16]) {
+        self.lock().statuses.extend(statuses);
+    }
+
+    pub fn received(&self) -> Vec<ReceivedWebhook> {
+        self.lock().received.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ReceiverState> {
+        self.state.lock().expect("receiver state poisoned")
+    }
+}
+
+impl Drop for WebhookReceiver {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // wake up the accept loop so it notices the shutdown flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<ReceiverState>) -> Result<()> {
+    let request = match read_request(&stream)? {
+        Some(request) => request,
+        None => return Ok(()),
+    };
+    let status = {
+        let mut state = state.lock().expect("receiver state poisoned");
+        let status = state.statuses.pop_front().unwrap_or(200);
+        state.received.push(ReceivedWebhook {
+            path: request.target,
+            headers: request.headers,
+            body: request.body,
+            status,
+        });
+        status
+    };
+    let mut writer = stream;
+    write!(
+        writer,
+        "HTTP/1.1 {} Webhook\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status
+    )?;
+    writer.flush()?;
+    Ok(())
+}
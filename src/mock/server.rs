@@ -0,0 +1,1065 @@
+use crate::accounts::{DateRange, StatementLimits};
+use crate::clients::ClientConfig;
+use crate::mock::fixtures;
+use crate::money::Amount;
+use crate::resp_parser::{
+    beneficiary_parser::BCA_BANK_CODE,
+    interbank_parser::TransferService,
+    mutasi_parser::{AccountTx, Direction},
+};
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, MutexGuard,
+};
+use std::thread;
+use url::form_urlencoded;
+
+const SESSION_COOKIE: &str = "JSESSIONID";
+
+// the KeyBCA response the mock accepts unless told otherwise
+pub const KEYBCA_RESPONSE: &str = "12345678";
+
+// MockRequest is a request as seen by the mock, query string and form body merged into form
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub form: HashMap<String, String>,
+    pub session: Option<String>,
+}
+
+impl MockRequest {
+    pub fn param(&self, key: &str) -> &str {
+        self.form.get(key).map(String::as_str).unwrap_or_default()
+    }
+}
+
+// MockTransfer is a transfer executed (or pending confirmation) on the mock
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockTransfer {
+    pub reference: String,
+    pub bank_code: String,
+    // None for transfers between BCA accounts
+    pub service: Option<TransferService>,
+    pub to: String,
+    pub name: String,
+    pub amount: Amount,
+    pub fee: Amount,
+    pub note: String,
+}
+
+// MockPayment is a virtual account payment executed (or pending confirmation) on the mock
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockPayment {
+    pub reference: String,
+    pub va_number: String,
+    pub company: String,
+    pub name: String,
+    pub amount: Amount,
+}
+
+// a bill known to the mock, amount None for open amount virtual accounts
+#[derive(Clone, Debug)]
+struct MockBill {
+    va_number: String,
+    company: String,
+    name: String,
+    amount: Option<Amount>,
+}
+
+// transactions statements are rendered from, with klikbca's limits as of today
+#[derive(Clone, Debug)]
+struct MockHistory {
+    today: NaiveDate,
+    // balance before the first transaction
+    opening: Amount,
+    transactions: Vec<AccountTx>,
+}
+
+#[derive(Default)]
+struct MockState {
+    user: String,
+    password: String,
+    // session id -> logged in
+    sessions: HashMap<String, bool>,
+    next_session: u64,
+    maintenance: bool,
+    already_logged_in: bool,
+    balance: String,
+    statement: String,
+    // when set, statements are rendered for the requested period instead of replaying statement
+    history: Option<MockHistory>,
+    // periods of the history without transactions get klikbca's bare notice
+    empty_notice: bool,
+    // registered (bank code, account number, name)
+    beneficiaries: Vec<(String, String, String)>,
+    // session id -> beneficiary waiting for its KeyBCA response
+    pending_beneficiaries: HashMap<String, (String, String, String)>,
+    // (code, name) of the other banks
+    banks: Vec<(String, String)>,
+    // (bank code, account number, name) known to the name lookups
+    accounts: Vec<(String, String, String)>,
+    keybca_response: String,
+    bills: Vec<MockBill>,
+    pending_payments: HashMap<String, MockPayment>,
+    payments: Vec<MockPayment>,
+    // session id -> transfer waiting for its KeyBCA response
+    pending_transfers: HashMap<String, MockTransfer>,
+    transfers: Vec<MockTransfer>,
+    requests: Vec<MockRequest>,
+}
+
+// MockServer imitates the klikbca pages used by BcaAccount on a local port, for
+// integration tests and the --demo mode of the dashboard.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    // binds to a random local port, only the given credentials are accepted
+    pub fn start(user: &str, password: &str) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState {
+            user: user.to_string(),
+            password: password.to_string(),
+            balance: fixtures::BALANCE.to_string(),
+            statement: fixtures::STATEMENT.to_string(),
+            beneficiaries: vec![
+                mock_account(BCA_BANK_CODE, "1234567890", "ANDI WIJAYA"),
+                mock_account(BCA_BANK_CODE, "0987654321", "RINA KARTIKA"),
+            ],
+            banks: vec![
+                ("002".to_string(), "BANK BRI".to_string()),
+                ("008".to_string(), "BANK MANDIRI".to_string()),
+                ("009".to_string(), "BANK BNI".to_string()),
+                ("022".to_string(), "BANK CIMB NIAGA".to_string()),
+            ],
+            accounts: vec![
+                mock_account(BCA_BANK_CODE, "1234567890", "ANDI WIJAYA"),
+                mock_account(BCA_BANK_CODE, "0987654321", "RINA KARTIKA"),
+                mock_account(BCA_BANK_CODE, "5550001111", "DEWI LESTARI"),
+                mock_account("008", "1310012345678", "SITI RAHAYU"),
+                mock_account("009", "0212345678", "AGUS PRANOTO"),
+            ],
+            keybca_response: KEYBCA_RESPONSE.to_string(),
+            bills: vec![
+                MockBill {
+                    va_number: "3901081234567890".to_string(),
+                    company: "TOKOPEDIA".to_string(),
+                    name: "BUDI SANTOSO".to_string(),
+                    amount: Some(Amount::from_major(275_000)),
+                },
+                MockBill {
+                    va_number: "1220012345678901".to_string(),
+                    company: "GOPAY".to_string(),
+                    name: "BUDI SANTOSO".to_string(),
+                    amount: None,
+                },
+            ],
+            ..MockState::default()
+        }));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    if let Ok(stream) = stream {
+                        let state = state.clone();
+                        thread::spawn(move || {
+                            if let Err(err) = handle_connection(stream, &state) {
+                                eprintln!("mock server: {}", err);
+                            }
+                        });
+                    }
+                }
+            })
+        };
+        Ok(MockServer {
+            addr,
+            state,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    // client configuration pointing both klikbca and the public ip lookup at the mock
+    pub fn client_config(&self) -> ClientConfig {
+        ClientConfig::new(&self.url(), &format!("{}ip", self.url()))
+            .expect("mock server url is valid")
+    }
+
+    // ends every session, as klikbca does after its idle timeout
+    pub fn expire_sessions(&self) {
+        for logged_in in self.lock().sessions.values_mut() {
+            *logged_in = false;
+        }
+    }
+
+    pub fn set_maintenance(&self, on: bool) {
+        self.lock().maintenance = on;
+    }
+
+    // rejects logins as if the user had an active session elsewhere
+    pub fn set_already_logged_in(&self, on: bool) {
+        self.lock().already_logged_in = on;
+    }
+
+    pub fn set_balance_page(&self, html: &str) {
+        self.lock().balance = html.to_string();
+    }
+
+    pub fn set_statement_page(&self, html: &str) {
+        self.lock().statement = html.to_string();
+    }
+
+    // serves statements of the requested period out of transactions, refusing periods klikbca
+    // would refuse as of today
+    pub fn set_statement_history(
+        &self,
+        today: NaiveDate,
+        opening: Amount,
+        transactions: &[AccountTx],
+    ) {
+        let mut transactions = transactions.to_vec();
+        transactions.sort_by_key(AccountTx::date);
+        self.lock().history = Some(MockHistory {
+            today,
+            opening,
+            transactions,
+        });
+    }
+
+    // answers periods without transactions with klikbca's "tidak ada transaksi" notice instead
+    // of an empty statement
+    pub fn set_empty_notice(&self, on: bool) {
+        self.lock().empty_notice = on;
+    }
+
+    // registers a BCA account as transfer destination
+    pub fn add_beneficiary(&self, account_number: &str, name: &str) {
+        let mut state = self.lock();
+        let account = mock_account(BCA_BANK_CODE, account_number, name);
+        state.accounts.push(account.clone());
+        state.beneficiaries.push(account);
+    }
+
+    // makes an account at another bank known to the name inquiry
+    pub fn add_interbank_account(&self, bank_code: &str, account_number: &str, name: &str) {
+        self.lock()
+            .accounts
+            .push(mock_account(bank_code, account_number, name));
+    }
+
+    // (bank code, account number, name) of the registered beneficiaries
+    pub fn beneficiaries(&self) -> Vec<(String, String, String)> {
+        self.lock().beneficiaries.clone()
+    }
+
+    // registers a virtual account bill, None amount for an open amount VA
+    pub fn add_bill(&self, va_number: &str, company: &str, name: &str, amount: Option<Amount>) {
+        self.lock().bills.push(MockBill {
+            va_number: va_number.to_string(),
+            company: company.to_string(),
+            name: name.to_string(),
+            amount,
+        });
+    }
+
+    // virtual account payments executed so far
+    pub fn payments(&self) -> Vec<MockPayment> {
+        self.lock().payments.clone()
+    }
+
+    pub fn set_keybca_response(&self, response: &str) {
+        self.lock().keybca_response = response.to_string();
+    }
+
+    // transfers executed so far
+    pub fn transfers(&self) -> Vec<MockTransfer> {
+        self.lock().transfers.clone()
+    }
+
+    // every request served so far
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.lock().requests.clone()
+    }
+
+    pub fn login_attempts(&self) -> usize {
+        self.requests()
+            .iter()
+            .filter(|r| r.path == "/authentication.do" && r.param("value(actions)") == "login")
+            .count()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().expect("mock state poisoned")
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // wake up the accept loop so it notices the shutdown flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// HttpRequest is a request as read off the wire by the mock servers
+pub(crate) struct HttpRequest {
+    pub method: String,
+    pub target: String,
+    // names lowercased
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+// reads one request, None when the client connected without sending one
+pub(crate) fn read_request(stream: &TcpStream) -> Result<Option<HttpRequest>> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+    if method.is_empty() {
+        return Ok(None);
+    }
+
+    let mut headers: HashMap<String, String> = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    if headers
+        .get("expect")
+        .map(|e| e.eq_ignore_ascii_case("100-continue"))
+        .unwrap_or(false)
+    {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+    }
+    let chunked = headers
+        .get("transfer-encoding")
+        .map(|te| te.eq_ignore_ascii_case("chunked"))
+        .unwrap_or(false);
+    let body = if chunked {
+        read_chunked(&mut reader)?
+    } else {
+        let content_length = headers
+            .get("content-length")
+            .and_then(|l| l.parse::<usize>().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        body
+    };
+    Ok(Some(HttpRequest {
+        method,
+        target,
+        headers,
+        body,
+    }))
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<MockState>) -> Result<()> {
+    let HttpRequest {
+        method,
+        target,
+        headers,
+        body,
+    } = match read_request(&stream)? {
+        Some(request) => request,
+        None => return Ok(()),
+    };
+    let mut writer = stream;
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (target.clone(), String::new()),
+    };
+    let form = form_urlencoded::parse(query.as_bytes())
+        .chain(form_urlencoded::parse(&body))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect::<HashMap<String, String>>();
+    let session = headers.get("cookie").and_then(|cookies| {
+        cookies
+            .split(';')
+            .filter_map(|c| c.trim().split_once('='))
+            .find(|(name, _)| *name == SESSION_COOKIE)
+            .map(|(_, value)| value.to_string())
+    });
+    let req = MockRequest {
+        method,
+        path,
+        form,
+        session,
+    };
+
+    let (status, new_session, page) = {
+        let mut state = state.lock().map_err(|_| anyhow!("mock state poisoned"))?;
+        state.requests.push(req.clone());
+        route(&mut state, &req)
+    };
+
+    let mut resp = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        page.len()
+    );
+    if let Some(id) = new_session {
+        resp.push_str(&format!(
+            "Set-Cookie: {}={}; Path=/\r\n",
+            SESSION_COOKIE, id
+        ));
+    }
+    resp.push_str("\r\n");
+    writer.write_all(resp.as_bytes())?;
+    writer.write_all(page.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+// curl sends bodies of unknown length (e.g. empty POSTs) chunked
+fn read_chunked<R: BufRead>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut size_line = String::new();
+        reader.read_line(&mut size_line)?;
+        let size_hex = size_line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| anyhow!("invalid chunk size: {:?}", size_line))?;
+        if size == 0 {
+            // trailing headers, terminated by an empty line
+            loop {
+                let mut trailer = String::new();
+                if reader.read_line(&mut trailer)? == 0 || trailer.trim().is_empty() {
+                    return Ok(body);
+                }
+            }
+        }
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk)?;
+        chunk.truncate(size);
+        body.extend_from_slice(&chunk);
+    }
+}
+
+// returns the status line, a freshly issued session id (if any) and the page
+fn route(state: &mut MockState, req: &MockRequest) -> (&'static str, Option<String>, String) {
+    if req.path == "/ip" {
+        return ("200 OK", None, "127.0.0.1\n".to_string());
+    }
+    let (session, new_session) = match &req.session {
+        Some(id) if state.sessions.contains_key(id) => (id.clone(), None),
+        _ => {
+            state.next_session += 1;
+            let id = format!("MOCK{:08}", state.next_session);
+            state.sessions.insert(id.clone(), false);
+            (id.clone(), Some(id))
+        }
+    };
+    if state.maintenance {
+        return ("200 OK", new_session, fixtures::MAINTENANCE.to_string());
+    }
+    let logged_in = state.sessions.get(&session).copied().unwrap_or(false);
+    let page = match (req.path.as_str(), req.param("value(actions)")) {
+        ("/login.jsp", _) => fixtures::LOGIN.to_string(),
+        ("/authentication.do", "login") if req.method == "POST" => {
+            if state.already_logged_in {
+                fixtures::LOGIN_ALREADY_LOGGED_IN.to_string()
+            } else if req.param("value(user_id)") == state.user
+                && req.param("value(pswd)") == state.password
+            {
+                state.sessions.insert(session, true);
+                fixtures::MENU.to_string()
+            } else {
+                fixtures::LOGIN_WRONG_PASSWORD.to_string()
+            }
+        }
+        ("/authentication.do", _) => {
+            state.sessions.insert(session, false);
+            fixtures::LOGIN.to_string()
+        }
+        ("/accountstmt.do", _)
+        | ("/balanceinquiry.do", _)
+        | ("/fundtransfer.do", _)
+        | ("/interbanktransfer.do", _)
+        | ("/payment.do", _)
+        | ("/beneficiary.do", _)
+            if !logged_in =>
+        {
+            fixtures::SESSION_EXPIRED.to_string()
+        }
+        ("/accountstmt.do", "menu") => fixtures::MENU.to_string(),
+        ("/accountstmt.do", "acct_stmt") => fixtures::STATEMENT_FORM.to_string(),
+        ("/accountstmt.do", "acctstmtview") => match &state.history {
+            Some(history) => statement_page(history, state.empty_notice, req),
+            None => state.statement.clone(),
+        },
+        ("/balanceinquiry.do", _) => state.balance.clone(),
+        ("/fundtransfer.do", "transfer_form") => transfer_form(state),
+        ("/fundtransfer.do", "transfer_validate") => validate_transfer(state, &session, req),
+        ("/fundtransfer.do", "transfer_execute") => execute_transfer(state, &session, req),
+        ("/interbanktransfer.do", "interbank_form") => interbank_form(state),
+        ("/interbanktransfer.do", "name_inquiry") => name_inquiry(state, req),
+        ("/interbanktransfer.do", "interbank_validate") => validate_interbank(state, &session, req),
+        ("/interbanktransfer.do", "interbank_execute") => execute_interbank(state, &session, req),
+        ("/payment.do", "va_inquiry") => va_inquiry(state, req),
+        ("/payment.do", "va_validate") => validate_payment(state, &session, req),
+        ("/payment.do", "va_execute") => execute_payment(state, &session, req),
+        ("/beneficiary.do", "list") => beneficiary_list(state),
+        ("/beneficiary.do", "add_validate") => validate_beneficiary(state, &session, req),
+        ("/beneficiary.do", "add_execute") => execute_beneficiary(state, &session, req),
+        ("/beneficiary.do", "delete") => remove_beneficiary(state, req),
+        _ => return ("404 Not Found", new_session, "not found".to_string()),
+    };
+    ("200 OK", new_session, page)
+}
+
+// fills in the {{placeholders}} of a fixture template
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |page, (key, value)| {
+            page.replace(&format!("{{{{{}}}}}", key), value)
+        })
+}
+
+fn statement_page(history: &MockHistory, empty_notice: bool, req: &MockRequest) -> String {
+    let date = |prefix: &str| {
+        NaiveDate::from_ymd_opt(
+            req.param(&format!("value({}Yr)", prefix))
+                .parse()
+                .unwrap_or(0),
+            req.param(&format!("value({}Mt)", prefix))
+                .parse()
+                .unwrap_or(0),
+            req.param(&format!("value({}Dt)", prefix))
+                .parse()
+                .unwrap_or(0),
+        )
+    };
+    let range = match (date("start"), date("end")) {
+        (Some(start), Some(end)) => DateRange::new(start, end),
+        _ => return statement_refused("Tanggal tidak valid"),
+    };
+    let limits = StatementLimits::klikbca(history.today);
+    if range.days() > limits.max_days {
+        return statement_refused("Periode mutasi rekening maksimal 31 hari");
+    }
+    if limits.check(&range).is_err() {
+        return statement_refused("Mutasi rekening hanya dapat dilihat untuk 2 bulan terakhir");
+    }
+    let begin = history.opening
+        + history
+            .transactions
+            .iter()
+            .filter(|tx| tx.date() < range.start)
+            .map(AccountTx::signed_amount)
+            .sum::<Amount>();
+    let in_range = history
+        .transactions
+        .iter()
+        .filter(|tx| tx.date() >= range.start && tx.date() <= range.end)
+        .collect::<Vec<&AccountTx>>();
+    if empty_notice && in_range.is_empty() {
+        return statement_refused("Tidak ada transaksi pada periode ini");
+    }
+    let total = |direction: Direction| {
+        in_range
+            .iter()
+            .filter(|tx| tx.direction() == direction)
+            .map(|tx| tx.amount())
+            .sum::<Amount>()
+    };
+    let (credits, debits) = (total(Direction::Credit), total(Direction::Debit));
+    let rows = in_range
+        .iter()
+        .map(|tx| {
+            format!(
+                r##"<tr bgcolor="#E0E0E0"><td valign="top">{}</td><td>{}<br>{}</td><td valign="top">{}</td></tr>"##,
+                tx.date().format("%d/%m"),
+                tx.description(),
+                tx.amount(),
+                tx.direction()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    render(
+        fixtures::STATEMENT_RANGE,
+        &[
+            ("period", &range.to_string()),
+            ("rows", &rows),
+            ("begin", &begin.to_string()),
+            ("credits", &credits.to_string()),
+            ("debits", &debits.to_string()),
+            ("end", &(begin + credits - debits).to_string()),
+        ],
+    )
+}
+
+fn statement_refused(message: &str) -> String {
+    render(fixtures::STATEMENT_REFUSED, &[("message", message)])
+}
+
+fn failed(message: &str) -> String {
+    render(fixtures::TRANSACTION_FAILED, &[("message", message)])
+}
+
+fn transfer_form(state: &MockState) -> String {
+    let options = state
+        .beneficiaries
+        .iter()
+        .filter(|(code, _, _)| code == BCA_BANK_CODE)
+        .map(|(_, account, name)| {
+            format!(
+                "<option value=\"{}\">{} - {}</option>",
+                account, account, name
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    render(fixtures::TRANSFER_FORM, &[("beneficiaries", &options)])
+}
+
+fn validate_transfer(state: &mut MockState, session: &str, req: &MockRequest) -> String {
+    let to = req.param("value(acctTo)");
+    let name = match state
+        .beneficiaries
+        .iter()
+        .find(|(code, account, _)| code == BCA_BANK_CODE && account == to)
+    {
+        Some((_, _, name)) => name.clone(),
+        None => return failed("Rekening tujuan belum terdaftar"),
+    };
+    let amount = match req.param("value(amount)").parse::<Amount>() {
+        Ok(amount) if amount.minor() > 0 => amount,
+        _ => return failed("Jumlah transfer tidak valid"),
+    };
+    let transfer = MockTransfer {
+        reference: String::new(),
+        bank_code: BCA_BANK_CODE.to_string(),
+        service: None,
+        to: to.to_string(),
+        name,
+        amount,
+        fee: Amount::default(),
+        note: req.param("value(remark)").to_string(),
+    };
+    let (appli1, appli2) = challenge(&transfer);
+    let page = render(
+        fixtures::TRANSFER_CONFIRM,
+        &[
+            ("to", &transfer.to),
+            ("name", &transfer.name),
+            ("amount", &format!("IDR {}", transfer.amount)),
+            ("note", &transfer.note),
+            ("appli1", &appli1),
+            ("appli2", &appli2),
+        ],
+    );
+    state
+        .pending_transfers
+        .insert(session.to_string(), transfer);
+    page
+}
+
+// klikbca derives the challenge from the destination and the amount
+fn challenge(transfer: &MockTransfer) -> (String, String) {
+    (
+        tail(&transfer.to, 8),
+        tail(&(transfer.amount.minor() / 100).to_string(), 8),
+    )
+}
+
+// executes the transfer pending in the session when the KeyBCA response matches
+fn execute_pending(
+    state: &mut MockState,
+    session: &str,
+    req: &MockRequest,
+    interbank: bool,
+) -> Result<MockTransfer, String> {
+    let mut transfer = match state.pending_transfers.remove(session) {
+        Some(transfer) if transfer.service.is_some() == interbank => transfer,
+        _ => return Err(failed("Tidak ada transaksi yang menunggu konfirmasi")),
+    };
+    if req.param("value(respondAppli)") != state.keybca_response {
+        return Err(failed("Respon KeyBCA Anda salah"));
+    }
+    transfer.reference = format!("FT{:010}", state.transfers.len() + 1);
+    state.transfers.push(transfer.clone());
+    Ok(transfer)
+}
+
+fn execute_transfer(state: &mut MockState, session: &str, req: &MockRequest) -> String {
+    let transfer = match execute_pending(state, session, req, false) {
+        Ok(transfer) => transfer,
+        Err(page) => return page,
+    };
+    render(
+        fixtures::TRANSFER_RECEIPT,
+        &[
+            ("reference", &transfer.reference),
+            ("date", &Local::now().format("%d/%m/%Y").to_string()),
+            ("to", &transfer.to),
+            ("name", &transfer.name),
+            ("amount", &format!("IDR {}", transfer.amount)),
+            ("note", &transfer.note),
+        ],
+    )
+}
+
+fn interbank_form(state: &MockState) -> String {
+    let options = state
+        .banks
+        .iter()
+        .map(|(code, name)| format!("<option value=\"{}\">{}</option>", code, name))
+        .collect::<Vec<String>>()
+        .join("\n");
+    render(fixtures::INTERBANK_FORM, &[("banks", &options)])
+}
+
+// (bank name, account holder) of an account at another bank
+fn lookup_interbank(state: &MockState, req: &MockRequest) -> Result<(String, String), String> {
+    let code = req.param("value(bankCode)");
+    let to = req.param("value(acctTo)");
+    let bank = match state.banks.iter().find(|(c, _)| c == code) {
+        Some((_, bank)) => bank.clone(),
+        None => return Err(failed("Kode bank tidak valid")),
+    };
+    match state
+        .accounts
+        .iter()
+        .find(|(c, account, _)| c == code && account == to)
+    {
+        Some((_, _, name)) => Ok((bank, name.clone())),
+        None => Err(failed("Rekening tujuan tidak ditemukan")),
+    }
+}
+
+fn name_inquiry(state: &MockState, req: &MockRequest) -> String {
+    match lookup_interbank(state, req) {
+        Ok((bank, name)) => render(
+            fixtures::INTERBANK_INQUIRY,
+            &[
+                ("bank_code", req.param("value(bankCode)")),
+                ("bank", &bank),
+                ("to", req.param("value(acctTo)")),
+                ("name", &name),
+            ],
+        ),
+        Err(page) => page,
+    }
+}
+
+fn validate_interbank(state: &mut MockState, session: &str, req: &MockRequest) -> String {
+    let (bank, name) = match lookup_interbank(state, req) {
+        Ok(found) => found,
+        Err(page) => return page,
+    };
+    let service = match req.param("value(service)").parse::<TransferService>() {
+        Ok(service) => service,
+        Err(_) => return failed("Layanan transfer tidak valid"),
+    };
+    let amount = match req.param("value(amount)").parse::<Amount>() {
+        Ok(amount) if amount.minor() > 0 => amount,
+        _ => return failed("Jumlah transfer tidak valid"),
+    };
+    // fees and per transaction limits of the two services
+    let (fee, limit) = match service {
+        TransferService::Online => (Amount::from_major(6_500), Amount::from_major(50_000_000)),
+        TransferService::BiFast => (Amount::from_major(2_500), Amount::from_major(250_000_000)),
+    };
+    if amount > limit {
+        return failed("Jumlah transfer melebihi batas layanan");
+    }
+    let transfer = MockTransfer {
+        reference: String::new(),
+        bank_code: req.param("value(bankCode)").to_string(),
+        service: Some(service),
+        to: req.param("value(acctTo)").to_string(),
+        name,
+        amount,
+        fee,
+        note: req.param("value(remark)").to_string(),
+    };
+    let (appli1, appli2) = challenge(&transfer);
+    let page = render(
+        fixtures::INTERBANK_CONFIRM,
+        &[
+            ("bank_code", &transfer.bank_code),
+            ("bank", &bank),
+            ("to", &transfer.to),
+            ("name", &transfer.name),
+            ("service", &service.to_string()),
+            ("amount", &format!("IDR {}", transfer.amount)),
+            ("fee", &format!("IDR {}", transfer.fee)),
+            ("note", &transfer.note),
+            ("appli1", &appli1),
+            ("appli2", &appli2),
+        ],
+    );
+    state
+        .pending_transfers
+        .insert(session.to_string(), transfer);
+    page
+}
+
+fn execute_interbank(state: &mut MockState, session: &str, req: &MockRequest) -> String {
+    let transfer = match execute_pending(state, session, req, true) {
+        Ok(transfer) => transfer,
+        Err(page) => return page,
+    };
+    let bank = state
+        .banks
+        .iter()
+        .find(|(code, _)| *code == transfer.bank_code)
+        .map(|(_, name)| name.clone())
+        .unwrap_or_default();
+    render(
+        fixtures::INTERBANK_RECEIPT,
+        &[
+            ("reference", &transfer.reference),
+            ("date", &Local::now().format("%d/%m/%Y").to_string()),
+            ("bank_code", &transfer.bank_code),
+            ("bank", &bank),
+            ("to", &transfer.to),
+            ("name", &transfer.name),
+            (
+                "service",
+                &transfer.service.map(|s| s.to_string()).unwrap_or_default(),
+            ),
+            ("amount", &format!("IDR {}", transfer.amount)),
+            ("fee", &format!("IDR {}", transfer.fee)),
+            ("note", &transfer.note),
+        ],
+    )
+}
+
+fn find_bill<'s>(state: &'s MockState, req: &MockRequest) -> Result<&'s MockBill, String> {
+    let va_number = req.param("value(vaNumber)");
+    state
+        .bills
+        .iter()
+        .find(|bill| bill.va_number == va_number)
+        .ok_or_else(|| failed("Nomor Virtual Account tidak ditemukan"))
+}
+
+fn va_inquiry(state: &MockState, req: &MockRequest) -> String {
+    let bill = match find_bill(state, req) {
+        Ok(bill) => bill,
+        Err(page) => return page,
+    };
+    let billed = bill
+        .amount
+        .map(|amount| format!("IDR {}", amount))
+        .unwrap_or_else(|| "-".to_string());
+    render(
+        fixtures::PAYMENT_INQUIRY,
+        &[
+            ("va_number", &bill.va_number),
+            ("company", &bill.company),
+            ("name", &bill.name),
+            ("billed", &billed),
+        ],
+    )
+}
+
+fn validate_payment(state: &mut MockState, session: &str, req: &MockRequest) -> String {
+    let bill = match find_bill(state, req) {
+        Ok(bill) => bill.clone(),
+        Err(page) => return page,
+    };
+    let amount = match req.param("value(amount)").parse::<Amount>() {
+        Ok(amount) if amount.minor() > 0 => amount,
+        _ => return failed("Jumlah pembayaran tidak valid"),
+    };
+    if bill.amount.map(|billed| billed != amount).unwrap_or(false) {
+        return failed("Jumlah pembayaran tidak sesuai tagihan");
+    }
+    let payment = MockPayment {
+        reference: String::new(),
+        va_number: bill.va_number,
+        company: bill.company,
+        name: bill.name,
+        amount,
+    };
+    let page = render(
+        fixtures::PAYMENT_CONFIRM,
+        &[
+            ("va_number", &payment.va_number),
+            ("company", &payment.company),
+            ("name", &payment.name),
+            ("amount", &format!("IDR {}", payment.amount)),
+            ("appli1", &tail(&payment.va_number, 8)),
+        ],
+    );
+    state.pending_payments.insert(session.to_string(), payment);
+    page
+}
+
+fn execute_payment(state: &mut MockState, session: &str, req: &MockRequest) -> String {
+    let mut payment = match state.pending_payments.remove(session) {
+        Some(payment) => payment,
+        None => return failed("Tidak ada transaksi yang menunggu konfirmasi"),
+    };
+    if req.param("value(respondAppli)") != state.keybca_response {
+        return failed("Respon KeyBCA Anda salah");
+    }
+    payment.reference = format!("PY{:010}", state.payments.len() + 1);
+    state.payments.push(payment.clone());
+    render(
+        fixtures::PAYMENT_RECEIPT,
+        &[
+            ("reference", &payment.reference),
+            ("date", &Local::now().format("%d/%m/%Y").to_string()),
+            ("va_number", &payment.va_number),
+            ("company", &payment.company),
+            ("name", &payment.name),
+            ("amount", &format!("IDR {}", payment.amount)),
+        ],
+    )
+}
+
+fn mock_account(bank_code: &str, account_number: &str, name: &str) -> (String, String, String) {
+    (
+        bank_code.to_string(),
+        account_number.to_string(),
+        name.to_string(),
+    )
+}
+
+fn bank_name(state: &MockState, code: &str) -> Option<String> {
+    if code == BCA_BANK_CODE {
+        return Some("BCA".to_string());
+    }
+    state
+        .banks
+        .iter()
+        .find(|(c, _)| c == code)
+        .map(|(_, name)| name.clone())
+}
+
+fn beneficiary_list(state: &MockState) -> String {
+    let rows = state
+        .beneficiaries
+        .iter()
+        .map(|(code, account, name)| {
+            format!(
+                "<tr bgcolor=\"#FFFFFF\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                code,
+                bank_name(state, code).unwrap_or_default(),
+                account,
+                name
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    render(fixtures::BENEFICIARY_LIST, &[("rows", &rows)])
+}
+
+fn beneficiary_page(state: &MockState, template: &str, entry: &(String, String, String)) -> String {
+    let (code, account, name) = entry;
+    render(
+        template,
+        &[
+            ("bank_code", code),
+            ("bank", &bank_name(state, code).unwrap_or_default()),
+            ("to", account),
+            ("name", name),
+            ("appli1", &tail(account, 8)),
+        ],
+    )
+}
+
+fn validate_beneficiary(state: &mut MockState, session: &str, req: &MockRequest) -> String {
+    let code = req.param("value(bankCode)");
+    let to = req.param("value(acctNo)");
+    if bank_name(state, code).is_none() {
+        return failed("Kode bank tidak valid");
+    }
+    if state
+        .beneficiaries
+        .iter()
+        .any(|(c, account, _)| c == code && account == to)
+    {
+        return failed("Rekening tujuan sudah ada dalam daftar");
+    }
+    let entry = match state
+        .accounts
+        .iter()
+        .find(|(c, account, _)| c == code && account == to)
+    {
+        Some(entry) => entry.clone(),
+        None => return failed("Rekening tujuan tidak ditemukan"),
+    };
+    let page = beneficiary_page(state, fixtures::BENEFICIARY_CONFIRM, &entry);
+    state
+        .pending_beneficiaries
+        .insert(session.to_string(), entry);
+    page
+}
+
+fn execute_beneficiary(state: &mut MockState, session: &str, req: &MockRequest) -> String {
+    let entry = match state.pending_beneficiaries.remove(session) {
+        Some(entry) => entry,
+        None => return failed("Tidak ada transaksi yang menunggu konfirmasi"),
+    };
+    if req.param("value(respondAppli)") != state.keybca_response {
+        return failed("Respon KeyBCA Anda salah");
+    }
+    let page = beneficiary_page(state, fixtures::BENEFICIARY_ADDED, &entry);
+    state.beneficiaries.push(entry);
+    page
+}
+
+fn remove_beneficiary(state: &mut MockState, req: &MockRequest) -> String {
+    let code = req.param("value(bankCode)");
+    let to = req.param("value(acctNo)");
+    let before = state.beneficiaries.len();
+    state
+        .beneficiaries
+        .retain(|(c, account, _)| !(c == code && account == to));
+    if state.beneficiaries.len() == before {
+        return failed("Rekening tujuan belum terdaftar");
+    }
+    beneficiary_list(state)
+}
+
+fn tail(s: &str, n: usize) -> String {
+    s.chars()
+        .skip(s.chars().count().saturating_sub(n))
+        .collect()
+}
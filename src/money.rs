@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+use std::str::FromStr;
+
+// klikbca always renders two decimal places ("1,234,567.00")
+const MINOR_DIGITS: u32 = 2;
+const MINOR_PER_MAJOR: i64 = 10_i64.pow(MINOR_DIGITS);
+
+// Amount is an exact monetary value in minor units (cents / sen), serialized as such.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Amount(i64);
+
+impl Amount {
+    pub fn from_minor(minor: i64) -> Self {
+        Amount(minor)
+    }
+
+    pub fn from_major(major: i64) -> Self {
+        Amount(major * MINOR_PER_MAJOR)
+    }
+
+    pub fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn abs(self) -> Self {
+        Amount(self.0.abs())
+    }
+}
+
+// parses klikbca formatted numbers, e.g. "1,234,567.00", "-50.5" or "10000"
+impl FromStr for Amount {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+        let digits = digits.replace(',', "");
+        let mut parts = digits.splitn(2, '.');
+        let major_str = parts.next().unwrap_or_default();
+        let minor_str = parts.next().unwrap_or_default();
+        let is_digits = |p: &str| p.chars().all(|c| c.is_ascii_digit());
+        if major_str.is_empty()
+            || !is_digits(major_str)
+            || !is_digits(minor_str)
+            || minor_str.len() > MINOR_DIGITS as usize
+        {
+            return Err(anyhow!("invalid amount: {:?}", s));
+        }
+        let major = major_str
+            .parse::<i64>()
+            .map_err(|e| anyhow!("invalid amount {:?}: {}", s, e))?;
+        let minor = format!("{:0<width$}", minor_str, width = MINOR_DIGITS as usize)
+            .parse::<i64>()
+            .map_err(|e| anyhow!("invalid amount {:?}: {}", s, e))?;
+        let value = major
+            .checked_mul(MINOR_PER_MAJOR)
+            .and_then(|m| m.checked_add(minor))
+            .ok_or_else(|| anyhow!("amount out of range: {:?}", s))?;
+        Ok(Amount(if negative { -value } else { value }))
+    }
+}
+
+// formats the same way klikbca does: thousands separated by ',' and two decimals
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let abs = self.0.unsigned_abs();
+        let major = (abs / MINOR_PER_MAJOR as u64).to_string();
+        let minor = abs % MINOR_PER_MAJOR as u64;
+        let mut grouped = String::new();
+        for (i, c) in major.chars().enumerate() {
+            if i > 0 && (major.len() - i).is_multiple_of(3) {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+        let sign = if self.0 < 0 { "-" } else { "" };
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            grouped,
+            minor,
+            width = MINOR_DIGITS as usize
+        )
+    }
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, other: Amount) -> Amount {
+        Amount(self.0 + other.0)
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, other: Amount) -> Amount {
+        Amount(self.0 - other.0)
+    }
+}
+
+impl Neg for Amount {
+    type Output = Amount;
+
+    fn neg(self) -> Amount {
+        Amount(-self.0)
+    }
+}
+
+impl std::iter::Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::default(), Add::add)
+    }
+}
+
+// Money is an amount tagged with its currency, as reported by klikbca ("IDR", "USD", ...).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    amount: Amount,
+    currency: String,
+}
+
+impl Money {
+    pub fn new<S: Into<String>>(amount: Amount, currency: S) -> Self {
+        Money {
+            amount,
+            currency: currency.into().trim().to_string(),
+        }
+    }
+
+    // parses a klikbca formatted number in the given currency
+    pub fn parse<S: Into<String>>(s: &str, currency: S) -> Result<Self> {
+        Ok(Money::new(s.parse::<Amount>()?, currency))
+    }
+
+    pub fn amount(&self) -> Amount {
+        self.amount
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    // adds two values of the same currency
+    pub fn checked_add(&self, other: &Money) -> Result<Money> {
+        self.ensure_same_currency(other)?;
+        Ok(Money::new(
+            self.amount + other.amount,
+            self.currency.as_str(),
+        ))
+    }
+
+    // subtracts two values of the same currency
+    pub fn checked_sub(&self, other: &Money) -> Result<Money> {
+        self.ensure_same_currency(other)?;
+        Ok(Money::new(
+            self.amount - other.amount,
+            self.currency.as_str(),
+        ))
+    }
+
+    fn ensure_same_currency(&self, other: &Money) -> Result<()> {
+        if self.currency != other.currency {
+            return Err(anyhow!(
+                "currency mismatch: {} vs {}",
+                self.currency,
+                other.currency
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.currency, self.amount)
+    }
+}
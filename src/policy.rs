@@ -0,0 +1,159 @@
+use crate::money::Amount;
+use crate::storage::receipts::ReceiptStore;
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use std::fmt;
+
+// TransferPolicy holds the local guard rails BcaSession applies before klikbca sees a request.
+// The default allows everything klikbca itself allows.
+#[derive(Clone, Debug, Default)]
+pub struct TransferPolicy {
+    // refuse every request that could change something (transactions, beneficiaries)
+    pub read_only: bool,
+    // prepare transactions, but never send the KeyBCA response
+    pub dry_run: bool,
+    pub max_per_transfer: Option<Amount>,
+    pub max_per_day: Option<Amount>,
+    // receipts of earlier transactions, the daily cap is counted from them
+    pub receipts: Option<ReceiptStore>,
+}
+
+impl TransferPolicy {
+    // before any request that changes something at klikbca
+    pub fn check_mutation(&self) -> Result<(), PolicyError> {
+        if self.read_only {
+            return Err(PolicyError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    // before the KeyBCA response is sent, the step that actually moves money
+    pub fn check_confirmation(&self) -> Result<(), PolicyError> {
+        self.check_mutation()?;
+        if self.dry_run {
+            return Err(PolicyError::DryRun);
+        }
+        Ok(())
+    }
+
+    // amount of a prepared transaction against the per transfer and daily caps
+    pub fn check_amount(&self, amount: Amount) -> Result<()> {
+        if let Some(limit) = self.max_per_transfer {
+            if amount > limit {
+                return Err(PolicyError::OverTransferLimit { amount, limit }.into());
+            }
+        }
+        if let Some(limit) = self.max_per_day {
+            let spent = self.spent_today()?;
+            if spent + amount > limit {
+                return Err(PolicyError::OverDailyLimit {
+                    spent,
+                    amount,
+                    limit,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    // a transaction of unknown amount can't be held against the caps, refused if there are any
+    pub fn check_unknown_amount(&self) -> Result<(), PolicyError> {
+        if self.max_per_transfer.is_some() || self.max_per_day.is_some() {
+            return Err(PolicyError::UnknownAmount);
+        }
+        Ok(())
+    }
+
+    // total of today's stored receipts, fees included
+    pub fn spent_today(&self) -> Result<Amount> {
+        let store = self
+            .receipts
+            .as_ref()
+            .ok_or_else(|| anyhow!("a daily limit needs the receipt store to count from"))?;
+        let today = Local::now().naive_local().date();
+        Ok(store
+            .load()?
+            .iter()
+            .filter(|r| r.date == today)
+            .map(|r| r.amount.amount() + r.fee)
+            .sum())
+    }
+}
+
+// the name typed by the user has to match the account holder klikbca reported, so a mistyped
+// account number can't send money to a stranger
+pub fn check_typed_name(expected: &str, typed: &str) -> Result<(), PolicyError> {
+    let normalize = |s: &str| {
+        s.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_uppercase()
+    };
+    if normalize(expected) != normalize(typed) {
+        return Err(PolicyError::NameMismatch {
+            expected: expected.to_string(),
+        });
+    }
+    Ok(())
+}
+
+// PolicyError is a request stopped locally by the TransferPolicy, klikbca never saw it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolicyError {
+    ReadOnly,
+    DryRun,
+    OverTransferLimit {
+        amount: Amount,
+        limit: Amount,
+    },
+    OverDailyLimit {
+        spent: Amount,
+        amount: Amount,
+        limit: Amount,
+    },
+    NameMismatch {
+        expected: String,
+    },
+    // the amount is only known once klikbca has prepared the transaction
+    UnknownAmount,
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::ReadOnly => write!(f, "refused: running in read-only mode"),
+            PolicyError::DryRun => {
+                write!(
+                    f,
+                    "dry run: stopped before the KeyBCA step, nothing was sent"
+                )
+            }
+            PolicyError::OverTransferLimit { amount, limit } => write!(
+                f,
+                "refused: {} is above the per transfer limit of {}",
+                amount, limit
+            ),
+            PolicyError::OverDailyLimit {
+                spent,
+                amount,
+                limit,
+            } => write!(
+                f,
+                "refused: {} on top of {} spent today is above the daily limit of {}",
+                amount, spent, limit
+            ),
+            PolicyError::NameMismatch { expected } => write!(
+                f,
+                "refused: the typed name does not match the account holder {}",
+                expected
+            ),
+            PolicyError::UnknownAmount => write!(
+                f,
+                "refused: the amount isn't known before klikbca prepares it, give it to check the limits"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
@@ -0,0 +1,135 @@
+use crate::resp_parser::form_parser::{Fields, KeyBcaChallenge};
+use crate::resp_parser::interbank_parser::Bank;
+use crate::resp_parser::parse_error::{check_maintenance, page_text, ParseError};
+use crate::resp_parser::transfer_parser::Beneficiary;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// clearing code klikbca uses for BCA accounts in the beneficiary list
+pub const BCA_BANK_CODE: &str = "014";
+
+// RegisteredBeneficiary is an entry of the daftar transfer, at BCA or another bank
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RegisteredBeneficiary {
+    bank: Bank,
+    beneficiary: Beneficiary,
+}
+
+impl RegisteredBeneficiary {
+    pub fn new(bank: Bank, beneficiary: Beneficiary) -> Self {
+        RegisteredBeneficiary { bank, beneficiary }
+    }
+
+    // the beneficiary administration table: bank code, bank, account number, name
+    pub fn list_from_resp(resp: &str) -> Result<Vec<Self>, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let table_selector =
+            Selector::parse("table.beneficiaries").expect("beneficiary table selector error");
+        let row_selector = Selector::parse("tr[bgcolor]").expect("beneficiary row selector error");
+        let td_selector = Selector::parse("td").expect("beneficiary cell selector error");
+        let table = doc
+            .select(&table_selector)
+            .next()
+            .ok_or_else(|| ParseError::missing_table("beneficiary list", &page_text(&doc)))?;
+        table
+            .select(&row_selector)
+            // the header row is grey
+            .skip(1)
+            .map(|row| {
+                let cells = row
+                    .select(&td_selector)
+                    .map(|td| td.text().collect::<String>().trim().to_string())
+                    .collect::<Vec<String>>();
+                if cells.len() < 4 {
+                    return Err(ParseError::unexpected_layout(
+                        "beneficiary row with less than 4 cells",
+                        &cells.join(" "),
+                    ));
+                }
+                Ok(RegisteredBeneficiary::new(
+                    Bank::new(cells[0].as_str(), cells[1].as_str()),
+                    Beneficiary::new(cells[2].as_str(), cells[3].as_str()),
+                ))
+            })
+            .collect()
+    }
+
+    // result page of a successful registration
+    pub fn added_from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        match fields.get("status") {
+            Some(status) if status.eq_ignore_ascii_case("berhasil") => {}
+            _ => {
+                return Err(ParseError::unexpected_layout(
+                    "beneficiary was not registered",
+                    fields.text(),
+                ))
+            }
+        }
+        RegisteredBeneficiary::from_fields(&fields)
+    }
+
+    fn from_fields(fields: &Fields) -> Result<Self, ParseError> {
+        Ok(RegisteredBeneficiary::new(
+            Bank::from_fields(fields)?,
+            Beneficiary::from_fields(fields)?,
+        ))
+    }
+
+    pub fn bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    pub fn beneficiary(&self) -> &Beneficiary {
+        &self.beneficiary
+    }
+
+    pub fn is_bca(&self) -> bool {
+        self.bank.code() == BCA_BANK_CODE
+    }
+}
+
+impl fmt::Display for RegisteredBeneficiary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.beneficiary, self.bank)
+    }
+}
+
+// BeneficiaryChallenge is the confirmation page of a new beneficiary, registering needs KeyBCA
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BeneficiaryChallenge {
+    entry: RegisteredBeneficiary,
+    keybca: KeyBcaChallenge,
+}
+
+impl BeneficiaryChallenge {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        Ok(BeneficiaryChallenge {
+            entry: RegisteredBeneficiary::from_fields(&fields)?,
+            keybca: KeyBcaChallenge::from_fields(&fields)?,
+        })
+    }
+
+    pub fn entry(&self) -> &RegisteredBeneficiary {
+        &self.entry
+    }
+
+    pub fn keybca(&self) -> &KeyBcaChallenge {
+        &self.keybca
+    }
+}
+
+impl fmt::Display for BeneficiaryChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Bank: {}", self.entry.bank)?;
+        writeln!(f, "Add:  {}", self.entry.beneficiary)?;
+        write!(f, "{}", self.keybca)
+    }
+}
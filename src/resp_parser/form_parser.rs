@@ -0,0 +1,256 @@
+use crate::money::{Amount, Money};
+use crate::resp_parser::parse_error::{page_text, snippet, ParseError};
+use chrono::NaiveDate;
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+use std::fmt;
+
+// klikbca confirmation and receipt pages are "label : value" tables,
+// fields maps the lowercased labels to their values
+pub(crate) struct Fields {
+    values: HashMap<String, String>,
+    text: String,
+}
+
+impl Fields {
+    pub(crate) fn from_doc(doc: &Html) -> Self {
+        let row_selector = Selector::parse("tr").expect("field row selector error");
+        let td_selector = Selector::parse("td").expect("field cell selector error");
+        let mut values = HashMap::new();
+        for row in doc.select(&row_selector) {
+            let cells = row
+                .select(&td_selector)
+                .map(|td| {
+                    td.text()
+                        .collect::<String>()
+                        .split_whitespace()
+                        .collect::<Vec<&str>>()
+                        .join(" ")
+                })
+                .collect::<Vec<String>>();
+            if cells.len() >= 3 && cells[1] == ":" {
+                values
+                    .entry(cells[0].to_lowercase())
+                    .or_insert_with(|| cells[2].clone());
+            }
+        }
+        Fields {
+            values,
+            text: page_text(doc),
+        }
+    }
+
+    pub(crate) fn get(&self, label: &str) -> Option<&str> {
+        self.values.get(label).map(String::as_str)
+    }
+
+    pub(crate) fn require(&self, label: &str) -> Result<&str, ParseError> {
+        self.get(label).ok_or_else(|| {
+            ParseError::unexpected_layout(format!("no {:?} field", label), &self.text)
+        })
+    }
+
+    // "IDR 1,500,000.00"
+    pub(crate) fn money(&self, label: &str) -> Result<Money, ParseError> {
+        let raw = self.require(label)?;
+        let mut parts = raw.rsplitn(2, ' ');
+        let number = parts.next().unwrap_or_default();
+        let currency = parts.next().unwrap_or("IDR");
+        Money::parse(number, currency)
+            .map_err(|e| ParseError::unexpected_layout(e.to_string(), raw))
+    }
+
+    // "dd/mm/yyyy"
+    pub(crate) fn date(&self, label: &str) -> Result<NaiveDate, ParseError> {
+        let raw = self.require(label)?;
+        NaiveDate::parse_from_str(raw, "%d/%m/%Y")
+            .map_err(|_| ParseError::unexpected_layout(format!("invalid {:?} date", label), raw))
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+// KeyBcaChallenge holds the values to key into the KeyBCA token, klikbca shows one or both
+// of APPLI 1 and APPLI 2 depending on the transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyBcaChallenge {
+    appli1: Option<String>,
+    appli2: Option<String>,
+}
+
+impl KeyBcaChallenge {
+    pub(crate) fn from_fields(fields: &Fields) -> Result<Self, ParseError> {
+        let challenge = KeyBcaChallenge {
+            appli1: fields.get("appli 1").map(str::to_string),
+            appli2: fields.get("appli 2").map(str::to_string),
+        };
+        if challenge.appli1.is_none() && challenge.appli2.is_none() {
+            return Err(ParseError::unexpected_layout(
+                "no KeyBCA challenge",
+                fields.text(),
+            ));
+        }
+        Ok(challenge)
+    }
+
+    pub fn appli1(&self) -> Option<&str> {
+        self.appli1.as_deref()
+    }
+
+    pub fn appli2(&self) -> Option<&str> {
+        self.appli2.as_deref()
+    }
+}
+
+impl fmt::Display for KeyBcaChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = vec![];
+        if let Some(a1) = &self.appli1 {
+            lines.push(format!("KeyBCA APPLI 1: {}", a1));
+        }
+        if let Some(a2) = &self.appli2 {
+            lines.push(format!("KeyBCA APPLI 2: {}", a2));
+        }
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+// messages klikbca shows when it refuses a transaction
+const INVALID_RESPONSE_MARKERS: [&str; 3] = [
+    "respon keybca anda salah",
+    "respon keybca salah",
+    "invalid keybca response",
+];
+const INSUFFICIENT_FUNDS_MARKERS: [&str; 2] = ["saldo tidak cukup", "insufficient"];
+const UNKNOWN_BENEFICIARY_MARKERS: [&str; 2] = ["belum terdaftar", "not registered"];
+const LIMIT_MARKERS: [&str; 2] = ["melebihi batas", "exceeds the limit"];
+const ACCOUNT_NOT_FOUND_MARKERS: [&str; 2] =
+    ["rekening tujuan tidak ditemukan", "account not found"];
+
+// TransactionError is a transaction (transfer, payment) refused by klikbca
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionError {
+    InvalidKeyBcaResponse,
+    InsufficientFunds,
+    UnknownBeneficiary,
+    LimitExceeded,
+    // the destination bank does not know the account (interbank name inquiry)
+    AccountNotFound,
+    Rejected { message: String },
+}
+
+impl TransactionError {
+    // Some(error) when the page is one of klikbca's refusal pages
+    pub(crate) fn from_fields(fields: &Fields) -> Option<Self> {
+        let lowered = fields.text().to_lowercase();
+        let contains_any = |markers: &[&str]| markers.iter().any(|m| lowered.contains(m));
+        if contains_any(&INVALID_RESPONSE_MARKERS) {
+            return Some(TransactionError::InvalidKeyBcaResponse);
+        }
+        if contains_any(&INSUFFICIENT_FUNDS_MARKERS) {
+            return Some(TransactionError::InsufficientFunds);
+        }
+        if contains_any(&UNKNOWN_BENEFICIARY_MARKERS) {
+            return Some(TransactionError::UnknownBeneficiary);
+        }
+        if contains_any(&LIMIT_MARKERS) {
+            return Some(TransactionError::LimitExceeded);
+        }
+        if contains_any(&ACCOUNT_NOT_FOUND_MARKERS) {
+            return Some(TransactionError::AccountNotFound);
+        }
+        match fields.get("status") {
+            Some(status) if status.eq_ignore_ascii_case("gagal") => {
+                Some(TransactionError::Rejected {
+                    message: fields
+                        .get("keterangan")
+                        .map(str::to_string)
+                        .unwrap_or_else(|| snippet(fields.text())),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::InvalidKeyBcaResponse => {
+                write!(f, "transaction refused: wrong KeyBCA response")
+            }
+            TransactionError::InsufficientFunds => {
+                write!(f, "transaction refused: insufficient funds")
+            }
+            TransactionError::UnknownBeneficiary => write!(
+                f,
+                "transaction refused: destination is not in the registered beneficiary list"
+            ),
+            TransactionError::LimitExceeded => {
+                write!(f, "transaction refused: transaction limit exceeded")
+            }
+            TransactionError::AccountNotFound => {
+                write!(f, "transaction refused: destination account not found")
+            }
+            TransactionError::Rejected { message } => {
+                write!(f, "transaction refused by klikbca: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+// errors out when klikbca answered with a refusal instead of the next step of the flow
+pub(crate) fn check_refusal(resp: &str) -> Result<(), TransactionError> {
+    match TransactionError::from_fields(&Fields::from_doc(&Html::parse_document(resp))) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+// Receipt is the common part of klikbca's transaction receipts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Receipt {
+    reference: String,
+    date: NaiveDate,
+    amount: Money,
+}
+
+impl Receipt {
+    pub(crate) fn from_fields(fields: &Fields) -> Result<Self, ParseError> {
+        match fields.get("status") {
+            Some(status) if status.eq_ignore_ascii_case("berhasil") => {}
+            _ => {
+                return Err(ParseError::unexpected_layout(
+                    "not a successful receipt",
+                    fields.text(),
+                ))
+            }
+        }
+        Ok(Receipt {
+            reference: fields.require("no. referensi")?.to_string(),
+            date: fields.date("tanggal")?,
+            amount: fields.money("jumlah")?,
+        })
+    }
+
+    pub fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub fn amount(&self) -> &Money {
+        &self.amount
+    }
+}
+
+// formats an amount the way klikbca forms expect it, without thousands separators
+pub(crate) fn form_amount(amount: Amount) -> String {
+    amount.to_string().replace(',', "")
+}
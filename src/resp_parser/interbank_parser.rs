@@ -0,0 +1,297 @@
+use crate::money::Money;
+use crate::resp_parser::form_parser::{Fields, KeyBcaChallenge, Receipt};
+use crate::resp_parser::parse_error::{check_maintenance, page_text, ParseError};
+use crate::resp_parser::transfer_parser::Beneficiary;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// Bank is a destination bank of interbank transfers, code is the 3 digit clearing code
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Bank {
+    code: String,
+    name: String,
+}
+
+impl Bank {
+    pub fn new<C: Into<String>, N: Into<String>>(code: C, name: N) -> Self {
+        Bank {
+            code: code.into(),
+            name: name.into(),
+        }
+    }
+
+    // the bank select of the interbank transfer form
+    pub fn list_from_resp(resp: &str) -> Result<Vec<Self>, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let select_selector =
+            Selector::parse(r#"select[name="value(bankCode)"]"#).expect("bank selector error");
+        let option_selector = Selector::parse("option").expect("bank option error");
+        let select = doc
+            .select(&select_selector)
+            .next()
+            .ok_or_else(|| ParseError::missing_table("bank list", &page_text(&doc)))?;
+        Ok(select
+            .select(&option_selector)
+            .filter_map(|opt| {
+                let code = opt.value().attr("value").unwrap_or_default().trim();
+                if code.is_empty() {
+                    return None;
+                }
+                Some(Bank::new(code, opt.text().collect::<String>().trim()))
+            })
+            .collect())
+    }
+
+    pub(crate) fn from_fields(fields: &Fields) -> Result<Self, ParseError> {
+        Ok(Bank::new(
+            fields.require("kode bank")?,
+            fields.require("bank tujuan")?,
+        ))
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for Bank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.name)
+    }
+}
+
+// TransferService is the clearing used for an interbank transfer, they differ in fee and limit
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TransferService {
+    // real time transfer over the ATM networks
+    Online,
+    // Bank Indonesia's fast payment system
+    BiFast,
+}
+
+impl TransferService {
+    // value of the service field in the klikbca form
+    pub fn code(self) -> &'static str {
+        match self {
+            TransferService::Online => "online",
+            TransferService::BiFast => "bifast",
+        }
+    }
+}
+
+// accepts both the form codes and the names klikbca shows ("Transfer Online", "BI-FAST")
+impl FromStr for TransferService {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        match normalized.as_str() {
+            "online" | "transferonline" => Ok(TransferService::Online),
+            "bifast" => Ok(TransferService::BiFast),
+            _ => Err(ParseError::unexpected_layout(
+                format!("unknown transfer service {:?}", s),
+                s,
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TransferService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferService::Online => write!(f, "Transfer Online"),
+            TransferService::BiFast => write!(f, "BI-FAST"),
+        }
+    }
+}
+
+// NameInquiry is the account holder klikbca found at the destination bank
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameInquiry {
+    bank: Bank,
+    beneficiary: Beneficiary,
+}
+
+impl NameInquiry {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        Ok(NameInquiry {
+            bank: Bank::from_fields(&fields)?,
+            beneficiary: Beneficiary::from_fields(&fields)?,
+        })
+    }
+
+    pub fn bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    pub fn beneficiary(&self) -> &Beneficiary {
+        &self.beneficiary
+    }
+}
+
+impl fmt::Display for NameInquiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.beneficiary, self.bank)
+    }
+}
+
+// InterbankChallenge is the confirmation page of an interbank transfer, showing the fee
+// charged on top of the amount
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterbankChallenge {
+    bank: Bank,
+    beneficiary: Beneficiary,
+    service: TransferService,
+    amount: Money,
+    fee: Money,
+    note: String,
+    keybca: KeyBcaChallenge,
+}
+
+impl InterbankChallenge {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        Ok(InterbankChallenge {
+            bank: Bank::from_fields(&fields)?,
+            beneficiary: Beneficiary::from_fields(&fields)?,
+            service: fields.require("layanan")?.parse()?,
+            amount: fields.money("jumlah")?,
+            fee: fields.money("biaya")?,
+            note: fields.get("berita").unwrap_or_default().to_string(),
+            keybca: KeyBcaChallenge::from_fields(&fields)?,
+        })
+    }
+
+    pub fn bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    pub fn beneficiary(&self) -> &Beneficiary {
+        &self.beneficiary
+    }
+
+    pub fn service(&self) -> TransferService {
+        self.service
+    }
+
+    pub fn amount(&self) -> &Money {
+        &self.amount
+    }
+
+    pub fn fee(&self) -> &Money {
+        &self.fee
+    }
+
+    // amount plus fee, what leaves the account
+    pub fn total(&self) -> Money {
+        Money::new(
+            self.amount.amount() + self.fee.amount(),
+            self.amount.currency(),
+        )
+    }
+
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+
+    pub fn keybca(&self) -> &KeyBcaChallenge {
+        &self.keybca
+    }
+}
+
+impl fmt::Display for InterbankChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Bank:    {}", self.bank)?;
+        writeln!(f, "To:      {}", self.beneficiary)?;
+        writeln!(f, "Service: {}", self.service)?;
+        writeln!(f, "Amount:  {}", self.amount)?;
+        writeln!(f, "Fee:     {}", self.fee)?;
+        writeln!(f, "Total:   {}", self.total())?;
+        writeln!(f, "Note:    {}", self.note)?;
+        write!(f, "{}", self.keybca)
+    }
+}
+
+// InterbankReceipt is the result page of an executed interbank transfer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterbankReceipt {
+    receipt: Receipt,
+    bank: Bank,
+    beneficiary: Beneficiary,
+    service: TransferService,
+    fee: Money,
+    note: String,
+}
+
+impl InterbankReceipt {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        Ok(InterbankReceipt {
+            receipt: Receipt::from_fields(&fields)?,
+            bank: Bank::from_fields(&fields)?,
+            beneficiary: Beneficiary::from_fields(&fields)?,
+            service: fields.require("layanan")?.parse()?,
+            fee: fields.money("biaya")?,
+            note: fields.get("berita").unwrap_or_default().to_string(),
+        })
+    }
+
+    pub fn reference(&self) -> &str {
+        self.receipt.reference()
+    }
+
+    pub fn receipt(&self) -> &Receipt {
+        &self.receipt
+    }
+
+    pub fn bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    pub fn beneficiary(&self) -> &Beneficiary {
+        &self.beneficiary
+    }
+
+    pub fn service(&self) -> TransferService {
+        self.service
+    }
+
+    pub fn fee(&self) -> &Money {
+        &self.fee
+    }
+
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+}
+
+impl fmt::Display for InterbankReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Reference: {}", self.receipt.reference())?;
+        writeln!(f, "Date:      {}", self.receipt.date().format("%d/%m/%Y"))?;
+        writeln!(f, "Bank:      {}", self.bank)?;
+        writeln!(f, "To:        {}", self.beneficiary)?;
+        writeln!(f, "Service:   {}", self.service)?;
+        writeln!(f, "Amount:    {}", self.receipt.amount())?;
+        writeln!(f, "Fee:       {}", self.fee)?;
+        write!(f, "Note:      {}", self.note)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::resp_parser::parse_error::{page_text, snippet};
+use crate::resp_parser::session_page::{classify_page, PageKind};
+use scraper::{Html, Selector};
+
+// messages klikbca shows in the login error element after a rejected authentication
+const WRONG_CREDENTIAL_MARKERS: [&str; 4] = [
+    "user id atau pin anda salah",
+    "user id atau pin salah",
+    "user id or pin is incorrect",
+    "invalid user id or pin",
+];
+const ALREADY_LOGGED_IN_MARKERS: [&str; 4] = [
+    "sudah login di tempat lain",
+    "sedang login",
+    "already logged in",
+    "logged in elsewhere",
+];
+const BLOCKED_MARKERS: [&str; 3] = ["terblokir", "diblokir", "blocked"];
+
+// LoginOutcome classifies the response of the authentication.do POST
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoginOutcome {
+    Success,
+    WrongCredentials,
+    AlreadyLoggedIn,
+    Blocked,
+    // klikbca sent the login form back without a message we know about
+    Rejected { snippet: String },
+}
+
+impl LoginOutcome {
+    pub fn from_resp(resp: &str) -> Self {
+        let doc = Html::parse_document(resp);
+        let text = page_text(&doc);
+        let message = login_message(&doc).to_lowercase();
+        let contains_any = |markers: &[&str]| markers.iter().any(|m| message.contains(m));
+        if contains_any(&BLOCKED_MARKERS) {
+            return LoginOutcome::Blocked;
+        }
+        if contains_any(&ALREADY_LOGGED_IN_MARKERS) {
+            return LoginOutcome::AlreadyLoggedIn;
+        }
+        if contains_any(&WRONG_CREDENTIAL_MARKERS) {
+            return LoginOutcome::WrongCredentials;
+        }
+        match classify_page(resp) {
+            PageKind::Content => LoginOutcome::Success,
+            PageKind::LoginForm | PageKind::SessionExpired => LoginOutcome::Rejected {
+                snippet: snippet(&text),
+            },
+        }
+    }
+}
+
+// the error klikbca prints in red above the login form. Only that is looked at: the menu after a
+// successful login carries notices of its own, e.g. about blocked ATM cards.
+fn login_message(doc: &Html) -> String {
+    let selector = Selector::parse(r#"font[color="red" i]"#).expect("login message selector error");
+    doc.select(&selector)
+        .flat_map(|e| e.text())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
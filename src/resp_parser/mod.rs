@@ -1,3 +1,10 @@
-pub(crate) mod resp_traits;
-pub(crate) mod mutasi_parser;
-pub(crate) mod saldo_parser;
+pub mod beneficiary_parser;
+pub mod form_parser;
+pub mod interbank_parser;
+pub mod login_parser;
+pub mod mutasi_parser;
+pub mod parse_error;
+pub mod payment_parser;
+pub mod saldo_parser;
+pub mod session_page;
+pub mod transfer_parser;
@@ -1,58 +1,152 @@
-use crate::resp_parser::resp_traits::{TuiList, TuiListCreator, TuiTable, TuiTableCreator};
-use anyhow::{anyhow, Result};
+use crate::accounts::DateRange;
+use crate::money::{Amount, Money};
+use crate::resp_parser::parse_error::{check_maintenance, page_text, ParseError};
+use chrono::{Datelike, NaiveDate};
 use regex::Regex;
 use scraper::{ElementRef, Html, Selector};
 use std::borrow::Cow;
-use tui::widgets::TableState;
+use std::fmt;
+use std::str::FromStr;
 
 fn get_last_text_el(s: scraper::ElementRef, separator: &str) -> String {
     s.text()
         .collect::<String>()
         .trim()
-        .to_string()
-        .split(separator)
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>()
-        .last()
-        .unwrap()
+        .rsplit(separator)
+        .next()
+        .unwrap_or_default()
         .to_string()
 }
 
 #[derive(Debug)]
 pub struct AccountMutasi<'am> {
     info: AccountInfo<'am>,
-    tx: AccountTxes<'am>,
-    summary: MutationSummary<'am>,
+    tx: AccountTxes,
+    summary: MutationSummary,
 }
 
 impl<'am> AccountMutasi<'am> {
-    pub fn new<S>(resp: S) -> Result<Self>
+    pub fn new<S>(resp: S) -> Result<Self, ParseError>
     where
         S: Into<Cow<'am, str>>,
     {
         let doc: Html = Html::parse_document(&resp.into());
+        check_maintenance(&doc)?;
         let info: AccountInfo<'am> = AccountInfo::from_resp(&doc)?;
-        let tx: AccountTxes<'am> = AccountTxes::from_resp(&doc)?;
-        let summary: MutationSummary<'am> = MutationSummary::from_resp(&doc)?;
+        let tx: AccountTxes = AccountTxes::from_resp(&doc, &info.period_range()?)?;
+        let summary: MutationSummary = MutationSummary::from_resp(&doc, info.currency())?;
 
         Ok(AccountMutasi { info, tx, summary })
     }
 
-    pub fn account_info_list(&self) -> TuiList {
-        self.info.to_tui_list()
+    // a statement without transactions, for a period klikbca only answers with its notice
+    pub fn empty(
+        account_number: &str,
+        period: DateRange,
+        balance: &Money,
+    ) -> AccountMutasi<'static> {
+        let nothing = Money::new(Amount::default(), balance.currency());
+        AccountMutasi {
+            info: AccountInfo {
+                account_number: account_number.to_string().into(),
+                owner_name: String::new().into(),
+                period: period.to_string().into(),
+                currency: balance.currency().to_string().into(),
+            },
+            tx: AccountTxes { txes: Some(vec![]) },
+            summary: MutationSummary {
+                balance_begin: balance.clone(),
+                total_credits: nothing.clone(),
+                total_debits: nothing,
+                balance_end: balance.clone(),
+            },
+        }
+    }
+
+    pub fn info(&self) -> &AccountInfo<'am> {
+        &self.info
+    }
+
+    pub fn transactions(&self) -> &[AccountTx] {
+        self.tx.txes.as_deref().unwrap_or(&[])
     }
 
-    pub fn account_tx_table(&self) -> TuiTable {
-        self.tx.to_tui_table()
+    pub fn summary(&self) -> &MutationSummary {
+        &self.summary
     }
 
-    pub fn account_summary_list(&self) -> TuiList {
-        self.summary.to_tui_list()
+    // joins the statements of consecutive windows (oldest first) into one statement of period.
+    // Windows klikbca had nothing for may be missing, the balance carries over them. Transactions
+    // on days an earlier statement already covered are dropped, so windows overlapping at the
+    // edges don't count anything twice.
+    pub fn merge(
+        period: DateRange,
+        windows: Vec<AccountMutasi<'am>>,
+    ) -> Result<AccountMutasi<'static>, ParseError> {
+        let mut windows = windows.into_iter();
+        let first = windows
+            .next()
+            .ok_or_else(|| ParseError::unexpected_layout("no statements to merge", ""))?;
+        // the days the statements merged so far cover
+        let mut covered = first.info.period_range()?;
+        let currency = first.summary.currency().to_string();
+        let balance_begin = first.summary.balance_begin.clone();
+        let mut credits = first.summary.total_credits.amount();
+        let mut debits = first.summary.total_debits.amount();
+        let mut balance_end = first.summary.balance_end.clone();
+        let mut txes = first.transactions().to_vec();
+        for window in windows {
+            let range = window.info.period_range()?;
+            if window.info.account_number != first.info.account_number {
+                return Err(ParseError::unexpected_layout(
+                    "statements of different accounts",
+                    &window.info.account_number,
+                ));
+            }
+            if range.start > covered.end && window.summary.balance_begin != balance_end {
+                return Err(ParseError::unexpected_layout(
+                    format!(
+                        "statement {} does not start where the one before it ended",
+                        range
+                    ),
+                    &window.summary.balance_begin.to_string(),
+                ));
+            }
+            credits = credits + window.summary.total_credits.amount();
+            debits = debits + window.summary.total_debits.amount();
+            for tx in window.transactions() {
+                if !tx.pending && tx.tx_date <= covered.end {
+                    match tx.tx_direction {
+                        Direction::Credit => credits = credits - tx.tx_amount,
+                        Direction::Debit => debits = debits - tx.tx_amount,
+                    }
+                    continue;
+                }
+                txes.push(tx.clone());
+            }
+            balance_end = window.summary.balance_end.clone();
+            covered.end = std::cmp::max(covered.end, range.end);
+        }
+        Ok(AccountMutasi {
+            info: AccountInfo {
+                account_number: first.info.account_number.into_owned().into(),
+                owner_name: first.info.owner_name.into_owned().into(),
+                period: period.to_string().into(),
+                currency: first.info.currency.into_owned().into(),
+            },
+            tx: AccountTxes { txes: Some(txes) },
+            summary: MutationSummary {
+                balance_begin,
+                total_credits: Money::new(credits, currency.as_str()),
+                total_debits: Money::new(debits, currency.as_str()),
+                balance_end,
+            },
+        })
     }
 }
 
 #[derive(Debug, Default)]
-struct AccountInfo<'a> {
+pub struct AccountInfo<'a> {
     account_number: Cow<'a, str>,
     owner_name: Cow<'a, str>,
     period: Cow<'a, str>,
@@ -60,19 +154,30 @@ struct AccountInfo<'a> {
 }
 
 impl<'a> AccountInfo<'a> {
-    fn from_resp(doc: &Html) -> Result<Self> {
+    fn from_resp(doc: &Html) -> Result<Self, ParseError> {
         let mut acc_info = AccountInfo::default();
         let account_info_selector = Selector::parse(
             r#"table[border="0"][width="100%"][cellpadding="0"][cellspacing="0"][class="blue"]"#,
         )
         .expect("html document error");
-        for row in doc.select(&account_info_selector) {
+        let info_tables = doc.select(&account_info_selector).collect::<Vec<_>>();
+        if info_tables.is_empty() {
+            let text = page_text(doc);
+            // klikbca renders a bare notice instead of the statement when there is nothing to show
+            if text.to_lowercase().contains("tidak ada transaksi") {
+                return Err(ParseError::EmptyStatement {
+                    snippet: text.trim().to_string(),
+                });
+            }
+            return Err(ParseError::missing_table("account info", &text));
+        }
+        for row in info_tables {
             smol::run(async {
                 let acc_num_sel =
                     Selector::parse("tr:nth-child(2)").expect("table has no account number");
                 for acc_num in row.select(&acc_num_sel) {
                     let n = get_last_text_el(acc_num, ".");
-                    acc_info.account_number = n[1..].to_string().into();
+                    acc_info.account_number = n.get(1..).unwrap_or_default().to_string().into();
                 }
             });
             smol::run(async {
@@ -102,28 +207,46 @@ impl<'a> AccountInfo<'a> {
         }
         Ok(acc_info)
     }
-}
 
-impl<'a> TuiListCreator for AccountInfo<'a> {
-    fn to_tui_list(&self) -> TuiList {
-        TuiList {
-            items: vec![
-                format!("Account Number:\t{}", self.account_number).into(),
-                format!("Account Owner:\t{}", self.owner_name).into(),
-                format!("Period:\t{}", self.period).into(),
-                format!("Account Currency:\t{}", self.currency).into(),
-            ],
+    pub fn account_number(&self) -> &str {
+        &self.account_number
+    }
+
+    pub fn owner_name(&self) -> &str {
+        &self.owner_name
+    }
+
+    pub fn period(&self) -> &str {
+        &self.period
+    }
+
+    // statement period, rendered by klikbca as "dd/mm/yyyy - dd/mm/yyyy"
+    pub fn period_range(&self) -> Result<DateRange, ParseError> {
+        let mut dates = self
+            .period
+            .split('-')
+            .map(|d| NaiveDate::parse_from_str(d.trim(), "%d/%m/%Y"));
+        match (dates.next(), dates.next()) {
+            (Some(Ok(start)), Some(Ok(end))) => Ok(DateRange::new(start, end)),
+            _ => Err(ParseError::unexpected_layout(
+                "invalid statement period",
+                &self.period,
+            )),
         }
     }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
 }
 
 #[derive(Debug, Default)]
-struct AccountTxes<'a> {
-    txes: Option<Vec<AccountTx<'a>>>,
+struct AccountTxes {
+    txes: Option<Vec<AccountTx>>,
 }
 
-impl<'a> AccountTxes<'a> {
-    fn from_resp(doc: &Html) -> Result<Self> {
+impl AccountTxes {
+    fn from_resp(doc: &Html, period: &DateRange) -> Result<Self, ParseError> {
         let mut txes_vec: Vec<AccountTx> = vec![];
         let acc_tx_selector =
             Selector::parse(r#"table[width="100%"][class="blue"]"#).expect("html document error");
@@ -131,7 +254,7 @@ impl<'a> AccountTxes<'a> {
         let acc_table_elements = doc
             .select(&acc_tx_selector)
             .last()
-            .expect("no transactions found")
+            .ok_or_else(|| ParseError::missing_table("transactions", &page_text(doc)))?
             .html();
         // Bad practice: replacing <br> element
         let re_br = Regex::new(r"<[/]?br>").unwrap();
@@ -139,147 +262,265 @@ impl<'a> AccountTxes<'a> {
         let tr_selector = Selector::parse(r#"tr[bgcolor]"#).expect("transaction fragment error");
         let acc_table_fragments = Html::parse_fragment(&acc_table_string);
         for tx in acc_table_fragments.select(&tr_selector) {
-            let new_acc_tx = AccountTx::from_resp(&tx)?;
+            let new_acc_tx = AccountTx::from_resp(&tx, period)?;
             txes_vec.push(new_acc_tx);
         }
 
-        if txes_vec.is_empty() {
-            ()
-        }
-
         Ok(AccountTxes {
             txes: Some(txes_vec),
         })
     }
 }
 
-impl<'a> TuiTableCreator for AccountTxes<'a> {
-    fn to_tui_table(&self) -> TuiTable {
-        let all_txes: Vec<Vec<String>>;
-        match self.txes.clone() {
-            Some(txes) => {
-                all_txes = txes
-                    .into_iter()
-                    .map(|atx| {
-                        let mut accum = Vec::<String>::new();
-                        accum.push(atx.tx_date.into());
-                        accum.push(atx.tx_note.into());
-                        accum.push(atx.tx_amount.into());
-                        accum.push(atx.tx_category.into());
-                        accum
-                    })
-                    .collect::<Vec<Vec<String>>>()
-            }
-            None => all_txes = Vec::<Vec<String>>::new(),
+// Direction of a transaction as seen from the account, klikbca marks them "DB" / "CR".
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Debit,
+    Credit,
+}
+
+impl Direction {
+    pub fn code(self) -> &'static str {
+        match self {
+            Direction::Debit => "DB",
+            Direction::Credit => "CR",
         }
-        TuiTable {
-            state: TableState::default(),
-            items: all_txes,
+    }
+}
+
+impl FromStr for Direction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        match s.trim() {
+            "DB" => Ok(Direction::Debit),
+            "CR" => Ok(Direction::Credit),
+            other => Err(ParseError::unexpected_layout(
+                "unknown transaction direction",
+                other,
+            )),
         }
     }
 }
 
-#[derive(Clone, Debug, Default)]
-struct AccountTx<'a> {
-    // transaction date & notes
-    tx_date: Cow<'a, str>,
-    tx_note: Cow<'a, str>,
-    tx_amount: Cow<'a, str>,
-    tx_category: Cow<'a, str>,
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountTx {
+    // transaction date, pending transactions ("PEND") are dated at the end of the period
+    tx_date: NaiveDate,
+    pending: bool,
+    tx_description: String,
+    tx_amount: Amount,
+    tx_direction: Direction,
 }
 
-impl<'a> AccountTx<'a> {
-    fn from_resp(row: &ElementRef) -> Result<Self> {
-        let mut acc_tx = AccountTx::default();
+impl AccountTx {
+    pub fn new(
+        date: NaiveDate,
+        pending: bool,
+        description: String,
+        amount: Amount,
+        direction: Direction,
+    ) -> Self {
+        AccountTx {
+            tx_date: date,
+            pending,
+            tx_description: description,
+            tx_amount: amount,
+            tx_direction: direction,
+        }
+    }
+
+    fn from_resp(row: &ElementRef, period: &DateRange) -> Result<Self, ParseError> {
+        let mut tx_date = String::new();
+        let mut note_amt = String::new();
+        let mut tx_category = String::new();
         smol::run(async {
             let date_selector =
                 Selector::parse(r#"tr>td[valign="top"]:first-of-type"#).expect("tx date not found");
             for td in row.select(&date_selector) {
-                let d = td.text().collect::<Cow<'a, str>>();
-                acc_tx.tx_date = d;
+                tx_date = td.text().collect::<String>();
             }
         });
         smol::run(async {
             let note_amt_selector =
                 Selector::parse("tr>td:first-of-type + td").expect("tx note and amount is invalid");
             for namt_sel in row.select(&note_amt_selector) {
-                let note_amt = namt_sel.text().collect::<String>();
-                let re_note = Regex::new(r"[\s]{1}+").unwrap();
-                let note = note_amt
-                    .split(" ")
-                    .map(|s| re_note.replace_all(s, " ").into())
-                    .collect::<Vec<String>>()
-                    .into_iter()
-                    .filter(|s| !s.is_empty())
-                    .collect::<Vec<String>>()
-                    .join(" ");
-                acc_tx.tx_note = note.into();
-                let tx_amt = note_amt
-                    .split(" ")
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>()
-                    .into_iter()
-                    .last()
-                    .unwrap();
-                acc_tx.tx_amount = tx_amt.into();
+                note_amt = namt_sel.text().collect::<String>();
             }
         });
         smol::run(async {
             let category_selector = Selector::parse(r#"tr>td[valign="top"]:last-of-type"#)
                 .expect("tx category not found");
             for cat_el in row.select(&category_selector) {
-                let cat = cat_el.text().collect::<Cow<'a, str>>();
-                acc_tx.tx_category = cat;
+                tx_category = cat_el.text().collect::<String>();
             }
         });
-        Ok(acc_tx)
+
+        // the amount is the last token of the note column
+        let mut tokens = note_amt.split_whitespace().collect::<Vec<&str>>();
+        let tx_amount = tokens
+            .pop()
+            .ok_or_else(|| ParseError::unexpected_layout("transaction has no amount", &row.html()))?
+            .parse::<Amount>()
+            .map_err(|e| ParseError::unexpected_layout(e.to_string(), &note_amt))?;
+        let (tx_date, pending) = parse_tx_date(&tx_date, period)?;
+
+        Ok(AccountTx {
+            tx_date,
+            pending,
+            tx_description: tokens.join(" "),
+            tx_amount,
+            tx_direction: tx_category.parse()?,
+        })
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        self.tx_date
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn description(&self) -> &str {
+        &self.tx_description
+    }
+
+    pub fn amount(&self) -> Amount {
+        self.tx_amount
     }
+
+    pub fn direction(&self) -> Direction {
+        self.tx_direction
+    }
+
+    // amount signed by direction, debits are negative
+    pub fn signed_amount(&self) -> Amount {
+        match self.tx_direction {
+            Direction::Debit => -self.tx_amount,
+            Direction::Credit => self.tx_amount,
+        }
+    }
+}
+
+// transaction dates are rendered as "dd/mm" (or "PEND"), the year is taken from the
+// statement period so that statements spanning new year resolve correctly.
+fn parse_tx_date(raw: &str, period: &DateRange) -> Result<(NaiveDate, bool), ParseError> {
+    let raw = raw.trim();
+    if raw.eq_ignore_ascii_case("PEND") {
+        return Ok((period.end, true));
+    }
+    let mut parts = raw.splitn(2, '/');
+    let day = parts.next().unwrap_or_default().trim().parse::<u32>();
+    let month = parts.next().unwrap_or_default().trim().parse::<u32>();
+    let (day, month) = match (day, month) {
+        (Ok(d), Ok(m)) => (d, m),
+        _ => {
+            return Err(ParseError::unexpected_layout(
+                "invalid transaction date",
+                raw,
+            ))
+        }
+    };
+    [period.end.year(), period.start.year()]
+        .iter()
+        .filter_map(|y| NaiveDate::from_ymd_opt(*y, month, day))
+        .find(|d| *d <= period.end)
+        .ok_or_else(|| ParseError::unexpected_layout("invalid transaction date", raw))
+        .map(|d| (d, false))
 }
 
 #[derive(Debug, Default)]
-struct MutationSummary<'a> {
-    balance_begin: Cow<'a, str>,
-    total_credits: Cow<'a, str>,
-    total_debits: Cow<'a, str>,
-    balance_end: Cow<'a, str>,
+pub struct MutationSummary {
+    balance_begin: Money,
+    total_credits: Money,
+    total_debits: Money,
+    balance_end: Money,
 }
 
-impl<'a> MutationSummary<'a> {
-    fn from_resp(doc: &Html) -> Result<Self> {
+impl MutationSummary {
+    fn from_resp(doc: &Html, currency: &str) -> Result<Self, ParseError> {
         let mut mut_sum = MutationSummary::default();
         let mut_sum_selector =
             Selector::parse(r#"table[width="97%"][cellspacing="0"][class="blue"]"#)
                 .expect("html document error");
-        for tr in doc.select(&mut_sum_selector) {
+        let summary_tables = doc.select(&mut_sum_selector).collect::<Vec<_>>();
+        if summary_tables.is_empty() {
+            return Err(ParseError::missing_table(
+                "mutation summary",
+                &page_text(doc),
+            ));
+        }
+        for tr in summary_tables {
             let sum_selector = Selector::parse(r#"tr>td[align="left"]:nth-of-type(n+2)"#)
                 .expect("table mutation summary is not found");
-            let mut rows = tr.select(&sum_selector);
-            let cloned_rows = rows.clone();
-            if cloned_rows.count() != 4 {
-                return Err(anyhow!("Mutation summary elements not found"));
+            let rows = tr
+                .select(&sum_selector)
+                .map(|td| {
+                    let text = td.text().collect::<String>();
+                    Money::parse(&text, currency)
+                        .map_err(|e| ParseError::unexpected_layout(e.to_string(), &text))
+                })
+                .collect::<Result<Vec<Money>, ParseError>>()?;
+            if rows.len() != 4 {
+                return Err(ParseError::unexpected_layout(
+                    "mutation summary elements not found",
+                    &tr.html(),
+                ));
             }
-            mut_sum.balance_begin = rows.next().unwrap().text().collect::<String>().into();
-            mut_sum.total_credits = rows.next().unwrap().text().collect::<String>().into();
-            mut_sum.total_debits = rows.next().unwrap().text().collect::<String>().into();
-            mut_sum.balance_end = rows.next().unwrap().text().collect::<String>().into();
+            let mut rows = rows.into_iter();
+            mut_sum.balance_begin = rows.next().unwrap();
+            mut_sum.total_credits = rows.next().unwrap();
+            mut_sum.total_debits = rows.next().unwrap();
+            mut_sum.balance_end = rows.next().unwrap();
         }
         Ok(mut_sum)
     }
-}
 
-impl<'a> TuiListCreator for MutationSummary<'a> {
-    fn to_tui_list(&self) -> TuiList {
-        TuiList {
-            items: vec![
-                "Starting Balance".into(),
-                format!("{}", self.balance_begin).into(),
-                "Credit Mutations".into(),
-                format!("{}", self.total_credits).into(),
-                "Debit Mutations".into(),
-                format!("{}", self.total_debits).into(),
-                "Balance".into(),
-                format!("{}", self.balance_end).into(),
-            ],
-        }
+    pub fn currency(&self) -> &str {
+        self.balance_end.currency()
+    }
+
+    pub fn balance_begin(&self) -> &Money {
+        &self.balance_begin
+    }
+
+    pub fn total_credits(&self) -> &Money {
+        &self.total_credits
+    }
+
+    pub fn total_debits(&self) -> &Money {
+        &self.total_debits
+    }
+
+    pub fn balance_end(&self) -> &Money {
+        &self.balance_end
+    }
+
+    // total credits minus total debits over the period
+    pub fn net_mutation(&self) -> Money {
+        Money::new(
+            self.total_credits.amount() - self.total_debits.amount(),
+            self.currency(),
+        )
+    }
+
+    // ending balance derived from the starting balance and the period's mutations
+    pub fn expected_balance_end(&self) -> Money {
+        Money::new(
+            self.balance_begin.amount() + self.net_mutation().amount(),
+            self.currency(),
+        )
+    }
+
+    // true when starting balance + credits - debits equals the reported ending balance
+    pub fn is_consistent(&self) -> bool {
+        self.expected_balance_end() == self.balance_end
     }
 }
@@ -0,0 +1,121 @@
+use scraper::{Html, Selector};
+use std::fmt;
+
+// max length of the page excerpt carried by a ParseError
+const SNIPPET_LEN: usize = 160;
+
+// ParseError describes why a klikbca page could not be turned into domain structs.
+// Every variant carries a short, whitespace collapsed excerpt of the offending html.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    // an expected table is not present in the page
+    MissingTable {
+        table: &'static str,
+        snippet: String,
+    },
+    // the tables are there but their content could not be interpreted
+    UnexpectedLayout {
+        reason: String,
+        snippet: String,
+    },
+    // klikbca answered without any statement data for the requested period
+    EmptyStatement {
+        snippet: String,
+    },
+    // klikbca is down for maintenance
+    MaintenancePage {
+        snippet: String,
+    },
+}
+
+impl ParseError {
+    pub(crate) fn missing_table(table: &'static str, html: &str) -> Self {
+        ParseError::MissingTable {
+            table,
+            snippet: snippet(html),
+        }
+    }
+
+    pub(crate) fn unexpected_layout<R: Into<String>>(reason: R, html: &str) -> Self {
+        ParseError::UnexpectedLayout {
+            reason: reason.into(),
+            snippet: snippet(html),
+        }
+    }
+
+    // klikbca answers a period without transactions with an error page, callers usually take
+    // that for an empty statement
+    pub fn is_empty_statement(e: &anyhow::Error) -> bool {
+        matches!(
+            e.downcast_ref::<ParseError>(),
+            Some(ParseError::EmptyStatement { .. })
+        )
+    }
+
+    pub fn snippet(&self) -> &str {
+        match self {
+            ParseError::MissingTable { snippet, .. }
+            | ParseError::UnexpectedLayout { snippet, .. }
+            | ParseError::EmptyStatement { snippet }
+            | ParseError::MaintenancePage { snippet } => snippet,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingTable { table, snippet } => {
+                write!(f, "{} table not found in page: {:?}", table, snippet)
+            }
+            ParseError::UnexpectedLayout { reason, snippet } => {
+                write!(f, "unexpected page layout, {}: {:?}", reason, snippet)
+            }
+            ParseError::EmptyStatement { snippet } => {
+                write!(f, "no statement data for the period: {:?}", snippet)
+            }
+            ParseError::MaintenancePage { snippet } => {
+                write!(f, "klikbca is under maintenance: {:?}", snippet)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// collapses whitespace and truncates (on a char boundary) to SNIPPET_LEN
+pub(crate) fn snippet(html: &str) -> String {
+    let collapsed = html.split_whitespace().collect::<Vec<&str>>().join(" ");
+    match collapsed.char_indices().nth(SNIPPET_LEN) {
+        Some((idx, _)) => format!("{}...", &collapsed[..idx]),
+        None => collapsed,
+    }
+}
+
+// all visible text of the document
+pub(crate) fn page_text(doc: &Html) -> String {
+    doc.root_element().text().collect::<Vec<&str>>().join(" ")
+}
+
+// same as check_maintenance, for responses that are not parsed any further
+pub(crate) fn check_maintenance_page(resp: &str) -> Result<(), ParseError> {
+    check_maintenance(&Html::parse_document(resp))
+}
+
+// klikbca serves a plain notice page while the service is down. Every page with data lays it
+// out in tables, whose cells may well quote a note like "IURAN PEMELIHARAAN", so only pages
+// without any table are taken for the notice.
+pub(crate) fn check_maintenance(doc: &Html) -> Result<(), ParseError> {
+    let table = Selector::parse("table").expect("invalid table selector");
+    if doc.select(&table).next().is_some() {
+        return Ok(());
+    }
+    let text = page_text(doc);
+    let lowered = text.to_lowercase();
+    if lowered.contains("maintenance") || lowered.contains("pemeliharaan") {
+        return Err(ParseError::MaintenancePage {
+            snippet: snippet(&text),
+        });
+    }
+    Ok(())
+}
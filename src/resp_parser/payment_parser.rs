@@ -0,0 +1,176 @@
+use crate::money::Money;
+use crate::resp_parser::form_parser::{Fields, KeyBcaChallenge, Receipt};
+use crate::resp_parser::parse_error::{check_maintenance, ParseError};
+use scraper::Html;
+use std::fmt;
+
+// VaInquiry is the bill behind a BCA Virtual Account number. Open VAs (e.g. top ups) have no
+// billed amount, the payer chooses it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VaInquiry {
+    va_number: String,
+    company: String,
+    name: String,
+    amount: Option<Money>,
+}
+
+impl VaInquiry {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        let amount = match fields.get("jumlah tagihan") {
+            Some(billed) if billed != "-" => Some(fields.money("jumlah tagihan")?),
+            _ => None,
+        };
+        Ok(VaInquiry {
+            va_number: fields.require("no. virtual account")?.to_string(),
+            company: fields.require("perusahaan")?.to_string(),
+            name: fields.require("nama")?.to_string(),
+            amount,
+        })
+    }
+
+    pub fn va_number(&self) -> &str {
+        &self.va_number
+    }
+
+    // the merchant or biller
+    pub fn company(&self) -> &str {
+        &self.company
+    }
+
+    // the billed customer
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn amount(&self) -> Option<&Money> {
+        self.amount.as_ref()
+    }
+}
+
+impl fmt::Display for VaInquiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Virtual Account: {}", self.va_number)?;
+        writeln!(f, "Company:         {}", self.company)?;
+        writeln!(f, "Name:            {}", self.name)?;
+        match &self.amount {
+            Some(amount) => write!(f, "Billed:          {}", amount),
+            None => write!(f, "Billed:          open amount"),
+        }
+    }
+}
+
+// PaymentChallenge is the confirmation page of a virtual account payment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentChallenge {
+    va_number: String,
+    company: String,
+    name: String,
+    amount: Money,
+    keybca: KeyBcaChallenge,
+}
+
+impl PaymentChallenge {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        Ok(PaymentChallenge {
+            va_number: fields.require("no. virtual account")?.to_string(),
+            company: fields.require("perusahaan")?.to_string(),
+            name: fields.require("nama")?.to_string(),
+            amount: fields.money("jumlah")?,
+            keybca: KeyBcaChallenge::from_fields(&fields)?,
+        })
+    }
+
+    pub fn va_number(&self) -> &str {
+        &self.va_number
+    }
+
+    pub fn company(&self) -> &str {
+        &self.company
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn amount(&self) -> &Money {
+        &self.amount
+    }
+
+    pub fn keybca(&self) -> &KeyBcaChallenge {
+        &self.keybca
+    }
+}
+
+impl fmt::Display for PaymentChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Virtual Account: {}", self.va_number)?;
+        writeln!(f, "Company:         {}", self.company)?;
+        writeln!(f, "Name:            {}", self.name)?;
+        writeln!(f, "Amount:          {}", self.amount)?;
+        write!(f, "{}", self.keybca)
+    }
+}
+
+// PaymentReceipt is the result page of a virtual account payment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentReceipt {
+    receipt: Receipt,
+    va_number: String,
+    company: String,
+    name: String,
+}
+
+impl PaymentReceipt {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        Ok(PaymentReceipt {
+            receipt: Receipt::from_fields(&fields)?,
+            va_number: fields.require("no. virtual account")?.to_string(),
+            company: fields.require("perusahaan")?.to_string(),
+            name: fields.require("nama")?.to_string(),
+        })
+    }
+
+    pub fn reference(&self) -> &str {
+        self.receipt.reference()
+    }
+
+    pub fn receipt(&self) -> &Receipt {
+        &self.receipt
+    }
+
+    pub fn va_number(&self) -> &str {
+        &self.va_number
+    }
+
+    pub fn company(&self) -> &str {
+        &self.company
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for PaymentReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Reference:       {}", self.receipt.reference())?;
+        writeln!(
+            f,
+            "Date:            {}",
+            self.receipt.date().format("%d/%m/%Y")
+        )?;
+        writeln!(f, "Virtual Account: {}", self.va_number)?;
+        writeln!(f, "Company:         {}", self.company)?;
+        writeln!(f, "Name:            {}", self.name)?;
+        write!(f, "Amount:          {}", self.receipt.amount())
+    }
+}
@@ -1,56 +1,52 @@
-use crate::resp_parser::resp_traits::{TuiList, TuiListCreator};
-use anyhow::Result;
+use crate::money::Money;
+use crate::resp_parser::parse_error::{check_maintenance, page_text, ParseError};
 use scraper::{Html, Selector};
 use std::borrow::Cow;
 
 #[derive(Debug, Default)]
 pub struct AccountBalance<'a> {
     account_number: Cow<'a, str>,
-    account_currency: Cow<'a, str>,
-    account_balance: Cow<'a, str>,
+    account_balance: Money,
 }
 
 impl<'a> AccountBalance<'a> {
-    pub fn new<S>(resp: S) -> Result<Self>
+    pub fn new<S>(resp: S) -> Result<Self, ParseError>
     where
         S: Into<Cow<'a, str>>,
     {
         let mut acc_bal = AccountBalance::default();
         let doc = Html::parse_document(&resp.into());
+        check_maintenance(&doc)?;
         let table_selector =
             Selector::parse("tr[bgcolor='#FFFFFF']>td").expect("saldo html document error");
-        let mut rows = doc.select(&table_selector);
-        acc_bal.account_number = rows
-            .next()
-            .expect("no saldo rows")
-            .text()
-            .collect::<String>()
-            .into();
-        acc_bal.account_currency = rows
-            .next()
-            .expect("no saldo rows")
-            .text()
-            .collect::<String>()
-            .into();
-        acc_bal.account_balance = rows
-            .next()
-            .expect("no saldo rows")
-            .text()
-            .collect::<String>()
-            .into();
+        let rows = doc
+            .select(&table_selector)
+            .map(|td| td.text().collect::<String>())
+            .collect::<Vec<String>>();
+        if rows.is_empty() {
+            return Err(ParseError::missing_table("balance", &page_text(&doc)));
+        }
+        if rows.len() < 3 {
+            return Err(ParseError::unexpected_layout(
+                "balance row has less than 3 columns",
+                &rows.join(" "),
+            ));
+        }
+        acc_bal.account_number = rows[0].clone().into();
+        acc_bal.account_balance = Money::parse(&rows[2], rows[1].as_str())
+            .map_err(|e| ParseError::unexpected_layout(e.to_string(), &rows[2]))?;
         Ok(acc_bal)
     }
 
-    pub fn account_balance_list(&self) -> TuiList {
-        self.to_tui_list()
+    pub fn account_number(&self) -> &str {
+        &self.account_number
+    }
+
+    pub fn currency(&self) -> &str {
+        self.account_balance.currency()
     }
-}
 
-impl<'a> TuiListCreator for AccountBalance<'a> {
-    fn to_tui_list(&self) -> TuiList {
-        TuiList::with_items(vec![
-            format!("{}", self.account_number).into(),
-            format!("{} {}", self.account_currency, self.account_balance).into(),
-        ])
+    pub fn balance(&self) -> &Money {
+        &self.account_balance
     }
 }
@@ -0,0 +1,31 @@
+use scraper::{Html, Selector};
+
+// markers klikbca uses when the server side session has timed out
+const EXPIRED_MARKERS: [&str; 4] = [
+    "session has expired",
+    "session expired",
+    "sesi anda telah berakhir",
+    "silakan login kembali",
+];
+
+// PageKind tells apart regular content from the pages klikbca serves to a dead session
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PageKind {
+    LoginForm,
+    SessionExpired,
+    Content,
+}
+
+pub fn classify_page(resp: &str) -> PageKind {
+    let lowered = resp.to_lowercase();
+    if EXPIRED_MARKERS.iter().any(|m| lowered.contains(m)) {
+        return PageKind::SessionExpired;
+    }
+    let doc = Html::parse_document(resp);
+    let login_input_selector =
+        Selector::parse(r#"input[name="value(user_id)"]"#).expect("login form selector error");
+    if doc.select(&login_input_selector).next().is_some() {
+        return PageKind::LoginForm;
+    }
+    PageKind::Content
+}
@@ -0,0 +1,168 @@
+use crate::money::Money;
+use crate::resp_parser::form_parser::{Fields, KeyBcaChallenge, Receipt};
+use crate::resp_parser::parse_error::{check_maintenance, page_text, ParseError};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Beneficiary is an entry of the registered transfer destinations (daftar transfer)
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Beneficiary {
+    account_number: String,
+    name: String,
+}
+
+impl Beneficiary {
+    pub fn new<A: Into<String>, N: Into<String>>(account_number: A, name: N) -> Self {
+        Beneficiary {
+            account_number: account_number.into(),
+            name: name.into(),
+        }
+    }
+
+    // the destination select of the transfer form, options read "<account> - <name>"
+    pub fn list_from_resp(resp: &str) -> Result<Vec<Self>, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let select_selector =
+            Selector::parse(r#"select[name="value(acctTo)"]"#).expect("beneficiary selector error");
+        let option_selector = Selector::parse("option").expect("beneficiary option error");
+        let select = doc
+            .select(&select_selector)
+            .next()
+            .ok_or_else(|| ParseError::missing_table("beneficiary", &page_text(&doc)))?;
+        select
+            .select(&option_selector)
+            .filter(|opt| !opt.value().attr("value").unwrap_or_default().is_empty())
+            .map(|opt| {
+                let account_number = opt.value().attr("value").unwrap_or_default().trim();
+                let text = opt.text().collect::<String>();
+                let name = text
+                    .split_once(" - ")
+                    .map(|(_, name)| name.trim())
+                    .ok_or_else(|| {
+                        ParseError::unexpected_layout("beneficiary without name", &text)
+                    })?;
+                Ok(Beneficiary::new(account_number, name))
+            })
+            .collect()
+    }
+
+    // "rekening tujuan" and "nama" of confirmation and receipt pages
+    pub(crate) fn from_fields(fields: &Fields) -> Result<Self, ParseError> {
+        Ok(Beneficiary::new(
+            fields.require("rekening tujuan")?,
+            fields.require("nama")?,
+        ))
+    }
+
+    pub fn account_number(&self) -> &str {
+        &self.account_number
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for Beneficiary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.account_number, self.name)
+    }
+}
+
+// TransferChallenge is klikbca's confirmation page, the transfer is only executed once
+// the KeyBCA response for the challenge is submitted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferChallenge {
+    beneficiary: Beneficiary,
+    amount: Money,
+    note: String,
+    keybca: KeyBcaChallenge,
+}
+
+impl TransferChallenge {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        Ok(TransferChallenge {
+            beneficiary: Beneficiary::from_fields(&fields)?,
+            amount: fields.money("jumlah")?,
+            note: fields.get("berita").unwrap_or_default().to_string(),
+            keybca: KeyBcaChallenge::from_fields(&fields)?,
+        })
+    }
+
+    pub fn beneficiary(&self) -> &Beneficiary {
+        &self.beneficiary
+    }
+
+    pub fn amount(&self) -> &Money {
+        &self.amount
+    }
+
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+
+    pub fn keybca(&self) -> &KeyBcaChallenge {
+        &self.keybca
+    }
+}
+
+impl fmt::Display for TransferChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "To:     {}", self.beneficiary)?;
+        writeln!(f, "Amount: {}", self.amount)?;
+        writeln!(f, "Note:   {}", self.note)?;
+        write!(f, "{}", self.keybca)
+    }
+}
+
+// TransferReceipt is the result page of an executed transfer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferReceipt {
+    receipt: Receipt,
+    beneficiary: Beneficiary,
+    note: String,
+}
+
+impl TransferReceipt {
+    pub fn from_resp(resp: &str) -> Result<Self, ParseError> {
+        let doc = Html::parse_document(resp);
+        check_maintenance(&doc)?;
+        let fields = Fields::from_doc(&doc);
+        Ok(TransferReceipt {
+            receipt: Receipt::from_fields(&fields)?,
+            beneficiary: Beneficiary::from_fields(&fields)?,
+            note: fields.get("berita").unwrap_or_default().to_string(),
+        })
+    }
+
+    pub fn reference(&self) -> &str {
+        self.receipt.reference()
+    }
+
+    pub fn receipt(&self) -> &Receipt {
+        &self.receipt
+    }
+
+    pub fn beneficiary(&self) -> &Beneficiary {
+        &self.beneficiary
+    }
+
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+}
+
+impl fmt::Display for TransferReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Reference: {}", self.receipt.reference())?;
+        writeln!(f, "Date:      {}", self.receipt.date().format("%d/%m/%Y"))?;
+        writeln!(f, "To:        {}", self.beneficiary)?;
+        writeln!(f, "Amount:    {}", self.receipt.amount())?;
+        write!(f, "Note:      {}", self.note)
+    }
+}
@@ -0,0 +1,332 @@
+use crate::accounts::{
+    BcaAccount, DateRange, InterbankTransferRequest, LoginError, PaymentRequest, SessionError,
+    StatementLimits, TransferRequest,
+};
+use crate::clients::{Client, ClientConfig};
+use crate::policy::TransferPolicy;
+use crate::resp_parser::{
+    beneficiary_parser::{BeneficiaryChallenge, RegisteredBeneficiary},
+    interbank_parser::{Bank, InterbankChallenge, InterbankReceipt, NameInquiry},
+    mutasi_parser::AccountMutasi,
+    parse_error::ParseError,
+    payment_parser::{PaymentChallenge, PaymentReceipt, VaInquiry},
+    saldo_parser::AccountBalance,
+    transfer_parser::{Beneficiary, TransferChallenge, TransferReceipt},
+};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local};
+
+// SessionState tracks the klikbca login of a BcaSession
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SessionState {
+    LoggedOut,
+    LoggedIn { since: DateTime<Local> },
+    // klikbca ended the session (idle timeout), the next request logs in again
+    Expired,
+    // klikbca rejected the login (or logging in again did not help),
+    // no further attempts are made so a wrong password can't get the user id blocked
+    Locked,
+}
+
+// BcaSession bundles the account credentials with the http client (and its cookie jar),
+// so library consumers only deal with plain domain structs.
+#[derive(Debug)]
+pub struct BcaSession {
+    account: BcaAccount,
+    client: Client,
+    state: SessionState,
+    policy: TransferPolicy,
+}
+
+impl BcaSession {
+    pub fn new(user: String, password: String) -> Result<Self> {
+        BcaSession::with_config(user, password, ClientConfig::default())
+    }
+
+    // session against the hosts in config (e.g. a local mock of klikbca)
+    pub fn with_config(user: String, password: String, config: ClientConfig) -> Result<Self> {
+        Ok(BcaSession {
+            account: BcaAccount::new(user, password),
+            client: Client::new(config)?,
+            state: SessionState::LoggedOut,
+            policy: TransferPolicy::default(),
+        })
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    // guard rails checked before any transaction or beneficiary change reaches klikbca
+    pub fn set_policy(&mut self, policy: TransferPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn policy(&self) -> &TransferPolicy {
+        &self.policy
+    }
+
+    pub fn is_logged_in(&self) -> bool {
+        matches!(self.state, SessionState::LoggedIn { .. })
+    }
+
+    pub fn login(&mut self) -> Result<()> {
+        if self.state == SessionState::Locked {
+            return Err(SessionError::Locked.into());
+        }
+        if let Err(e) = self.account.login(&mut self.client) {
+            if e.downcast_ref::<LoginError>().is_some() {
+                self.state = SessionState::Locked;
+            }
+            return Err(e);
+        }
+        self.state = SessionState::LoggedIn {
+            since: Local::now(),
+        };
+        Ok(())
+    }
+
+    // runs a request, re-authenticating once when klikbca reports the session as gone
+    fn with_session<T, F>(&mut self, request: F) -> Result<T>
+    where
+        F: Fn(&BcaAccount, &mut Client) -> Result<T>,
+    {
+        if !self.is_logged_in() {
+            self.login()?;
+        }
+        match request(&self.account, &mut self.client) {
+            Err(e) if is_expired(&e) => {
+                self.state = SessionState::Expired;
+                self.login()?;
+                let retried = request(&self.account, &mut self.client);
+                if let Err(e) = &retried {
+                    if is_expired(e) {
+                        self.state = SessionState::Locked;
+                    }
+                }
+                retried
+            }
+            resp => resp,
+        }
+    }
+
+    // runs the second step of a KeyBCA confirmed transaction. The prepared transaction lives
+    // in the klikbca session, so an expired session is not retried: it has to be prepared again.
+    fn in_current_session<T, F>(&mut self, request: F) -> Result<T>
+    where
+        F: FnOnce(&BcaAccount, &mut Client) -> Result<T>,
+    {
+        if !self.is_logged_in() {
+            return Err(SessionError::Expired.into());
+        }
+        let resp = request(&self.account, &mut self.client);
+        if let Err(e) = &resp {
+            if is_expired(e) {
+                self.state = SessionState::Expired;
+            }
+        }
+        resp
+    }
+
+    // current balance of the account
+    pub fn balance(&mut self) -> Result<AccountBalance<'static>> {
+        self.with_session(|acc, client| acc.get_saldo(client))
+    }
+
+    // account statement (mutasi rekening) for the given period
+    pub fn statement(&mut self, range: DateRange) -> Result<AccountMutasi<'static>> {
+        self.with_session(|acc, client| acc.get_mutasi(client, &range))
+    }
+
+    // statement over any period within limits, fetched in windows of at most limits.max_days
+    // and merged. progress is called before each window with its (1 based) number and the count.
+    pub fn statement_range<F>(
+        &mut self,
+        range: DateRange,
+        limits: &StatementLimits,
+        mut progress: F,
+    ) -> Result<AccountMutasi<'static>>
+    where
+        F: FnMut(usize, usize, &DateRange),
+    {
+        limits.check(&range)?;
+        let windows = range.windows(limits.max_days);
+        let mut statements = Vec::with_capacity(windows.len());
+        let mut empty = None;
+        for (i, window) in windows.iter().enumerate() {
+            progress(i + 1, windows.len(), window);
+            match self.statement(*window) {
+                Ok(statement) => statements.push(statement),
+                // a bare "no transactions" notice, the balance carries over to the next window
+                Err(e) if ParseError::is_empty_statement(&e) => empty = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+        match empty {
+            Some(e) if statements.is_empty() => Err(e),
+            _ => Ok(AccountMutasi::merge(range, statements)?),
+        }
+    }
+
+    // statement_range for callers that want a statement whatever klikbca has: a period without
+    // any transactions is an empty statement instead of an error. Its balance is where the days
+    // after the period start from, or the current balance if nothing happened since either.
+    pub fn statement_range_or_empty<F>(
+        &mut self,
+        range: DateRange,
+        limits: &StatementLimits,
+        progress: F,
+    ) -> Result<AccountMutasi<'static>>
+    where
+        F: FnMut(usize, usize, &DateRange),
+    {
+        match self.statement_range(range, limits, progress) {
+            Err(e) if ParseError::is_empty_statement(&e) => {}
+            result => return result,
+        }
+        let current = self.balance()?;
+        let mut balance = current.balance().clone();
+        if range.end < limits.latest {
+            let since = DateRange::new(range.end + Duration::days(1), limits.latest);
+            match self.statement_range(since, limits, |_, _, _| {}) {
+                Ok(since) => balance = since.summary().balance_begin().clone(),
+                Err(e) if ParseError::is_empty_statement(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(AccountMutasi::empty(
+            current.account_number(),
+            range,
+            &balance,
+        ))
+    }
+
+    // registered destinations for transfers between BCA accounts
+    pub fn beneficiaries(&mut self) -> Result<Vec<Beneficiary>> {
+        self.with_session(|acc, client| acc.get_beneficiaries(client))
+    }
+
+    // first step of a transfer, the returned challenge is keyed into the KeyBCA token
+    pub fn prepare_transfer(&mut self, request: &TransferRequest) -> Result<TransferChallenge> {
+        self.policy.check_mutation()?;
+        self.policy.check_amount(request.amount)?;
+        self.with_session(|acc, client| acc.prepare_transfer(client, request))
+    }
+
+    // second step of a transfer, with the response read off the KeyBCA token
+    pub fn confirm_transfer(&mut self, keybca_response: &str) -> Result<TransferReceipt> {
+        self.policy.check_confirmation()?;
+        self.in_current_session(|acc, client| acc.confirm_transfer(client, keybca_response))
+    }
+
+    // destination banks for interbank transfers
+    pub fn banks(&mut self) -> Result<Vec<Bank>> {
+        self.with_session(|acc, client| acc.get_banks(client))
+    }
+
+    // name of the account holder at another bank
+    pub fn inquire_account(
+        &mut self,
+        bank_code: &str,
+        account_number: &str,
+    ) -> Result<NameInquiry> {
+        self.with_session(|acc, client| acc.inquire_account(client, bank_code, account_number))
+    }
+
+    pub fn prepare_interbank_transfer(
+        &mut self,
+        request: &InterbankTransferRequest,
+    ) -> Result<InterbankChallenge> {
+        self.policy.check_mutation()?;
+        self.policy.check_amount(request.amount)?;
+        let challenge =
+            self.with_session(|acc, client| acc.prepare_interbank_transfer(client, request))?;
+        // the fee leaves the account too, it is only known from the challenge
+        self.policy.check_amount(challenge.total().amount())?;
+        Ok(challenge)
+    }
+
+    pub fn confirm_interbank_transfer(
+        &mut self,
+        keybca_response: &str,
+    ) -> Result<InterbankReceipt> {
+        self.policy.check_confirmation()?;
+        self.in_current_session(|acc, client| {
+            acc.confirm_interbank_transfer(client, keybca_response)
+        })
+    }
+
+    // the bill behind a virtual account number
+    pub fn inquire_va(&mut self, va_number: &str) -> Result<VaInquiry> {
+        self.with_session(|acc, client| acc.inquire_va(client, va_number))
+    }
+
+    pub fn prepare_payment(&mut self, request: &PaymentRequest) -> Result<PaymentChallenge> {
+        self.policy.check_mutation()?;
+        // a fixed amount VA bills what the inquiry says, which is only a lookup
+        let amount = match request.amount {
+            Some(amount) => Some(amount),
+            None => self
+                .inquire_va(&request.va_number)?
+                .amount()
+                .map(|billed| billed.amount()),
+        };
+        match amount {
+            Some(amount) => self.policy.check_amount(amount)?,
+            None => self.policy.check_unknown_amount()?,
+        }
+        let challenge = self.with_session(|acc, client| acc.prepare_payment(client, request))?;
+        // what klikbca is about to debit, whatever was asked for
+        self.policy.check_amount(challenge.amount().amount())?;
+        Ok(challenge)
+    }
+
+    pub fn confirm_payment(&mut self, keybca_response: &str) -> Result<PaymentReceipt> {
+        self.policy.check_confirmation()?;
+        self.in_current_session(|acc, client| acc.confirm_payment(client, keybca_response))
+    }
+
+    // the whole daftar transfer, BCA and other banks
+    pub fn registered_beneficiaries(&mut self) -> Result<Vec<RegisteredBeneficiary>> {
+        self.with_session(|acc, client| acc.get_registered_beneficiaries(client))
+    }
+
+    pub fn prepare_add_beneficiary(
+        &mut self,
+        bank_code: &str,
+        account_number: &str,
+    ) -> Result<BeneficiaryChallenge> {
+        self.policy.check_mutation()?;
+        self.with_session(|acc, client| {
+            acc.prepare_add_beneficiary(client, bank_code, account_number)
+        })
+    }
+
+    pub fn confirm_add_beneficiary(
+        &mut self,
+        keybca_response: &str,
+    ) -> Result<RegisteredBeneficiary> {
+        self.policy.check_confirmation()?;
+        self.in_current_session(|acc, client| acc.confirm_add_beneficiary(client, keybca_response))
+    }
+
+    // returns the remaining beneficiaries
+    pub fn remove_beneficiary(
+        &mut self,
+        bank_code: &str,
+        account_number: &str,
+    ) -> Result<Vec<RegisteredBeneficiary>> {
+        self.policy.check_mutation()?;
+        self.with_session(|acc, client| acc.remove_beneficiary(client, bank_code, account_number))
+    }
+
+    pub fn logout(&mut self) -> Result<()> {
+        self.account.logout(&mut self.client)?;
+        self.state = SessionState::LoggedOut;
+        Ok(())
+    }
+}
+
+fn is_expired(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<SessionError>() == Some(&SessionError::Expired)
+}
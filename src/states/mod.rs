@@ -1 +1,3 @@
+pub(crate) mod resp_traits;
+#[allow(clippy::module_inception)]
 pub(crate) mod states;
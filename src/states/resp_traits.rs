@@ -0,0 +1,175 @@
+use bca_rust::{
+    AccountBalance, AccountInfo, AccountMutasi, MutationSummary, RegisteredBeneficiary,
+    StoredReceipt,
+};
+use tui::widgets::TableState;
+
+pub trait TuiTableCreator {
+    fn to_tui_table(&self) -> TuiTable;
+}
+
+pub trait TuiListCreator {
+    fn to_tui_list(&self) -> TuiList;
+}
+
+#[derive(Clone)]
+pub struct TuiTable {
+    pub state: TableState,
+    pub items: Vec<Vec<String>>,
+}
+
+impl TuiTable {
+    pub fn new(items: Vec<Vec<String>>) -> TuiTable {
+        TuiTable {
+            state: TableState::default(),
+            items,
+        }
+    }
+    pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i >= self.items.len() - 1 {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    self.items.len() - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+}
+
+#[derive(Clone)]
+pub struct TuiList {
+    pub items: Vec<String>,
+}
+
+impl TuiList {
+    pub fn new() -> TuiList {
+        TuiList { items: Vec::new() }
+    }
+
+    pub fn with_items(items: Vec<String>) -> TuiList {
+        TuiList { items }
+    }
+}
+
+impl<'a> TuiListCreator for AccountInfo<'a> {
+    fn to_tui_list(&self) -> TuiList {
+        TuiList {
+            items: vec![
+                format!("Account Number:\t{}", self.account_number()),
+                format!("Account Owner:\t{}", self.owner_name()),
+                format!("Period:\t{}", self.period()),
+                format!("Account Currency:\t{}", self.currency()),
+            ],
+        }
+    }
+}
+
+impl<'a> TuiTableCreator for AccountMutasi<'a> {
+    fn to_tui_table(&self) -> TuiTable {
+        let all_txes = self
+            .transactions()
+            .iter()
+            .map(|atx| {
+                let date = if atx.is_pending() {
+                    "PEND".to_string()
+                } else {
+                    atx.date().format("%d/%m").to_string()
+                };
+                vec![
+                    date,
+                    atx.description().to_string(),
+                    atx.amount().to_string(),
+                    atx.direction().to_string(),
+                ]
+            })
+            .collect::<Vec<Vec<String>>>();
+        TuiTable::new(all_txes)
+    }
+}
+
+impl TuiListCreator for MutationSummary {
+    fn to_tui_list(&self) -> TuiList {
+        TuiList {
+            items: vec![
+                "Starting Balance".into(),
+                self.balance_begin().to_string(),
+                "Credit Mutations".into(),
+                self.total_credits().to_string(),
+                "Debit Mutations".into(),
+                self.total_debits().to_string(),
+                "Balance".into(),
+                self.balance_end().to_string(),
+            ],
+        }
+    }
+}
+
+impl<'a> TuiListCreator for AccountBalance<'a> {
+    fn to_tui_list(&self) -> TuiList {
+        TuiList::with_items(vec![
+            self.account_number().to_string(),
+            self.balance().to_string(),
+        ])
+    }
+}
+
+// newest receipt first
+impl TuiTableCreator for [StoredReceipt] {
+    fn to_tui_table(&self) -> TuiTable {
+        let rows = self
+            .iter()
+            .rev()
+            .map(|r| {
+                vec![
+                    r.date.format("%d/%m/%Y").to_string(),
+                    format!("{:?}", r.kind),
+                    r.reference.clone(),
+                    format!("{} {}", r.account, r.name),
+                    r.amount.to_string(),
+                ]
+            })
+            .collect::<Vec<Vec<String>>>();
+        TuiTable::new(rows)
+    }
+}
+
+impl TuiTableCreator for [RegisteredBeneficiary] {
+    fn to_tui_table(&self) -> TuiTable {
+        let rows = self
+            .iter()
+            .map(|b| {
+                vec![
+                    b.bank().code().to_string(),
+                    b.bank().name().to_string(),
+                    b.beneficiary().account_number().to_string(),
+                    b.beneficiary().name().to_string(),
+                ]
+            })
+            .collect::<Vec<Vec<String>>>();
+        TuiTable::new(rows)
+    }
+}
@@ -1,20 +1,28 @@
-use crate::resp_parser::{
-    mutasi_parser::AccountMutasi,
-    resp_traits::{TuiList, TuiTable},
-    saldo_parser::AccountBalance,
-};
+use crate::states::resp_traits::{TuiList, TuiListCreator, TuiTable, TuiTableCreator};
 use anyhow::Result;
+use bca_rust::{AccountBalance, AccountMutasi, DateRange, RegisteredBeneficiary, StoredReceipt};
 use chrono::{offset::Local, DateTime, Duration, NaiveDate};
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum InputMode {
     Normal,
     Editing,
+    // bank code and account number of a new beneficiary
+    AddBeneficiary,
+    // response to the KeyBCA challenge in status
+    KeyBca,
+}
+// which table the dashboard shows
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Screen {
+    Statements,
+    Receipts,
+    Beneficiaries,
 }
+
 // Global AppState
 #[derive(Clone)]
 pub struct AppState {
-    pub is_logged_in: bool,
     pub input_string: String,
     pub input_mode: InputMode,
     pub start_date: NaiveDate,
@@ -23,33 +31,98 @@ pub struct AppState {
     pub account_mutations: TuiTable,
     pub account_balance: TuiList,
     pub account_summary: TuiList,
+    pub screen: Screen,
+    // locally stored transfer and payment receipts
+    pub receipts: TuiTable,
+    // the daftar transfer
+    pub beneficiaries: TuiTable,
+    pub beneficiary_entries: Vec<RegisteredBeneficiary>,
+    // entry waiting for y/n before it is removed
+    pub pending_removal: Option<RegisteredBeneficiary>,
+    // prompts and results of beneficiary changes
+    pub status: Option<String>,
+    // last refresh error, shown in the dashboard instead of tearing it down
+    pub last_error: Option<String>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         let now = Local::now();
         AppState {
-            // is logged in to klikbca individual
             input_string: String::new(),
             input_mode: InputMode::Normal,
             start_date: add_date(now, Duration::days(-7)),
             end_date: add_date(now, Duration::seconds(0)),
-            is_logged_in: false,
             account_info: TuiList::new(),
             account_mutations: TuiTable::new(Vec::new()),
             account_balance: TuiList::new(),
             account_summary: TuiList::new(),
+            screen: Screen::Statements,
+            receipts: TuiTable::new(Vec::new()),
+            beneficiaries: TuiTable::new(Vec::new()),
+            beneficiary_entries: Vec::new(),
+            pending_removal: None,
+            status: None,
+            last_error: None,
+        }
+    }
+
+    pub fn date_range(&self) -> DateRange {
+        DateRange::new(self.start_date, self.end_date)
+    }
+
+    pub fn update_balance(&mut self, saldo: &AccountBalance) {
+        self.account_balance = saldo.to_tui_list();
+    }
+
+    pub fn update_mutations(&mut self, mutasi: &AccountMutasi) {
+        self.account_mutations = mutasi.to_tui_table();
+        self.account_info = mutasi.info().to_tui_list();
+        self.account_summary = mutasi.summary().to_tui_list();
+    }
+
+    pub fn update_receipts(&mut self, receipts: &[StoredReceipt]) {
+        self.receipts = receipts.to_tui_table();
+    }
+
+    pub fn update_beneficiaries(&mut self, entries: &[RegisteredBeneficiary]) {
+        let selected = self.beneficiaries.state.selected();
+        self.beneficiaries = entries.to_tui_table();
+        if let Some(i) = selected.filter(|i| *i < entries.len()) {
+            self.beneficiaries.state.select(Some(i));
         }
+        self.beneficiary_entries = entries.to_vec();
+    }
+
+    // switches to screen, or back to the statements when it is already shown
+    pub fn toggle_screen(&mut self, screen: Screen) {
+        self.screen = if self.screen == screen {
+            Screen::Statements
+        } else {
+            screen
+        };
+        self.cancel();
     }
 
-    pub fn update_balance(&mut self, saldo: AccountBalance) {
-        self.account_balance = saldo.account_balance_list();
+    // asks for confirmation before the selected beneficiary is removed
+    pub fn select_removal(&mut self) {
+        let selected = self
+            .beneficiaries
+            .state
+            .selected()
+            .and_then(|i| self.beneficiary_entries.get(i))
+            .cloned();
+        self.status = selected
+            .as_ref()
+            .map(|entry| format!("remove {}? (y/n)", entry));
+        self.pending_removal = selected;
     }
 
-    pub fn update_mutations(&mut self, mutasi: AccountMutasi) {
-        self.account_mutations = mutasi.account_tx_table();
-        self.account_info = mutasi.account_info_list();
-        self.account_summary = mutasi.account_summary_list();
+    // drops a pending removal or a half entered beneficiary
+    pub fn cancel(&mut self) {
+        self.pending_removal = None;
+        self.status = None;
+        self.input_string.clear();
     }
 
     pub fn update_dates(&mut self) -> Result<()> {
@@ -68,16 +141,20 @@ impl AppState {
         Ok(())
     }
 
-    pub fn toggle_login(&mut self) {
-        self.is_logged_in = !self.is_logged_in;
-    }
-
     pub fn on_up(&mut self) {
-        self.account_mutations.previous();
+        match self.screen {
+            Screen::Statements => self.account_mutations.previous(),
+            Screen::Receipts => self.receipts.previous(),
+            Screen::Beneficiaries => self.beneficiaries.previous(),
+        }
     }
 
     pub fn on_down(&mut self) {
-        self.account_mutations.next();
+        match self.screen {
+            Screen::Statements => self.account_mutations.next(),
+            Screen::Receipts => self.receipts.next(),
+            Screen::Beneficiaries => self.beneficiaries.next(),
+        }
     }
 }
 
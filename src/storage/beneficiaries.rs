@@ -0,0 +1,60 @@
+use crate::resp_parser::beneficiary_parser::RegisteredBeneficiary;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BENEFICIARIES_FILE: &str = "beneficiaries.json";
+
+// CachedBeneficiaries is the last daftar transfer fetched from klikbca
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedBeneficiaries {
+    pub fetched_at: DateTime<Local>,
+    pub entries: Vec<RegisteredBeneficiary>,
+}
+
+// BeneficiaryCache keeps the beneficiary list on disk, so it can be shown without logging in
+#[derive(Clone, Debug)]
+pub struct BeneficiaryCache {
+    path: PathBuf,
+}
+
+impl BeneficiaryCache {
+    pub fn new(data_dir: &Path) -> Self {
+        BeneficiaryCache {
+            path: data_dir.join(BENEFICIARIES_FILE),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // replaces the cached list, written to a temporary file first so a crash can't truncate it
+    pub fn save(&self, entries: &[RegisteredBeneficiary]) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let cached = CachedBeneficiaries {
+            fetched_at: Local::now(),
+            entries: entries.to_vec(),
+        };
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&cached)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    // None when the list was never fetched
+    pub fn load(&self) -> Result<Option<CachedBeneficiaries>> {
+        let json = match fs::read_to_string(&self.path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let cached = serde_json::from_str(&json)
+            .map_err(|e| anyhow!("{}: invalid beneficiary cache: {}", self.path.display(), e))?;
+        Ok(Some(cached))
+    }
+}
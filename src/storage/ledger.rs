@@ -0,0 +1,377 @@
+use crate::accounts::DateRange;
+use crate::money::{Amount, Money};
+use crate::resp_parser::{
+    mutasi_parser::{AccountMutasi, AccountTx, Direction},
+    saldo_parser::AccountBalance,
+};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+const LEDGER_FILE: &str = "ledger.sqlite3";
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    id TEXT PRIMARY KEY,
+    account TEXT NOT NULL,
+    date TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    direction TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    fetched_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS transactions_by_date ON transactions (account, date, position);
+CREATE TABLE IF NOT EXISTS balances (
+    account TEXT NOT NULL,
+    as_of TEXT NOT NULL,
+    taken_at TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    source TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS balances_by_date ON balances (account, as_of);
+";
+
+// stable id of a booked transaction: the same row of klikbca's statement gets the same id in
+// every fetch. position is the index of the transaction within its day, which tells apart
+// identical transactions (two equal ATM withdrawals) on the same day.
+pub fn transaction_id(account: &str, tx: &AccountTx, position: usize) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(
+        format!(
+            "{}|{}|{}|{}|{}|{}",
+            account,
+            tx.date().format("%Y-%m-%d"),
+            tx.amount().minor(),
+            tx.direction().code(),
+            tx.description(),
+            position
+        )
+        .as_bytes(),
+    );
+    hasher.finalize()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// the booked (not pending) transactions of a statement with their position within the day, as
+// used in their transaction_id
+pub fn booked_transactions<'s>(statement: &'s AccountMutasi) -> Vec<(&'s AccountTx, usize)> {
+    let mut day = None;
+    let mut position = 0;
+    statement
+        .transactions()
+        .iter()
+        .filter(|tx| !tx.is_pending())
+        .map(|tx| {
+            if day == Some(tx.date()) {
+                position += 1;
+            } else {
+                day = Some(tx.date());
+                position = 0;
+            }
+            (tx, position)
+        })
+        .collect()
+}
+
+// LedgerTx is a transaction as kept in the ledger
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LedgerTx {
+    pub id: String,
+    pub account: String,
+    pub date: NaiveDate,
+    pub position: usize,
+    pub description: String,
+    pub amount: Amount,
+    pub direction: Direction,
+    pub currency: String,
+}
+
+impl LedgerTx {
+    // amount signed by direction, debits are negative
+    pub fn signed_amount(&self) -> Amount {
+        match self.direction {
+            Direction::Debit => -self.amount,
+            Direction::Credit => self.amount,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BalanceSource {
+    // the balance inquiry page
+    Inquiry,
+    // the ending balance of a statement
+    Statement,
+}
+
+impl BalanceSource {
+    fn code(self) -> &'static str {
+        match self {
+            BalanceSource::Inquiry => "inquiry",
+            BalanceSource::Statement => "statement",
+        }
+    }
+}
+
+// BalanceSnapshot is the balance of the account at the end of as_of
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BalanceSnapshot {
+    pub account: String,
+    pub as_of: NaiveDate,
+    pub balance: Money,
+}
+
+// LedgerInsert counts what a statement added to the ledger
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LedgerInsert {
+    pub inserted: usize,
+    // already in the ledger from an earlier, overlapping fetch
+    pub duplicates: usize,
+    // not booked yet (PEND), they get their final date later
+    pub pending: usize,
+}
+
+// Ledger is the local history of the account in sqlite: every booked transaction klikbca
+// returned and the balances seen, so the history outlives klikbca's two months.
+pub struct Ledger {
+    conn: Connection,
+}
+
+impl Ledger {
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(data_dir)?;
+        let path = data_dir.join(LEDGER_FILE);
+        let conn = Connection::open(&path)
+            .map_err(|e| anyhow!("{}: can't open ledger: {}", path.display(), e))?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(anyhow!(
+                "{}: ledger was written by a newer version (schema {})",
+                path.display(),
+                version
+            ));
+        }
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(Ledger { conn })
+    }
+
+    // stores the booked transactions of a statement and its ending balance, transactions
+    // already in the ledger are skipped
+    pub fn insert_statement(&mut self, statement: &AccountMutasi) -> Result<LedgerInsert> {
+        let info = statement.info();
+        let account = info.account_number();
+        let period = info.period_range()?;
+        let now = Local::now().to_rfc3339();
+        let mut counts = LedgerInsert::default();
+        let db = self.conn.transaction()?;
+        {
+            let mut insert = db.prepare(
+                "INSERT OR IGNORE INTO transactions
+                 (id, account, date, position, description, amount, direction, currency, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            counts.pending = statement
+                .transactions()
+                .iter()
+                .filter(|tx| tx.is_pending())
+                .count();
+            for (tx, position) in booked_transactions(statement) {
+                let changed = insert.execute(params![
+                    transaction_id(account, tx, position),
+                    account,
+                    tx.date().format("%Y-%m-%d").to_string(),
+                    position as i64,
+                    tx.description(),
+                    tx.amount().minor(),
+                    tx.direction().code(),
+                    info.currency(),
+                    now,
+                ])?;
+                if changed == 0 {
+                    counts.duplicates += 1;
+                } else {
+                    counts.inserted += 1;
+                }
+            }
+        }
+        insert_balance(
+            &db,
+            account,
+            period.end,
+            statement.summary().balance_end(),
+            BalanceSource::Statement,
+        )?;
+        db.commit()?;
+        Ok(counts)
+    }
+
+    // stores the balance of the inquiry page as of today. Returns false if the latest snapshot
+    // already was that balance today: the dashboard asks on every refresh.
+    pub fn insert_balance(&mut self, balance: &AccountBalance) -> Result<bool> {
+        insert_balance(
+            &self.conn,
+            balance.account_number(),
+            Local::now().naive_local().date(),
+            balance.balance(),
+            BalanceSource::Inquiry,
+        )
+    }
+
+    // transactions of account in booking order, all of them without a range
+    pub fn transactions(&self, account: &str, range: Option<DateRange>) -> Result<Vec<LedgerTx>> {
+        let (start, end) = match range {
+            Some(range) => (
+                range.start.format("%Y-%m-%d").to_string(),
+                range.end.format("%Y-%m-%d").to_string(),
+            ),
+            None => ("0000-01-01".to_string(), "9999-12-31".to_string()),
+        };
+        let mut query = self.conn.prepare(
+            "SELECT id, account, date, position, description, amount, direction, currency
+             FROM transactions WHERE account = ?1 AND date BETWEEN ?2 AND ?3
+             ORDER BY date, position",
+        )?;
+        let rows = query.query_map(params![account, start, end], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (id, account, date, position, description, amount, direction, currency) = row?;
+            Ok(LedgerTx {
+                id,
+                account,
+                date: parse_date(&date)?,
+                position: position as usize,
+                description,
+                amount: Amount::from_minor(amount),
+                direction: direction.parse()?,
+                currency,
+            })
+        })
+        .collect()
+    }
+
+    // date of the newest booked transaction of account
+    pub fn last_transaction_date(&self, account: &str) -> Result<Option<NaiveDate>> {
+        let date: Option<String> = self.conn.query_row(
+            "SELECT MAX(date) FROM transactions WHERE account = ?1",
+            params![account],
+            |row| row.get(0),
+        )?;
+        date.map(|d| parse_date(&d)).transpose()
+    }
+
+    // last day a statement of account was fetched through. Transactions of that day may still
+    // have been booked after the fetch, so a sync starts again from it.
+    pub fn synced_through(&self, account: &str) -> Result<Option<NaiveDate>> {
+        let date: Option<String> = self.conn.query_row(
+            "SELECT MAX(as_of) FROM balances WHERE account = ?1 AND source = ?2",
+            params![account, BalanceSource::Statement.code()],
+            |row| row.get(0),
+        )?;
+        date.map(|d| parse_date(&d)).transpose()
+    }
+
+    // the most recent balance of account as of the end of a day, None before the first fetch
+    pub fn balance_at(&self, account: &str, day: NaiveDate) -> Result<Option<BalanceSnapshot>> {
+        self.conn
+            .query_row(
+                "SELECT as_of, amount, currency FROM balances
+                 WHERE account = ?1 AND as_of <= ?2
+                 ORDER BY as_of DESC, taken_at DESC LIMIT 1",
+                params![account, day.format("%Y-%m-%d").to_string()],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()?
+            .map(|(as_of, amount, currency)| {
+                Ok(BalanceSnapshot {
+                    account: account.to_string(),
+                    as_of: parse_date(&as_of)?,
+                    balance: Money::new(Amount::from_minor(amount), currency),
+                })
+            })
+            .transpose()
+    }
+
+    // every account the ledger has transactions or balances of
+    pub fn accounts(&self) -> Result<Vec<String>> {
+        let mut query = self.conn.prepare(
+            "SELECT account FROM transactions UNION SELECT account FROM balances ORDER BY account",
+        )?;
+        let accounts = query
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(accounts)
+    }
+}
+
+// stores a snapshot unless it repeats the latest one of the account and source
+fn insert_balance(
+    conn: &Connection,
+    account: &str,
+    as_of: NaiveDate,
+    balance: &Money,
+    source: BalanceSource,
+) -> Result<bool> {
+    let as_of = as_of.format("%Y-%m-%d").to_string();
+    let latest: Option<(String, i64, String)> = conn
+        .query_row(
+            "SELECT as_of, amount, currency FROM balances WHERE account = ?1 AND source = ?2
+             ORDER BY taken_at DESC, rowid DESC LIMIT 1",
+            params![account, source.code()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    if latest
+        == Some((
+            as_of.clone(),
+            balance.amount().minor(),
+            balance.currency().to_string(),
+        ))
+    {
+        return Ok(false);
+    }
+    let taken_at: DateTime<Local> = Local::now();
+    conn.execute(
+        "INSERT INTO balances (account, as_of, taken_at, amount, currency, source)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            account,
+            as_of,
+            taken_at.to_rfc3339(),
+            balance.amount().minor(),
+            balance.currency(),
+            source.code(),
+        ],
+    )?;
+    Ok(true)
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|e| anyhow!("invalid ledger date {}: {}", s, e))
+}
@@ -0,0 +1,20 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::path::PathBuf;
+
+pub mod beneficiaries;
+pub mod ledger;
+pub mod receipts;
+
+// directory for local state (receipts, caches, the ledger), $BCA_DATA_DIR or the XDG data dir
+pub fn default_data_dir() -> Result<PathBuf> {
+    if let Some(dir) = env::var_os("BCA_DATA_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_DATA_HOME") {
+        return Ok(PathBuf::from(dir).join("bca-rust"));
+    }
+    env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".local/share/bca-rust"))
+        .ok_or_else(|| anyhow!("no data directory, set BCA_DATA_DIR"))
+}
@@ -0,0 +1,132 @@
+use crate::money::{Amount, Money};
+use crate::resp_parser::{
+    interbank_parser::InterbankReceipt, payment_parser::PaymentReceipt,
+    transfer_parser::TransferReceipt,
+};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const RECEIPTS_FILE: &str = "receipts.jsonl";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptKind {
+    Transfer,
+    Interbank,
+    Payment,
+}
+
+// StoredReceipt is the kept copy of a transaction receipt, klikbca does not show them again
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredReceipt {
+    pub kind: ReceiptKind,
+    pub reference: String,
+    pub date: NaiveDate,
+    pub amount: Money,
+    // charged on top of amount, only interbank transfers have one
+    #[serde(default)]
+    pub fee: Amount,
+    // destination account (or virtual account number) and its holder
+    pub account: String,
+    pub name: String,
+    pub note: String,
+}
+
+impl From<&TransferReceipt> for StoredReceipt {
+    fn from(r: &TransferReceipt) -> Self {
+        StoredReceipt {
+            kind: ReceiptKind::Transfer,
+            reference: r.reference().to_string(),
+            date: r.receipt().date(),
+            amount: r.receipt().amount().clone(),
+            fee: Amount::default(),
+            account: r.beneficiary().account_number().to_string(),
+            name: r.beneficiary().name().to_string(),
+            note: r.note().to_string(),
+        }
+    }
+}
+
+impl From<&InterbankReceipt> for StoredReceipt {
+    fn from(r: &InterbankReceipt) -> Self {
+        StoredReceipt {
+            kind: ReceiptKind::Interbank,
+            reference: r.reference().to_string(),
+            date: r.receipt().date(),
+            amount: r.receipt().amount().clone(),
+            fee: r.fee().amount(),
+            account: format!("{}/{}", r.bank().code(), r.beneficiary().account_number()),
+            name: r.beneficiary().name().to_string(),
+            note: r.note().to_string(),
+        }
+    }
+}
+
+impl From<&PaymentReceipt> for StoredReceipt {
+    fn from(r: &PaymentReceipt) -> Self {
+        StoredReceipt {
+            kind: ReceiptKind::Payment,
+            reference: r.reference().to_string(),
+            date: r.receipt().date(),
+            amount: r.receipt().amount().clone(),
+            fee: Amount::default(),
+            account: r.va_number().to_string(),
+            name: format!("{} {}", r.company(), r.name()),
+            note: String::new(),
+        }
+    }
+}
+
+// ReceiptStore appends receipts to a json lines file in the data directory
+#[derive(Clone, Debug)]
+pub struct ReceiptStore {
+    path: PathBuf,
+}
+
+impl ReceiptStore {
+    pub fn new(data_dir: &Path) -> Self {
+        ReceiptStore {
+            path: data_dir.join(RECEIPTS_FILE),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, receipt: &StoredReceipt) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(receipt)?)?;
+        Ok(())
+    }
+
+    // every stored receipt, oldest first. A missing file is an empty store.
+    pub fn load(&self) -> Result<Vec<StoredReceipt>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut receipts = vec![];
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            receipts.push(serde_json::from_str(&line).map_err(|e| {
+                anyhow!("{}:{}: invalid receipt: {}", self.path.display(), i + 1, e)
+            })?);
+        }
+        Ok(receipts)
+    }
+}
@@ -0,0 +1,62 @@
+use crate::accounts::{DateRange, StatementLimits};
+use crate::money::Money;
+use crate::resp_parser::parse_error::ParseError;
+use crate::session::BcaSession;
+use crate::storage::ledger::{Ledger, LedgerInsert};
+use anyhow::Result;
+use std::cmp;
+
+// SyncReport is what a sync added to the ledger
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncReport {
+    pub account: String,
+    // period fetched from klikbca
+    pub range: DateRange,
+    pub added: LedgerInsert,
+    // days between the previous sync and klikbca's oldest statement day, lost for good
+    pub gap: Option<DateRange>,
+    pub balance: Money,
+}
+
+// brings the ledger up to date with klikbca: the balance is stored and only the period since
+// the last synced day is fetched, the whole history klikbca keeps on the first run. progress
+// is called before each statement window, as in BcaSession::statement_range.
+pub fn sync<F>(
+    session: &mut BcaSession,
+    ledger: &mut Ledger,
+    limits: &StatementLimits,
+    progress: F,
+) -> Result<SyncReport>
+where
+    F: FnMut(usize, usize, &DateRange),
+{
+    let balance = session.balance()?;
+    let account = balance.account_number().to_string();
+    ledger.insert_balance(&balance)?;
+    let (start, gap) = match ledger.synced_through(&account)? {
+        Some(last) if last < limits.earliest => (
+            limits.earliest,
+            Some(DateRange::new(
+                last.succ_opt().unwrap_or(last),
+                limits.earliest.pred_opt().unwrap_or(limits.earliest),
+            ))
+            .filter(|gap| gap.start <= gap.end),
+        ),
+        Some(last) => (cmp::min(last, limits.latest), None),
+        None => (limits.earliest, None),
+    };
+    let range = DateRange::new(start, limits.latest);
+    let added = match session.statement_range(range, limits, progress) {
+        Ok(statement) => ledger.insert_statement(&statement)?,
+        // not a single transaction in the period, nothing to add
+        Err(e) if ParseError::is_empty_statement(&e) => LedgerInsert::default(),
+        Err(e) => return Err(e),
+    };
+    Ok(SyncReport {
+        account,
+        range,
+        added,
+        gap,
+        balance: balance.balance().clone(),
+    })
+}
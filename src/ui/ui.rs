@@ -1,4 +1,4 @@
-use crate::states::states::AppState;
+use crate::states::states::{AppState, Screen};
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -11,7 +11,83 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut AppState) {
     let chunks = Layout::default()
         .constraints([Constraint::Min(0)].as_ref())
         .split(f.size());
-    draw_account_statements(f, app, chunks[0]);
+    match app.screen {
+        Screen::Statements => draw_account_statements(f, app, chunks[0]),
+        Screen::Receipts => draw_receipts(f, app, chunks[0]),
+        Screen::Beneficiaries => draw_beneficiaries(f, app, chunks[0]),
+    }
+}
+
+fn draw_beneficiaries<B: Backend>(f: &mut Frame<B>, app: &mut AppState, area: Rect) {
+    let chunks = Layout::default()
+        .constraints([Constraint::Percentage(10), Constraint::Percentage(90)].as_ref())
+        .split(area);
+    let text = [Text::raw(&app.input_string)];
+    let (title, title_style) = match (&app.last_error, &app.status) {
+        (Some(err), _) => (err.clone(), Style::default().fg(Color::Red)),
+        (None, Some(status)) => (status.clone(), Style::default().fg(Color::Green)),
+        (None, None) => ("a: add, d: remove selected".to_string(), Style::default()),
+    };
+    let input = Paragraph::new(text.iter())
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(&title)
+                .title_style(title_style),
+        );
+    f.render_widget(input, chunks[0]);
+
+    let tbl_selected_style = Style::default().fg(Color::Cyan).modifier(Modifier::BOLD);
+    let tbl_normal_style = Style::default();
+    let tbl_header = ["Code", "Bank", "Account", "Name"];
+    let rows = app
+        .beneficiaries
+        .items
+        .iter()
+        .map(|i| Row::StyledData(i.iter(), tbl_normal_style));
+    let t = Table::new(tbl_header.iter(), rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Beneficiaries (b: back to statements)"),
+        )
+        .highlight_style(tbl_selected_style)
+        .highlight_symbol("➜ ")
+        .widths(&[
+            Constraint::Percentage(8),
+            Constraint::Percentage(27),
+            Constraint::Percentage(25),
+            Constraint::Percentage(40),
+        ]);
+    f.render_stateful_widget(t, chunks[1], &mut app.beneficiaries.state);
+}
+
+fn draw_receipts<B: Backend>(f: &mut Frame<B>, app: &mut AppState, area: Rect) {
+    let tbl_selected_style = Style::default().fg(Color::Cyan).modifier(Modifier::BOLD);
+    let tbl_normal_style = Style::default();
+    let tbl_header = ["Date", "Type", "Reference", "To", "Amount"];
+    let rows = app
+        .receipts
+        .items
+        .iter()
+        .map(|i| Row::StyledData(i.iter(), tbl_normal_style));
+    let t = Table::new(tbl_header.iter(), rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Receipts (r: back to statements)"),
+        )
+        .highlight_style(tbl_selected_style)
+        .highlight_symbol("➜ ")
+        .widths(&[
+            Constraint::Percentage(12),
+            Constraint::Percentage(10),
+            Constraint::Percentage(15),
+            Constraint::Percentage(43),
+            Constraint::Percentage(20),
+        ]);
+    f.render_stateful_widget(t, area, &mut app.receipts.state);
 }
 
 fn draw_account_statements<B: Backend>(f: &mut Frame<B>, app: &mut AppState, area: Rect) {
@@ -34,12 +110,27 @@ fn draw_account_statements<B: Backend>(f: &mut Frame<B>, app: &mut AppState, are
 
 fn draw_input<B: Backend>(f: &mut Frame<B>, app: &mut AppState, area: Rect) {
     let text = [Text::raw(&app.input_string)];
+    let (title, title_style) = match (&app.last_error, &app.status) {
+        (Some(err), _) => (
+            format!("End Date: dd/mm/yyyy - {}", err),
+            Style::default().fg(Color::Red),
+        ),
+        (None, Some(status)) => (
+            format!("End Date: dd/mm/yyyy - {}", status),
+            Style::default().fg(Color::Green),
+        ),
+        (None, None) => (
+            "End Date: dd/mm/yyyy (x: export csv)".to_string(),
+            Style::default(),
+        ),
+    };
     let input = Paragraph::new(text.iter())
         .style(Style::default().fg(Color::Yellow))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("End Date: dd/mm/yyyy"),
+                .title(&title)
+                .title_style(title_style),
         );
     f.render_widget(input, area);
 }
@@ -59,7 +150,7 @@ fn draw_balance<B: Backend>(f: &mut Frame<B>, app: &mut AppState, area: Rect) {
         .items
         .iter()
         .step_by(2)
-        .map(|i| Text::raw(i))
+        .map(Text::raw)
         .collect::<Vec<Text>>();
     let info = Paragraph::new(info.iter())
         .block(Block::default().borders(Borders::NONE))
@@ -0,0 +1,91 @@
+use crate::resp_parser::mutasi_parser::AccountMutasi;
+use crate::storage::ledger::{booked_transactions, transaction_id, LedgerTx};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const WATCH_FILE: &str = "watch.json";
+
+// transaction ids seen per account, with their date so ids that can't show up again are dropped
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WatchState {
+    // set by the first poll, everything before it is history
+    #[serde(default)]
+    started: bool,
+    accounts: BTreeMap<String, BTreeMap<String, NaiveDate>>,
+}
+
+// Watcher tells the transactions of a statement apart from those seen in earlier polls. The
+// seen ids are kept in the data dir, so a restarted watch carries on where it stopped.
+pub struct Watcher {
+    path: PathBuf,
+    state: WatchState,
+}
+
+impl Watcher {
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        Watcher::open_named(data_dir, WATCH_FILE)
+    }
+
+    // a watcher with its own state file, so watches of different consumers don't share the
+    // seen ids
+    pub fn open_named(data_dir: &Path, file: &str) -> Result<Self> {
+        fs::create_dir_all(data_dir)?;
+        let path = data_dir.join(file);
+        let state = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| anyhow!("{}: invalid watch state: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => WatchState::default(),
+            Err(e) => return Err(anyhow!("{}: {}", path.display(), e)),
+        };
+        Ok(Watcher { path, state })
+    }
+
+    // booked transactions of statement not seen before, in statement order. The first poll
+    // only marks its transactions as seen: the history isn't new. Nothing is persisted until
+    // save.
+    pub fn observe(&mut self, statement: &AccountMutasi) -> Result<Vec<LedgerTx>> {
+        let info = statement.info();
+        let account = info.account_number();
+        let period = info.period_range()?;
+        let first = !self.state.started;
+        self.state.started = true;
+        let seen = self.state.accounts.entry(account.to_string()).or_default();
+        let mut new = vec![];
+        for (tx, position) in booked_transactions(statement) {
+            let id = transaction_id(account, tx, position);
+            if seen.insert(id.clone(), tx.date()).is_some() || first {
+                continue;
+            }
+            new.push(LedgerTx {
+                id,
+                account: account.to_string(),
+                date: tx.date(),
+                position,
+                description: tx.description().to_string(),
+                amount: tx.amount(),
+                direction: tx.direction(),
+                currency: info.currency().to_string(),
+            });
+        }
+        // polls only move forward, older transactions aren't fetched again
+        seen.retain(|_, date| *date >= period.start);
+        Ok(new)
+    }
+
+    // a first poll that found no statement at all, what comes after it is new
+    pub fn mark_started(&mut self) {
+        self.state.started = true;
+    }
+
+    // written to a temporary file first so a crash can't lose the seen ids
+    pub fn save(&self) -> Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string(&self.state)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
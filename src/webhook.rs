@@ -0,0 +1,303 @@
+use crate::export;
+use crate::storage::ledger::LedgerTx;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use isahc::prelude::*;
+use rusqlite::{params, Connection};
+use sha2::Sha256;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const QUEUE_FILE: &str = "webhooks.sqlite3";
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS deliveries (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL,
+    event TEXT NOT NULL,
+    body TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt INTEGER NOT NULL,
+    last_error TEXT,
+    delivered_at INTEGER,
+    given_up INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (url, event)
+);
+";
+
+// "sha256=" and the hex HMAC-SHA256 of the body, keyed with the shared secret
+pub const SIGNATURE_HEADER: &str = "X-Bca-Signature";
+// the transaction id, the same in every retry: receivers use it to drop duplicates
+pub const EVENT_HEADER: &str = "X-Bca-Event";
+// 1 on the first try
+pub const ATTEMPT_HEADER: &str = "X-Bca-Attempt";
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("hmac takes keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// checks a signature header in constant time, for receivers written in rust
+pub fn verify(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let expected = match signature
+        .strip_prefix("sha256=")
+        .and_then(|h| hex::decode(h).ok())
+    {
+        Some(expected) => expected,
+        None => return false,
+    };
+    let mut mac = HmacSha256::new_from_slice(secret).expect("hmac takes keys of any length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+// Backoff spaces the retries of a failed delivery: base, 2 * base, 4 * base, ... up to max,
+// until max_attempts were made
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    pub base: Duration,
+    pub max: Duration,
+    pub max_attempts: u32,
+}
+
+impl Default for Backoff {
+    // about a day of retries before giving up
+    fn default() -> Self {
+        Backoff {
+            base: Duration::from_secs(30),
+            max: Duration::from_secs(3600),
+            max_attempts: 30,
+        }
+    }
+}
+
+impl Backoff {
+    // wait after the given number of failed attempts
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        std::cmp::min(self.base.saturating_mul(factor), self.max)
+    }
+}
+
+// Delivery is one transaction to post to one url
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delivery {
+    pub id: i64,
+    pub url: String,
+    // transaction id
+    pub event: String,
+    pub body: String,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+// DeliveryRun counts what one pass over the due deliveries did
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeliveryRun {
+    pub delivered: usize,
+    // failed, tried again later
+    pub retrying: usize,
+    // failed for the last time
+    pub given_up: usize,
+}
+
+// DeliveryQueue keeps the webhooks to send in sqlite, so a restart neither loses nor repeats
+// them
+pub struct DeliveryQueue {
+    conn: Connection,
+}
+
+impl DeliveryQueue {
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(data_dir)?;
+        let path = data_dir.join(QUEUE_FILE);
+        let conn = Connection::open(&path)
+            .map_err(|e| anyhow!("{}: can't open webhook queue: {}", path.display(), e))?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(anyhow!(
+                "{}: queue was written by a newer version (schema {})",
+                path.display(),
+                version
+            ));
+        }
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(DeliveryQueue { conn })
+    }
+
+    // queues the transaction for every url, due right away. A transaction already queued for a
+    // url isn't queued again. Returns the number of deliveries added.
+    pub fn enqueue(&mut self, tx: &LedgerTx, urls: &[String], now: DateTime<Utc>) -> Result<usize> {
+        let mut body = vec![];
+        export::json::write_transaction(tx, &mut body)?;
+        let body = String::from_utf8(body)?;
+        let db = self.conn.transaction()?;
+        let mut added = 0;
+        for url in urls {
+            added += db.execute(
+                "INSERT OR IGNORE INTO deliveries (url, event, body, next_attempt)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![url, tx.id, body.trim_end(), now.timestamp()],
+            )?;
+        }
+        db.commit()?;
+        Ok(added)
+    }
+
+    // deliveries to attempt now, oldest first
+    pub fn due(&self, now: DateTime<Utc>) -> Result<Vec<Delivery>> {
+        self.select(
+            "WHERE delivered_at IS NULL AND given_up = 0 AND next_attempt <= ?1 ORDER BY id",
+            params![now.timestamp()],
+        )
+    }
+
+    // deliveries not yet made and still being retried
+    pub fn pending(&self) -> Result<Vec<Delivery>> {
+        self.select(
+            "WHERE delivered_at IS NULL AND given_up = 0 ORDER BY id",
+            params![],
+        )
+    }
+
+    // deliveries that failed max_attempts times
+    pub fn given_up(&self) -> Result<Vec<Delivery>> {
+        self.select("WHERE given_up = 1 ORDER BY id", params![])
+    }
+
+    // when the next pending delivery is due
+    pub fn next_due(&self) -> Result<Option<DateTime<Utc>>> {
+        let next: Option<i64> = self.conn.query_row(
+            "SELECT MIN(next_attempt) FROM deliveries WHERE delivered_at IS NULL AND given_up = 0",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(next.map(|t| Utc.timestamp_opt(t, 0).unwrap()))
+    }
+
+    fn select(&self, filter: &str, params: impl rusqlite::Params) -> Result<Vec<Delivery>> {
+        let mut query = self.conn.prepare(&format!(
+            "SELECT id, url, event, body, attempts, last_error FROM deliveries {}",
+            filter
+        ))?;
+        let deliveries = query
+            .query_map(params, |row| {
+                Ok(Delivery {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    event: row.get(2)?,
+                    body: row.get(3)?,
+                    attempts: row.get(4)?,
+                    last_error: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(deliveries)
+    }
+
+    fn delivered(&mut self, delivery: &Delivery, now: DateTime<Utc>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE deliveries SET attempts = attempts + 1, delivered_at = ?2, last_error = NULL
+             WHERE id = ?1",
+            params![delivery.id, now.timestamp()],
+        )?;
+        Ok(())
+    }
+
+    // records a failed attempt, the delivery is retried at retry_at or given up without one
+    fn failed(
+        &mut self,
+        delivery: &Delivery,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE deliveries SET attempts = attempts + 1, last_error = ?2,
+             next_attempt = COALESCE(?3, next_attempt), given_up = ?4
+             WHERE id = ?1",
+            params![
+                delivery.id,
+                error,
+                retry_at.map(|t| t.timestamp()),
+                retry_at.is_none()
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+// WebhookSender posts queued deliveries, signed with the shared secret
+pub struct WebhookSender {
+    client: HttpClient,
+    secret: Vec<u8>,
+    backoff: Backoff,
+}
+
+impl WebhookSender {
+    pub fn new(secret: &[u8], backoff: Backoff) -> Result<Self> {
+        if secret.is_empty() {
+            return Err(anyhow!("webhooks need a secret to sign them with"));
+        }
+        let client = HttpClient::builder()
+            .timeout(Duration::from_secs(30))
+            .redirect_policy(isahc::config::RedirectPolicy::None)
+            .build()?;
+        Ok(WebhookSender {
+            client,
+            secret: secret.to_vec(),
+            backoff,
+        })
+    }
+
+    // attempts every due delivery once, failures are rescheduled with the backoff
+    pub fn deliver_due(
+        &self,
+        queue: &mut DeliveryQueue,
+        now: DateTime<Utc>,
+    ) -> Result<DeliveryRun> {
+        let mut run = DeliveryRun::default();
+        for delivery in queue.due(now)? {
+            match self.post(&delivery) {
+                Ok(()) => {
+                    queue.delivered(&delivery, now)?;
+                    run.delivered += 1;
+                }
+                Err(e) => {
+                    let attempts = delivery.attempts + 1;
+                    if attempts >= self.backoff.max_attempts {
+                        queue.failed(&delivery, &e.to_string(), None)?;
+                        run.given_up += 1;
+                    } else {
+                        let delay = chrono::Duration::from_std(self.backoff.delay(attempts))?;
+                        queue.failed(&delivery, &e.to_string(), Some(now + delay))?;
+                        run.retrying += 1;
+                    }
+                }
+            }
+        }
+        Ok(run)
+    }
+
+    // anything but a 2xx answer is a failure
+    fn post(&self, delivery: &Delivery) -> Result<()> {
+        let body = delivery.body.as_bytes();
+        let req = Request::post(delivery.url.as_str())
+            .header("Content-Type", "application/json")
+            .header(SIGNATURE_HEADER, sign(&self.secret, body))
+            .header(EVENT_HEADER, delivery.event.as_str())
+            .header(ATTEMPT_HEADER, (delivery.attempts + 1).to_string())
+            .body(body.to_vec())?;
+        let resp = self.client.send(req)?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("{} answered {}", delivery.url, resp.status()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1441 @@
+use bca_rust::{
+    export,
+    mock::{MockServer, WebhookReceiver, KEYBCA_RESPONSE},
+    sync, webhook, AccountMutasi, AccountTx, Amount, Backoff, BatchReport, BcaSession,
+    BeneficiaryCache, CsvOptions, DateRange, DecimalStyle, DeliveryQueue, Direction, EntryStatus,
+    InterbankTransferRequest, InvoiceBook, InvoiceError, InvoiceStatus, JournalFlavor,
+    JournalOptions, JournalState, Ledger, LoginError, MappingRule, MatchOutcome, ParseError,
+    PaymentRequest, PolicyError, RangeError, ReceiptKind, ReceiptStore, SessionState,
+    StatementLimits, StoredReceipt, SyncReport, TransactionError, TransferBatch, TransferPolicy,
+    TransferRequest, TransferService, Watcher, WebhookSender, JSON_VERSION,
+};
+use chrono::{NaiveDate, Utc};
+
+const USER: &str = "budi1234";
+const PASSWORD: &str = "135790";
+
+fn session(server: &MockServer, password: &str) -> BcaSession {
+    BcaSession::with_config(
+        USER.to_string(),
+        password.to_string(),
+        server.client_config(),
+    )
+    .unwrap()
+}
+
+fn june_2020() -> DateRange {
+    DateRange::new(
+        NaiveDate::from_ymd_opt(2020, 6, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2020, 6, 7).unwrap(),
+    )
+}
+
+#[test]
+fn login_balance_statement_logout() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    session.login().unwrap();
+    assert!(session.is_logged_in());
+
+    let balance = session.balance().unwrap();
+    assert_eq!(balance.account_number(), "0123456789");
+    assert_eq!(
+        balance.balance().amount(),
+        Amount::from_minor(1_234_567_890)
+    );
+    assert_eq!(balance.currency(), "IDR");
+
+    let statement = session.statement(june_2020()).unwrap();
+    assert_eq!(statement.info().owner_name(), "BUDI SANTOSO");
+    assert_eq!(statement.transactions().len(), 3);
+    assert_eq!(statement.transactions()[0].direction(), Direction::Credit);
+    assert!(statement.summary().is_consistent());
+
+    session.logout().unwrap();
+    assert_eq!(session.state(), SessionState::LoggedOut);
+}
+
+#[test]
+fn expired_session_logs_in_again_once() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    session.login().unwrap();
+    session.balance().unwrap();
+
+    server.expire_sessions();
+    session.statement(june_2020()).unwrap();
+    assert_eq!(server.login_attempts(), 2);
+    assert!(session.is_logged_in());
+}
+
+#[test]
+fn wrong_password_stops_after_first_attempt() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, "000000");
+    let err = session.login().unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<LoginError>(),
+        Some(&LoginError::WrongCredentials)
+    );
+    assert_eq!(session.state(), SessionState::Locked);
+
+    assert!(session.balance().is_err());
+    assert_eq!(server.login_attempts(), 1);
+}
+
+#[test]
+fn concurrent_session_is_reported() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    server.set_already_logged_in(true);
+    let mut session = session(&server, PASSWORD);
+    let err = session.login().unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<LoginError>(),
+        Some(&LoginError::AlreadyLoggedIn)
+    );
+}
+
+#[test]
+fn maintenance_page_is_a_parse_error() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    session.login().unwrap();
+
+    server.set_maintenance(true);
+    let err = session.balance().unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ParseError>(),
+        Some(ParseError::MaintenancePage { .. })
+    ));
+    assert!(session.is_logged_in());
+}
+
+#[test]
+fn transfer_with_keybca_challenge() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+
+    let beneficiaries = session.beneficiaries().unwrap();
+    assert_eq!(beneficiaries.len(), 2);
+    assert_eq!(beneficiaries[0].account_number(), "1234567890");
+    assert_eq!(beneficiaries[0].name(), "ANDI WIJAYA");
+
+    let request = TransferRequest::new("1234567890", Amount::from_major(150_000), "arisan");
+    let challenge = session.prepare_transfer(&request).unwrap();
+    assert_eq!(challenge.beneficiary().name(), "ANDI WIJAYA");
+    assert_eq!(challenge.amount().amount(), Amount::from_major(150_000));
+    assert_eq!(challenge.keybca().appli1(), Some("34567890"));
+    assert_eq!(challenge.keybca().appli2(), Some("150000"));
+
+    let receipt = session.confirm_transfer(KEYBCA_RESPONSE).unwrap();
+    assert_eq!(receipt.reference(), "FT0000000001");
+    assert_eq!(receipt.note(), "arisan");
+    assert_eq!(receipt.receipt().amount().to_string(), "IDR 150,000.00");
+    assert_eq!(server.transfers().len(), 1);
+}
+
+#[test]
+fn refused_transfers_are_typed_errors() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+
+    let unknown = TransferRequest::new("5555555555", Amount::from_major(10_000), "");
+    let err = session.prepare_transfer(&unknown).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<TransactionError>(),
+        Some(&TransactionError::UnknownBeneficiary)
+    );
+
+    let request = TransferRequest::new("0987654321", Amount::from_major(10_000), "");
+    session.prepare_transfer(&request).unwrap();
+    let err = session.confirm_transfer("00000000").unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<TransactionError>(),
+        Some(&TransactionError::InvalidKeyBcaResponse)
+    );
+    assert!(server.transfers().is_empty());
+}
+
+#[test]
+fn interbank_transfer_with_fee() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+
+    let banks = session.banks().unwrap();
+    assert!(banks
+        .iter()
+        .any(|b| b.code() == "008" && b.name() == "BANK MANDIRI"));
+
+    let found = session.inquire_account("008", "1310012345678").unwrap();
+    assert_eq!(found.beneficiary().name(), "SITI RAHAYU");
+    let err = session.inquire_account("008", "999").unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<TransactionError>(),
+        Some(&TransactionError::AccountNotFound)
+    );
+
+    let request = InterbankTransferRequest {
+        bank_code: "008".to_string(),
+        to: "1310012345678".to_string(),
+        amount: Amount::from_major(1_000_000),
+        service: TransferService::Online,
+        note: "sewa".to_string(),
+    };
+    let challenge = session.prepare_interbank_transfer(&request).unwrap();
+    assert_eq!(challenge.fee().amount(), Amount::from_major(6_500));
+    assert_eq!(challenge.total().amount(), Amount::from_major(1_006_500));
+
+    let receipt = session.confirm_interbank_transfer(KEYBCA_RESPONSE).unwrap();
+    assert_eq!(receipt.service(), TransferService::Online);
+    assert_eq!(receipt.bank().code(), "008");
+    assert_eq!(server.transfers()[0].fee, Amount::from_major(6_500));
+
+    let over_limit = InterbankTransferRequest {
+        amount: Amount::from_major(60_000_000),
+        ..request
+    };
+    let err = session.prepare_interbank_transfer(&over_limit).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<TransactionError>(),
+        Some(&TransactionError::LimitExceeded)
+    );
+}
+
+#[test]
+fn virtual_account_payment_is_stored() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+
+    let bill = session.inquire_va("3901081234567890").unwrap();
+    assert_eq!(bill.company(), "TOKOPEDIA");
+    assert_eq!(bill.name(), "BUDI SANTOSO");
+    assert_eq!(
+        bill.amount().map(|m| m.amount()),
+        Some(Amount::from_major(275_000))
+    );
+    let open = session.inquire_va("1220012345678901").unwrap();
+    assert_eq!(open.amount(), None);
+
+    let request = PaymentRequest {
+        va_number: "3901081234567890".to_string(),
+        amount: None,
+    };
+    let challenge = session.prepare_payment(&request).unwrap();
+    assert_eq!(challenge.amount().amount(), Amount::from_major(275_000));
+    let receipt = session.confirm_payment(KEYBCA_RESPONSE).unwrap();
+    assert_eq!(receipt.reference(), "PY0000000001");
+    assert_eq!(server.payments().len(), 1);
+
+    let dir = std::env::temp_dir().join(format!("bca-rust-receipts-{}", std::process::id()));
+    let store = ReceiptStore::new(&dir);
+    store.append(&StoredReceipt::from(&receipt)).unwrap();
+    let stored = store.load().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].kind, ReceiptKind::Payment);
+    assert_eq!(stored[0].reference, "PY0000000001");
+    assert_eq!(stored[0].amount.amount(), Amount::from_major(275_000));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let open_without_amount = PaymentRequest {
+        va_number: "1220012345678901".to_string(),
+        amount: None,
+    };
+    assert!(session.prepare_payment(&open_without_amount).is_err());
+}
+
+#[test]
+fn beneficiaries_are_added_with_keybca_and_cached() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+
+    let listed = session.registered_beneficiaries().unwrap();
+    assert_eq!(listed.len(), 2);
+    assert!(listed.iter().all(|b| b.is_bca()));
+
+    let challenge = session
+        .prepare_add_beneficiary("008", "1310012345678")
+        .unwrap();
+    assert_eq!(challenge.entry().beneficiary().name(), "SITI RAHAYU");
+    assert_eq!(challenge.entry().bank().code(), "008");
+    let wrong = session.confirm_add_beneficiary("00000000").unwrap_err();
+    assert_eq!(
+        wrong.downcast_ref::<TransactionError>(),
+        Some(&TransactionError::InvalidKeyBcaResponse)
+    );
+    assert_eq!(server.beneficiaries().len(), 2);
+
+    session
+        .prepare_add_beneficiary("008", "1310012345678")
+        .unwrap();
+    let added = session.confirm_add_beneficiary(KEYBCA_RESPONSE).unwrap();
+    assert!(!added.is_bca());
+    assert_eq!(session.registered_beneficiaries().unwrap().len(), 3);
+
+    let remaining = session.remove_beneficiary("014", "0987654321").unwrap();
+    assert_eq!(remaining.len(), 2);
+    assert!(remaining
+        .iter()
+        .all(|b| b.beneficiary().account_number() != "0987654321"));
+
+    let dir = std::env::temp_dir().join(format!("bca-rust-beneficiaries-{}", std::process::id()));
+    let cache = BeneficiaryCache::new(&dir);
+    assert_eq!(cache.load().unwrap(), None);
+    cache.save(&remaining).unwrap();
+    assert_eq!(cache.load().unwrap().unwrap().entries, remaining);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn transfer_batch_resumes_without_paying_twice() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let dir = std::env::temp_dir().join(format!("bca-rust-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let store = ReceiptStore::new(&dir);
+    let file = dir.join("payroll.csv");
+    std::fs::write(
+        &file,
+        "beneficiary,amount,note\n\
+         1234567890,\"150,000\",gaji juni\n\
+         0987654321,200000,gaji juni\n\
+         1234567890,200000,bonus\n",
+    )
+    .unwrap();
+
+    let batch = TransferBatch::read(&file).unwrap();
+    assert_eq!(batch.total(), Amount::from_major(550_000));
+    let beneficiaries = session.beneficiaries().unwrap();
+    assert!(batch.validate(&beneficiaries).is_empty());
+
+    let report_path = BatchReport::default_path(&file);
+    assert_eq!(report_path, dir.join("payroll.report.csv"));
+    let mut report = BatchReport::open(&report_path, &batch).unwrap();
+    assert_eq!(report.to_run(), vec![0, 1, 2]);
+    let keybca = |_: &_| Ok(KEYBCA_RESPONSE.to_string());
+    let done = report.execute(0, &mut session, &store, keybca).unwrap();
+    assert_eq!(done.status, EntryStatus::Done);
+    assert_eq!(done.name, "ANDI WIJAYA");
+    let refused = report
+        .execute(1, &mut session, &store, |_| Ok("00000000".to_string()))
+        .unwrap();
+    assert_eq!(refused.status, EntryStatus::Failed);
+    // the receipt page never arrives, the transfer may or may not have been made
+    let unknown = report
+        .execute(2, &mut session, &store, |_| {
+            server.set_maintenance(true);
+            Ok(KEYBCA_RESPONSE.to_string())
+        })
+        .unwrap();
+    assert_eq!(unknown.status, EntryStatus::Submitted);
+    server.set_maintenance(false);
+    assert_eq!(server.transfers().len(), 1);
+
+    // a second run only retries the refused row
+    let mut report = BatchReport::open(&report_path, &batch).unwrap();
+    assert_eq!(report.to_run(), vec![1]);
+    report.execute(1, &mut session, &store, keybca).unwrap();
+    assert_eq!(report.count(EntryStatus::Done), 2);
+    assert_eq!(report.count(EntryStatus::Submitted), 1);
+    assert_eq!(server.transfers().len(), 2);
+    assert_eq!(store.load().unwrap().len(), 2);
+
+    std::fs::write(
+        &file,
+        "beneficiary,amount,note\n5550001111,abc,\n0987654321,0,\n",
+    )
+    .unwrap();
+    let changed = TransferBatch::read(&file).unwrap();
+    assert_eq!(changed.validate(&beneficiaries).len(), 2);
+    assert!(BatchReport::open(&report_path, &changed).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn policy_stops_transactions_before_klikbca() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let policy_error = |e: anyhow::Error| e.downcast_ref::<PolicyError>().cloned();
+    let request = TransferRequest::new("1234567890", Amount::from_major(200_000), "sewa");
+
+    session.set_policy(TransferPolicy {
+        read_only: true,
+        ..TransferPolicy::default()
+    });
+    let refused = session.prepare_transfer(&request).unwrap_err();
+    assert_eq!(policy_error(refused), Some(PolicyError::ReadOnly));
+    assert!(session.remove_beneficiary("014", "1234567890").is_err());
+    assert!(server
+        .requests()
+        .iter()
+        .all(|r| r.path != "/fundtransfer.do" && r.path != "/beneficiary.do"));
+
+    session.set_policy(TransferPolicy {
+        dry_run: true,
+        ..TransferPolicy::default()
+    });
+    session.prepare_transfer(&request).unwrap();
+    let dry = session.confirm_transfer(KEYBCA_RESPONSE).unwrap_err();
+    assert_eq!(policy_error(dry), Some(PolicyError::DryRun));
+    assert!(server.transfers().is_empty());
+
+    let dir = std::env::temp_dir().join(format!("bca-rust-policy-{}", std::process::id()));
+    let store = ReceiptStore::new(&dir);
+    session.set_policy(TransferPolicy {
+        max_per_transfer: Some(Amount::from_major(250_000)),
+        max_per_day: Some(Amount::from_major(300_000)),
+        receipts: Some(store.clone()),
+        ..TransferPolicy::default()
+    });
+    // transactions klikbca was asked to prepare
+    let validations = || {
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.method == "POST" && r.param("value(actions)").ends_with("_validate"))
+            .count()
+    };
+    let before = validations();
+    let large = TransferRequest::new("1234567890", Amount::from_major(260_000), "sewa");
+    assert!(matches!(
+        session.prepare_transfer(&large).map_err(policy_error),
+        Err(Some(PolicyError::OverTransferLimit { .. }))
+    ));
+    let interbank = InterbankTransferRequest {
+        bank_code: "002".to_string(),
+        to: "1234567890".to_string(),
+        amount: Amount::from_major(260_000),
+        service: TransferService::BiFast,
+        note: String::new(),
+    };
+    assert!(matches!(
+        session
+            .prepare_interbank_transfer(&interbank)
+            .map_err(policy_error),
+        Err(Some(PolicyError::OverTransferLimit { .. }))
+    ));
+    // the fixed amount VA bills 275,000
+    let fixed = PaymentRequest {
+        va_number: "3901081234567890".to_string(),
+        amount: None,
+    };
+    assert!(matches!(
+        session.prepare_payment(&fixed).map_err(policy_error),
+        Err(Some(PolicyError::OverTransferLimit { .. }))
+    ));
+    let open = PaymentRequest {
+        va_number: "1220012345678901".to_string(),
+        amount: None,
+    };
+    assert_eq!(
+        session.prepare_payment(&open).map_err(policy_error).err(),
+        Some(Some(PolicyError::UnknownAmount))
+    );
+    assert_eq!(validations(), before);
+    // 97,500 and the 2,500 BI-FAST fee count against the day
+    let interbank = InterbankTransferRequest {
+        bank_code: "008".to_string(),
+        to: "1310012345678".to_string(),
+        amount: Amount::from_major(97_500),
+        ..interbank
+    };
+    session.prepare_interbank_transfer(&interbank).unwrap();
+    let receipt = session.confirm_interbank_transfer(KEYBCA_RESPONSE).unwrap();
+    store.append(&StoredReceipt::from(&receipt)).unwrap();
+    let over_with_fee = TransferRequest::new("1234567890", Amount::from_major(201_000), "sewa");
+    assert!(matches!(
+        session
+            .prepare_transfer(&over_with_fee)
+            .map_err(policy_error),
+        Err(Some(PolicyError::OverDailyLimit { .. }))
+    ));
+    session.prepare_transfer(&request).unwrap();
+    let receipt = session.confirm_transfer(KEYBCA_RESPONSE).unwrap();
+    store.append(&StoredReceipt::from(&receipt)).unwrap();
+    assert!(matches!(
+        session.prepare_transfer(&request).map_err(policy_error),
+        Err(Some(PolicyError::OverDailyLimit { .. }))
+    ));
+    assert_eq!(validations(), before + 2);
+    assert_eq!(server.transfers().len(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(bca_rust::check_typed_name("ANDI WIJAYA", " andi  wijaya ").is_ok());
+    assert!(bca_rust::check_typed_name("ANDI WIJAYA", "ANDI").is_err());
+}
+
+#[test]
+fn long_ranges_are_fetched_in_windows() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let day = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+    let tx = |date, note: &str, major, direction| {
+        AccountTx::new(
+            date,
+            false,
+            note.to_string(),
+            Amount::from_major(major),
+            direction,
+        )
+    };
+    server.set_statement_history(
+        day(6, 30),
+        Amount::from_major(1_000_000),
+        &[
+            tx(day(5, 2), "GAJI", 5_000_000, Direction::Credit),
+            tx(day(5, 31), "TARIKAN ATM", 500_000, Direction::Debit),
+            tx(day(6, 1), "TARIKAN ATM", 500_000, Direction::Debit),
+            tx(day(6, 29), "BIAYA ADM", 10_000, Direction::Debit),
+        ],
+    );
+    let limits = StatementLimits::klikbca(day(6, 30));
+    let statement_requests = || {
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.param("value(actions)") == "acctstmtview")
+            .count()
+    };
+
+    let mut progress = vec![];
+    let range = DateRange::new(day(5, 1), day(6, 30));
+    let merged = session
+        .statement_range(range, &limits, |i, n, window| {
+            progress.push((i, n, *window))
+        })
+        .unwrap();
+    assert_eq!(
+        progress,
+        vec![
+            (1, 2, DateRange::new(day(5, 1), day(5, 31))),
+            (2, 2, DateRange::new(day(6, 1), day(6, 30))),
+        ]
+    );
+    assert_eq!(statement_requests(), 2);
+    assert_eq!(merged.info().period(), "01/05/2020 - 30/06/2020");
+    assert_eq!(merged.transactions().len(), 4);
+    let summary = merged.summary();
+    assert_eq!(
+        summary.balance_begin().amount(),
+        Amount::from_major(1_000_000)
+    );
+    assert_eq!(
+        summary.balance_end().amount(),
+        Amount::from_major(4_990_000)
+    );
+    assert!(summary.is_consistent());
+
+    // windows overlapping on 31/05 don't count its withdrawal twice
+    let may = session
+        .statement(DateRange::new(day(5, 1), day(5, 31)))
+        .unwrap();
+    let june = session
+        .statement(DateRange::new(day(5, 31), day(6, 30)))
+        .unwrap();
+    let overlapped = AccountMutasi::merge(range, vec![may, june]).unwrap();
+    assert_eq!(overlapped.transactions(), merged.transactions());
+    assert_eq!(
+        overlapped.summary().total_debits(),
+        merged.summary().total_debits()
+    );
+
+    // a window klikbca has nothing for doesn't shorten the period
+    server.set_empty_notice(true);
+    server.set_statement_history(
+        day(6, 30),
+        Amount::from_major(1_000_000),
+        &[tx(day(6, 10), "BIAYA ADM", 10_000, Direction::Debit)],
+    );
+    let merged = session
+        .statement_range(range, &limits, |_, _, _| {})
+        .unwrap();
+    assert_eq!(merged.info().period(), "01/05/2020 - 30/06/2020");
+    assert_eq!(merged.transactions().len(), 1);
+    assert_eq!(
+        merged.summary().balance_begin().amount(),
+        Amount::from_major(1_000_000)
+    );
+    assert!(merged.summary().is_consistent());
+    // a quiet period is an empty statement ending at the balance the days after it start from,
+    // or at the balance page's without any
+    let quiet = session
+        .statement_range_or_empty(DateRange::new(day(5, 1), day(5, 31)), &limits, |_, _, _| {})
+        .unwrap();
+    assert_eq!(quiet.info().period(), "01/05/2020 - 31/05/2020");
+    assert!(quiet.transactions().is_empty());
+    assert_eq!(
+        quiet.summary().balance_end().amount(),
+        Amount::from_major(1_000_000)
+    );
+    let quiet = session
+        .statement_range_or_empty(
+            DateRange::new(day(6, 11), day(6, 30)),
+            &limits,
+            |_, _, _| {},
+        )
+        .unwrap();
+    assert_eq!(
+        quiet.summary().balance_end(),
+        session.balance().unwrap().balance()
+    );
+    assert!(quiet.summary().is_consistent());
+
+    let too_old = session
+        .statement_range(DateRange::new(day(4, 1), day(6, 30)), &limits, |_, _, _| {})
+        .unwrap_err();
+    assert_eq!(
+        too_old.downcast_ref::<RangeError>(),
+        Some(&RangeError::TooOld {
+            start: day(4, 1),
+            earliest: day(5, 1)
+        })
+    );
+    assert_eq!(statement_requests(), 9);
+    // klikbca itself refuses periods over 31 days
+    assert!(session.statement(range).is_err());
+}
+
+#[test]
+fn ledger_keeps_transactions_across_overlapping_fetches() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let day = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+    let atm = |date| {
+        AccountTx::new(
+            date,
+            false,
+            "TARIKAN ATM".to_string(),
+            Amount::from_major(100_000),
+            Direction::Debit,
+        )
+    };
+    // two identical withdrawals on the same day are two transactions
+    server.set_statement_history(
+        day(6, 30),
+        Amount::from_major(1_000_000),
+        &[
+            atm(day(5, 10)),
+            atm(day(5, 25)),
+            atm(day(5, 25)),
+            atm(day(6, 5)),
+        ],
+    );
+    let dir = std::env::temp_dir().join(format!("bca-rust-ledger-{}", std::process::id()));
+    let mut ledger = Ledger::open(&dir).unwrap();
+
+    let may = session
+        .statement(DateRange::new(day(5, 1), day(5, 31)))
+        .unwrap();
+    let added = ledger.insert_statement(&may).unwrap();
+    assert_eq!((added.inserted, added.duplicates), (3, 0));
+    let overlapping = session
+        .statement(DateRange::new(day(5, 20), day(6, 19)))
+        .unwrap();
+    let added = ledger.insert_statement(&overlapping).unwrap();
+    assert_eq!((added.inserted, added.duplicates), (1, 2));
+    let balance = session.balance().unwrap();
+    assert!(ledger.insert_balance(&balance).unwrap());
+    // the dashboard's refreshes don't repeat an unchanged balance
+    assert!(!ledger.insert_balance(&balance).unwrap());
+    drop(ledger);
+
+    let ledger = Ledger::open(&dir).unwrap();
+    let history = ledger.transactions("0123456789", None).unwrap();
+    assert_eq!(history.len(), 4);
+    assert_ne!(history[1].id, history[2].id);
+    assert_eq!((history[1].position, history[2].position), (0, 1));
+    let again = ledger
+        .transactions("0123456789", Some(DateRange::new(day(5, 25), day(5, 25))))
+        .unwrap();
+    assert_eq!(again, history[1..3].to_vec());
+    assert_eq!(
+        ledger.last_transaction_date("0123456789").unwrap(),
+        Some(day(6, 5))
+    );
+    let balance = ledger
+        .balance_at("0123456789", day(6, 19))
+        .unwrap()
+        .unwrap();
+    assert_eq!(balance.as_of, day(6, 19));
+    assert_eq!(balance.balance.amount(), Amount::from_major(600_000));
+    assert_eq!(ledger.accounts().unwrap(), vec!["0123456789".to_string()]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sync_fetches_only_what_is_new() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut locked = session(&server, "000000");
+    let mut session = session(&server, PASSWORD);
+    let day = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+    let tx = |date, note: &str| {
+        AccountTx::new(
+            date,
+            false,
+            note.to_string(),
+            Amount::from_major(50_000),
+            Direction::Debit,
+        )
+    };
+    let history = vec![tx(day(5, 2), "PULSA"), tx(day(6, 10), "BIAYA ADM")];
+    server.set_statement_history(day(6, 30), Amount::from_major(1_000_000), &history);
+    let dir = std::env::temp_dir().join(format!("bca-rust-sync-{}", std::process::id()));
+    let mut ledger = Ledger::open(&dir).unwrap();
+    let mut windows = vec![];
+
+    // first run: everything klikbca still keeps
+    let first = sync(
+        &mut session,
+        &mut ledger,
+        &StatementLimits::klikbca(day(6, 30)),
+        |_, _, window| windows.push(*window),
+    )
+    .unwrap();
+    assert_eq!(first.range, DateRange::new(day(5, 1), day(6, 30)));
+    assert_eq!(first.added.inserted, 2);
+    assert_eq!(first.gap, None);
+    assert_eq!(windows.len(), 2);
+
+    // a week later: from the last synced day on, in one window
+    let mut later = history.clone();
+    later.push(tx(day(7, 1), "TARIKAN ATM"));
+    server.set_statement_history(day(7, 7), Amount::from_major(1_000_000), &later);
+    windows.clear();
+    let second: SyncReport = sync(
+        &mut session,
+        &mut ledger,
+        &StatementLimits::klikbca(day(7, 7)),
+        |_, _, window| windows.push(*window),
+    )
+    .unwrap();
+    assert_eq!(windows, vec![DateRange::new(day(6, 30), day(7, 7))]);
+    assert_eq!((second.added.inserted, second.added.duplicates), (1, 0));
+    assert_eq!(
+        ledger.synced_through("0123456789").unwrap(),
+        Some(day(7, 7))
+    );
+
+    // months later: the days klikbca dropped are reported
+    server.set_statement_history(day(10, 1), Amount::from_major(1_000_000), &later);
+    let third = sync(
+        &mut session,
+        &mut ledger,
+        &StatementLimits::klikbca(day(10, 1)),
+        |_, _, _| {},
+    )
+    .unwrap();
+    assert_eq!(third.gap, Some(DateRange::new(day(7, 8), day(8, 1))));
+    assert_eq!(third.added, Default::default());
+    assert_eq!(ledger.transactions("0123456789", None).unwrap().len(), 3);
+
+    // a failed login fails the sync
+    assert!(sync(
+        &mut locked,
+        &mut ledger,
+        &StatementLimits::klikbca(day(10, 1)),
+        |_, _, _| {}
+    )
+    .is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn statements_export_to_csv() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let day = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+    server.set_statement_history(
+        day(6, 30),
+        Amount::from_major(1_000_000),
+        &[
+            AccountTx::new(
+                day(6, 2),
+                false,
+                "GAJI; JUNI".to_string(),
+                Amount::from_minor(250_000_050),
+                Direction::Credit,
+            ),
+            AccountTx::new(
+                day(6, 3),
+                false,
+                "TARIKAN ATM".to_string(),
+                Amount::from_major(100_000),
+                Direction::Debit,
+            ),
+        ],
+    );
+    let statement = session
+        .statement(DateRange::new(day(6, 1), day(6, 7)))
+        .unwrap();
+    let options = CsvOptions {
+        delimiter: b';',
+        decimal: DecimalStyle::Comma,
+    };
+    let mut out = vec![];
+    export::csv::write_statement(&statement, &options, &mut out).unwrap();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(out.as_slice());
+    let rows = reader
+        .records()
+        .map(|r| r.unwrap().iter().map(String::from).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(rows[0], vec!["account", "0123456789"]);
+    assert_eq!(rows[2], vec!["period", "2020-06-01", "2020-06-07"]);
+    assert_eq!(rows[4], vec!["starting_balance", "1000000,00"]);
+    assert_eq!(rows[5], vec!["ending_balance", "3400000,50"]);
+    assert_eq!(
+        rows[6],
+        vec![
+            "date",
+            "description",
+            "amount",
+            "direction",
+            "balance",
+            "pending"
+        ]
+    );
+    assert_eq!(
+        rows[7],
+        vec![
+            "2020-06-02",
+            "GAJI; JUNI",
+            "2500000,50",
+            "CR",
+            "3500000,50",
+            "false"
+        ]
+    );
+    assert_eq!(
+        rows[8],
+        vec![
+            "2020-06-03",
+            "TARIKAN ATM",
+            "100000,00",
+            "DB",
+            "3400000,50",
+            "false"
+        ]
+    );
+    assert_eq!(rows.len(), 9);
+}
+
+#[test]
+fn ofx_uses_ledger_ids_and_qif_lists_booked_transactions() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let day = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+    let atm = |date| {
+        AccountTx::new(
+            date,
+            false,
+            "TARIKAN ATM".to_string(),
+            Amount::from_major(100_000),
+            Direction::Debit,
+        )
+    };
+    server.set_statement_history(
+        day(6, 30),
+        Amount::from_major(1_000_000),
+        &[atm(day(6, 3)), atm(day(6, 3)), atm(day(6, 9))],
+    );
+    let export = |session: &mut BcaSession, range| {
+        let statement = session.statement(range).unwrap();
+        let mut ofx = vec![];
+        export::ofx::write_statement(&statement, &mut ofx).unwrap();
+        let mut qif = vec![];
+        export::qif::write_statement(&statement, &mut qif).unwrap();
+        (
+            String::from_utf8(ofx).unwrap(),
+            String::from_utf8(qif).unwrap(),
+        )
+    };
+    let fitids = |ofx: &str| {
+        ofx.lines()
+            .filter_map(|l| l.strip_prefix("<FITID>"))
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+
+    let (ofx, qif) = export(&mut session, DateRange::new(day(6, 1), day(6, 7)));
+    assert!(ofx.starts_with("OFXHEADER:100\n"));
+    assert!(ofx.contains("<ACCTID>0123456789<ACCTTYPE>CHECKING"));
+    assert!(ofx.contains("<CURDEF>IDR"));
+    assert!(ofx.contains("<TRNAMT>-100000.00"));
+    assert!(ofx.contains("<LEDGERBAL>\n<BALAMT>800000.00\n<DTASOF>20200607"));
+    let first = fitids(&ofx);
+    assert_eq!(first.len(), 2);
+    assert_ne!(first[0], first[1]);
+    assert_eq!(qif.matches("\n^\n").count(), 2);
+    assert!(qif.starts_with("!Type:Bank\nD06/03/2020\nT-100000.00\nPTARIKAN ATM\n^\n"));
+
+    // an overlapping export names the same transactions the same way, as the ledger does
+    let (ofx, _) = export(&mut session, DateRange::new(day(6, 3), day(6, 10)));
+    let second = fitids(&ofx);
+    assert_eq!(second.len(), 3);
+    assert_eq!(second[..2], first[..]);
+    let dir = std::env::temp_dir().join(format!("bca-rust-ofx-{}", std::process::id()));
+    let mut ledger = Ledger::open(&dir).unwrap();
+    let statement = session
+        .statement(DateRange::new(day(6, 3), day(6, 10)))
+        .unwrap();
+    ledger.insert_statement(&statement).unwrap();
+    let ids = ledger
+        .transactions("0123456789", None)
+        .unwrap()
+        .into_iter()
+        .map(|tx| tx.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, second);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// replays the balance assertions of an exported journal the way the tools do: ledger in file
+// order, hledger and beancount in date order, beancount checking at the start of the day and
+// filling pads. Only the bank account is followed.
+fn check_assertions(journal: &str, flavor: JournalFlavor) -> Result<(), String> {
+    const BANK: &str = "Assets:Bank:BCA";
+    let amount = |s: &str| -> Amount { s.split_whitespace().next().unwrap().parse().unwrap() };
+    let mut entries: Vec<(&str, u8, &str)> = journal
+        .split("\n\n")
+        .filter(|e| !e.trim().is_empty())
+        .flat_map(|e| {
+            // beancount writes a pad and its balance as one block
+            let mut directives = vec![];
+            let mut start = 0;
+            for (i, _) in e.match_indices('\n') {
+                if e[i + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+                    directives.push(&e[start..i]);
+                    start = i + 1;
+                }
+            }
+            directives.push(&e[start..]);
+            directives
+        })
+        .map(|e| {
+            // beancount checks a balance before the transactions of its day
+            let order = if e.split_whitespace().nth(1) == Some("balance") {
+                0
+            } else {
+                1
+            };
+            (&e[..10], order, e)
+        })
+        .collect();
+    if flavor != JournalFlavor::Ledger {
+        entries.sort_by_key(|&(date, order, _)| (date, order));
+    }
+    let mut balance: Option<Amount> = None;
+    let mut padding = false;
+    for (date, _, entry) in entries {
+        let mut words = entry.lines().next().unwrap().split_whitespace().skip(1);
+        match (flavor, words.next()) {
+            (JournalFlavor::Beancount, Some("pad")) => padding = true,
+            (JournalFlavor::Beancount, Some("balance")) => {
+                let expected = amount(entry.split_once(BANK).unwrap().1);
+                if padding {
+                    balance = Some(expected);
+                    padding = false;
+                } else if balance != Some(expected) {
+                    return Err(format!("{}: {:?} isn't {}", date, balance, expected));
+                }
+            }
+            _ => {
+                for posting in entry.lines().skip(1) {
+                    let rest = match posting.trim().strip_prefix(BANK) {
+                        Some(rest) => rest.trim(),
+                        None => continue,
+                    };
+                    let (change, asserted) = match rest.split_once('=') {
+                        Some((change, asserted)) => (change.trim(), Some(amount(asserted))),
+                        None => (rest, None),
+                    };
+                    if change.is_empty() {
+                        // a balance assignment
+                        balance = asserted;
+                        continue;
+                    }
+                    let current = balance.ok_or(format!("{}: posting before opening", date))?;
+                    balance = Some(current + amount(change));
+                    if asserted.is_some() && balance != asserted {
+                        return Err(format!("{}: {:?} isn't {:?}", date, balance, asserted));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn journal_export_is_idempotent_over_overlapping_periods() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let day = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+    let tx = |date, note: &str, major, direction| {
+        AccountTx::new(
+            date,
+            false,
+            note.to_string(),
+            Amount::from_major(major),
+            direction,
+        )
+    };
+    server.set_statement_history(
+        day(6, 30),
+        Amount::from_major(1_000_000),
+        &[
+            tx(day(6, 2), "GAJI PT MAJU", 5_000_000, Direction::Credit),
+            tx(day(6, 5), "TARIKAN ATM", 100_000, Direction::Debit),
+            tx(day(6, 12), "BIAYA ADM", 10_000, Direction::Debit),
+        ],
+    );
+    for flavor in [
+        JournalFlavor::Ledger,
+        JournalFlavor::Hledger,
+        JournalFlavor::Beancount,
+    ] {
+        let mut options = JournalOptions::new(flavor);
+        options.rules = vec![
+            MappingRule::new("^gaji", "Income:Salary").unwrap(),
+            MappingRule::new("tarikan atm", "Assets:Cash").unwrap(),
+        ];
+        let mut journal = vec![];
+        let mut export_into = |journal: &mut Vec<u8>, range| {
+            let statement = session.statement(range).unwrap();
+            let state = JournalState::read(std::str::from_utf8(journal).unwrap());
+            export::journal::write_statement(&statement, &options, &state, journal).unwrap()
+        };
+
+        // beancount opens the 6 accounts, then opening balance, 2 transactions, closing assertion
+        let opens = if flavor == JournalFlavor::Beancount {
+            6
+        } else {
+            0
+        };
+        let written = export_into(&mut journal, DateRange::new(day(6, 1), day(6, 7)));
+        assert_eq!(written, opens + 4, "{:?}", flavor);
+        // the overlap is skipped: 1 new transaction and the new closing assertion
+        let written = export_into(&mut journal, DateRange::new(day(6, 3), day(6, 14)));
+        assert_eq!(written, 2, "{:?}", flavor);
+        // an earlier period adds only an assertion, and none for ledger which would check it
+        // after the later entries
+        let written = export_into(&mut journal, DateRange::new(day(6, 1), day(6, 3)));
+        let expected = if flavor == JournalFlavor::Ledger {
+            0
+        } else {
+            1
+        };
+        assert_eq!(written, expected, "{:?}", flavor);
+        let written = export_into(&mut journal, DateRange::new(day(6, 1), day(6, 14)));
+        assert_eq!(written, 0, "{:?}", flavor);
+
+        let journal = String::from_utf8(journal).unwrap();
+        assert_eq!(check_assertions(&journal, flavor), Ok(()), "{}", journal);
+        let wrong = journal.replace("5890000.00", "5889999.00");
+        assert!(check_assertions(&wrong, flavor).is_err(), "{:?}", flavor);
+        assert_eq!(journal.matches("GAJI PT MAJU").count(), 1);
+        assert_eq!(journal.matches("TARIKAN ATM").count(), 1);
+    }
+
+    let mut journal = vec![];
+    let statement = session
+        .statement(DateRange::new(day(6, 1), day(6, 7)))
+        .unwrap();
+    let options = JournalOptions::new(JournalFlavor::Hledger);
+    export::journal::write_statement(&statement, &options, &JournalState::default(), &mut journal)
+        .unwrap();
+    let journal = String::from_utf8(journal).unwrap();
+    assert!(journal.starts_with(
+        "2020-05-31 * Opening balance per klikbca\n    ; id: opening-0123456789-20200531\n    \
+         Assets:Bank:BCA  = 1000000.00 IDR\n    Equity:Opening Balances\n\n"
+    ));
+    assert!(journal.contains("2020-06-02 * GAJI PT MAJU\n    ; id: "));
+    assert!(journal.contains("    Income:Uncategorized  -5000000.00 IDR\n"));
+    assert!(journal.contains("    Expenses:Uncategorized  100000.00 IDR\n"));
+    assert!(journal.ends_with(
+        "2020-06-07 * Balance per klikbca\n    ; id: balance-0123456789-20200607\n    \
+         Assets:Bank:BCA  0 IDR = 5900000.00 IDR\n\n"
+    ));
+
+    // bean-check takes a fresh beancount journal as it is: every account is opened first
+    let mut journal = vec![];
+    let options = JournalOptions::new(JournalFlavor::Beancount);
+    export::journal::write_statement(&statement, &options, &JournalState::default(), &mut journal)
+        .unwrap();
+    let ids = bca_rust::booked_transactions(&statement)
+        .into_iter()
+        .map(|(tx, position)| bca_rust::transaction_id("0123456789", tx, position))
+        .collect::<Vec<String>>();
+    assert_eq!(
+        String::from_utf8(journal).unwrap(),
+        format!(
+            r#"2020-05-31 open Assets:Bank:BCA
+  id: "open-assets-bank-bca"
+
+2020-05-31 open Equity:Opening-Balances
+  id: "open-equity-opening-balances"
+
+2020-05-31 open Income:Uncategorized
+  id: "open-income-uncategorized"
+
+2020-05-31 open Expenses:Uncategorized
+  id: "open-expenses-uncategorized"
+
+2020-05-31 pad Assets:Bank:BCA Equity:Opening-Balances
+  id: "opening-0123456789-20200531"
+2020-06-01 balance Assets:Bank:BCA  1000000.00 IDR
+
+2020-06-02 * "GAJI PT MAJU"
+  id: "{}"
+  Assets:Bank:BCA  5000000.00 IDR
+  Income:Uncategorized  -5000000.00 IDR
+
+2020-06-05 * "TARIKAN ATM"
+  id: "{}"
+  Assets:Bank:BCA  -100000.00 IDR
+  Expenses:Uncategorized  100000.00 IDR
+
+2020-06-08 balance Assets:Bank:BCA  5900000.00 IDR
+  id: "balance-0123456789-20200607"
+
+"#,
+            ids[0], ids[1]
+        )
+    );
+    // accounts opened by hand aren't opened again
+    let state = JournalState::read("2020-01-01 open Assets:Bank:BCA IDR\n");
+    let mut journal = vec![];
+    export::journal::write_statement(&statement, &options, &state, &mut journal).unwrap();
+    assert!(!String::from_utf8(journal)
+        .unwrap()
+        .contains("open Assets:Bank:BCA"));
+}
+
+#[test]
+fn json_documents_are_versioned() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let day = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+    server.set_statement_history(
+        day(6, 30),
+        Amount::from_major(1_000_000),
+        &[
+            AccountTx::new(
+                day(6, 2),
+                false,
+                "GAJI".to_string(),
+                Amount::from_major(500_000),
+                Direction::Credit,
+            ),
+            AccountTx::new(
+                day(6, 3),
+                false,
+                "TARIKAN ATM".to_string(),
+                Amount::from_minor(10_050),
+                Direction::Debit,
+            ),
+        ],
+    );
+    let document = |write: &dyn Fn(&mut Vec<u8>)| {
+        let mut out = vec![];
+        write(&mut out);
+        serde_json::from_slice::<serde_json::Value>(&out).unwrap()
+    };
+
+    let balance = session.balance().unwrap();
+    let json = document(&|out| export::json::write_balance(&balance, out).unwrap());
+    assert_eq!(json["version"], JSON_VERSION);
+    assert_eq!(json["kind"], "balance");
+    assert_eq!(json["account"], "0123456789");
+    assert_eq!(json["balance"]["currency"], "IDR");
+
+    let statement = session
+        .statement(DateRange::new(day(6, 1), day(6, 7)))
+        .unwrap();
+    let json = document(&|out| export::json::write_statement(&statement, out).unwrap());
+    assert_eq!(json["version"], JSON_VERSION);
+    assert_eq!(json["kind"], "statement");
+    assert_eq!(json["period"]["start"], "2020-06-01");
+    assert_eq!(json["starting_balance"]["amount"], "1000000.00");
+    let txs = json["transactions"].as_array().unwrap();
+    assert_eq!(txs.len(), 2);
+    assert_eq!(txs[1]["date"], "2020-06-03");
+    assert_eq!(txs[1]["direction"], "DB");
+    assert_eq!(txs[1]["amount"]["amount"], "100.50");
+    assert_eq!(txs[1]["balance"]["amount"], "1499899.50");
+    assert_eq!(json["ending_balance"]["amount"], "1499899.50");
+    assert_eq!(
+        txs[0]["id"],
+        bca_rust::transaction_id("0123456789", &statement.transactions()[0], 0)
+    );
+}
+
+#[test]
+fn watch_reports_each_new_transaction_once_across_restarts() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let day = |m, d| NaiveDate::from_ymd_opt(2020, m, d).unwrap();
+    let transfer = |date, major| {
+        AccountTx::new(
+            date,
+            false,
+            "TRSF E-BANKING CR TOKO".to_string(),
+            Amount::from_major(major),
+            Direction::Credit,
+        )
+    };
+    let dir = std::env::temp_dir().join(format!("bca-rust-watch-{}", std::process::id()));
+    let mut history = vec![transfer(day(6, 1), 150_000)];
+    let mut poll = |history: &[AccountTx], watcher: &mut Watcher, today| {
+        server.set_statement_history(today, Amount::from_major(1_000_000), history);
+        let statement = session.statement(DateRange::new(day(6, 1), today)).unwrap();
+        watcher.observe(&statement).unwrap()
+    };
+
+    // the first poll is history
+    let mut watcher = Watcher::open(&dir).unwrap();
+    assert!(poll(&history, &mut watcher, day(6, 2)).is_empty());
+    history.push(transfer(day(6, 2), 150_000));
+    let new = poll(&history, &mut watcher, day(6, 2));
+    assert_eq!(new.len(), 1);
+    assert_eq!(new[0].date, day(6, 2));
+    assert_eq!(new[0].amount, Amount::from_major(150_000));
+    assert_eq!(new[0].direction, Direction::Credit);
+    assert!(poll(&history, &mut watcher, day(6, 2)).is_empty());
+    watcher.save().unwrap();
+
+    // a restarted watch only reports what came in since, even an identical second payment
+    let mut watcher = Watcher::open(&dir).unwrap();
+    assert!(poll(&history, &mut watcher, day(6, 2)).is_empty());
+    history.push(transfer(day(6, 2), 150_000));
+    let new = poll(&history, &mut watcher, day(6, 2));
+    assert_eq!(new.len(), 1);
+    assert_eq!(new[0].position, 1);
+
+    let mut line = vec![];
+    export::json::write_transaction(&new[0], &mut line).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&line).unwrap();
+    assert_eq!(json["kind"], "transaction");
+    assert_eq!(json["id"], new[0].id.as_str());
+    assert_eq!(json["amount"]["amount"], "150000.00");
+    assert!(line.ends_with(b"}\n"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn webhooks_are_signed_retried_and_survive_restarts() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let receiver = WebhookReceiver::start().unwrap();
+    let day = |d| NaiveDate::from_ymd_opt(2020, 6, d).unwrap();
+    let payment = AccountTx::new(
+        day(2),
+        false,
+        "TRSF E-BANKING CR INV-0042".to_string(),
+        Amount::from_major(150_123),
+        Direction::Credit,
+    );
+    let dir = std::env::temp_dir().join(format!("bca-rust-webhook-{}", std::process::id()));
+    let urls = vec![receiver.url("/paid")];
+
+    let mut watcher = Watcher::open_named(&dir, "daemon.json").unwrap();
+    server.set_statement_history(day(2), Amount::from_major(1_000_000), &[]);
+    let statement = session.statement(DateRange::new(day(1), day(2))).unwrap();
+    assert!(watcher.observe(&statement).unwrap().is_empty());
+    server.set_statement_history(day(2), Amount::from_major(1_000_000), &[payment]);
+    let statement = session.statement(DateRange::new(day(1), day(2))).unwrap();
+    let new = watcher.observe(&statement).unwrap();
+    assert_eq!(new.len(), 1);
+
+    let mut queue = DeliveryQueue::open(&dir).unwrap();
+    let now = Utc::now();
+    assert_eq!(queue.enqueue(&new[0], &urls, now).unwrap(), 1);
+    assert_eq!(queue.enqueue(&new[0], &urls, now).unwrap(), 0);
+
+    let backoff = Backoff {
+        base: std::time::Duration::from_secs(30),
+        max: std::time::Duration::from_secs(3600),
+        max_attempts: 5,
+    };
+    let sender = WebhookSender::new(b"s3cret", backoff).unwrap();
+    receiver.respond_with(&[500, 503]);
+    assert_eq!(sender.deliver_due(&mut queue, now).unwrap().retrying, 1);
+    // not due again before the backoff passed
+    assert_eq!(sender.deliver_due(&mut queue, now).unwrap().retrying, 0);
+    let later = now + chrono::Duration::seconds(30);
+    assert_eq!(sender.deliver_due(&mut queue, later).unwrap().retrying, 1);
+
+    // a restart keeps the pending delivery and doesn't queue it again
+    drop(queue);
+    let mut queue = DeliveryQueue::open(&dir).unwrap();
+    let pending = queue.pending().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].attempts, 2);
+    assert!(pending[0].last_error.as_deref().unwrap().contains("503"));
+    assert_eq!(queue.enqueue(&new[0], &urls, later).unwrap(), 0);
+    let later = later + chrono::Duration::seconds(60);
+    assert_eq!(sender.deliver_due(&mut queue, later).unwrap().delivered, 1);
+    assert!(queue.pending().unwrap().is_empty());
+    assert!(queue.given_up().unwrap().is_empty());
+
+    let received = receiver.received();
+    assert_eq!(
+        received.iter().map(|r| r.status).collect::<Vec<_>>(),
+        vec![500, 503, 200]
+    );
+    let last = &received[2];
+    assert_eq!(last.path, "/paid");
+    assert_eq!(last.header(webhook::EVENT_HEADER), new[0].id);
+    assert_eq!(last.header(webhook::ATTEMPT_HEADER), "3");
+    assert!(webhook::verify(
+        b"s3cret",
+        &last.body,
+        last.header(webhook::SIGNATURE_HEADER)
+    ));
+    assert!(!webhook::verify(
+        b"other",
+        &last.body,
+        last.header(webhook::SIGNATURE_HEADER)
+    ));
+    let json: serde_json::Value = serde_json::from_slice(&last.body).unwrap();
+    assert_eq!(json["kind"], "transaction");
+    assert_eq!(json["amount"]["amount"], "150123.00");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn invoices_are_matched_by_unique_amount_window_and_name() {
+    let server = MockServer::start(USER, PASSWORD).unwrap();
+    let mut session = session(&server, PASSWORD);
+    let day = |d| NaiveDate::from_ymd_opt(2020, 6, d).unwrap();
+    let dir = std::env::temp_dir().join(format!("bca-rust-invoices-{}", std::process::id()));
+    let mut book = InvoiceBook::open(&dir).unwrap();
+    let mut issue = |reference, major, name| {
+        book.issue(reference, Amount::from_major(major), name, day(1), day(8))
+            .unwrap()
+            .unique_amount
+    };
+    assert_eq!(issue("INV-1", 150_000, None), Amount::from_major(150_001));
+    assert_eq!(
+        issue("INV-2", 150_000, Some("Budi Santoso")),
+        Amount::from_major(150_002)
+    );
+    assert_eq!(issue("INV-3", 200_000, None), Amount::from_major(200_001));
+    assert_eq!(
+        issue("INV-4", 300_000, Some("Siti Aminah")),
+        Amount::from_major(300_001)
+    );
+    let err = book
+        .issue("INV-1", Amount::from_major(1), None, day(1), day(8))
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<InvoiceError>(),
+        Some(&InvoiceError::DuplicateReference("INV-1".to_string()))
+    );
+
+    let tx = |d, major, direction, note: &str| {
+        AccountTx::new(
+            day(d),
+            false,
+            note.to_string(),
+            Amount::from_major(major),
+            direction,
+        )
+    };
+    let mut history = vec![
+        tx(2, 150_001, Direction::Credit, "TRSF E-BANKING CR ANDI"),
+        // the amount of INV-2, without its payer
+        tx(2, 150_002, Direction::Credit, "TRSF E-BANKING CR JOKO"),
+        tx(
+            2,
+            100_000,
+            Direction::Credit,
+            "TRSF E-BANKING CR SITI AMINAH",
+        ),
+        tx(2, 200_001, Direction::Debit, "TRSF E-BANKING DB TOKO"),
+        tx(2, 999, Direction::Credit, "BUNGA"),
+    ];
+    server.set_statement_history(day(10), Amount::from_major(1_000_000), &history);
+    let statement = session.statement(DateRange::new(day(1), day(10))).unwrap();
+    let matches = book.match_statement(&statement).unwrap();
+    let outcomes: Vec<_> = matches.iter().map(|m| m.outcome.clone()).collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            MatchOutcome::Paid {
+                reference: "INV-1".to_string()
+            },
+            MatchOutcome::Ambiguous {
+                candidates: vec!["INV-2".to_string()]
+            },
+            MatchOutcome::Partial {
+                reference: "INV-4".to_string(),
+                outstanding: Amount::from_major(200_000)
+            },
+        ]
+    );
+    // matching an overlapping statement again takes nothing twice
+    assert!(book.match_statement(&statement).unwrap().is_empty());
+
+    // the rest of INV-4, and INV-3's amount after its window closed
+    history.push(tx(
+        3,
+        200_000,
+        Direction::Credit,
+        "TRSF E-BANKING CR SITI AMINAH",
+    ));
+    history.push(tx(9, 200_001, Direction::Credit, "TRSF E-BANKING CR LATE"));
+    server.set_statement_history(day(10), Amount::from_major(1_000_000), &history);
+    let statement = session.statement(DateRange::new(day(1), day(10))).unwrap();
+    let matches = book.match_statement(&statement).unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(
+        matches[0].outcome,
+        MatchOutcome::Paid {
+            reference: "INV-4".to_string()
+        }
+    );
+
+    // a restart keeps the flagged transfer, resolving it pays the invoice
+    drop(book);
+    let mut book = InvoiceBook::open(&dir).unwrap();
+    let ambiguous = book.ambiguous().unwrap();
+    assert_eq!(ambiguous.len(), 1);
+    assert_eq!(ambiguous[0].amount, Amount::from_major(150_002));
+    book.resolve(&ambiguous[0].tx_id, "INV-2").unwrap();
+    assert!(book.ambiguous().unwrap().is_empty());
+    let inv2 = book.get("INV-2").unwrap();
+    assert_eq!(inv2.status, InvoiceStatus::Paid);
+    assert_eq!(inv2.paid_on, Some(day(2)));
+    assert_eq!(
+        book.get("INV-4").unwrap().received,
+        Amount::from_major(300_000)
+    );
+    let open: Vec<_> = book
+        .invoices(false)
+        .unwrap()
+        .into_iter()
+        .map(|i| i.reference)
+        .collect();
+    assert_eq!(open, vec!["INV-3".to_string()]);
+
+    // paid invoices free their code, cancelled ones too
+    assert_eq!(
+        book.issue("INV-5", Amount::from_major(150_000), None, day(1), day(8))
+            .unwrap()
+            .unique_amount,
+        Amount::from_major(150_001)
+    );
+    book.cancel("INV-3").unwrap();
+    assert!(book.cancel("INV-3").is_err());
+    assert_eq!(
+        book.issue("INV-6", Amount::from_major(200_000), None, day(1), day(8))
+            .unwrap()
+            .unique_amount,
+        Amount::from_major(200_001)
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
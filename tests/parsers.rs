@@ -0,0 +1,293 @@
+use bca_rust::resp_parser::login_parser::LoginOutcome;
+use bca_rust::{AccountBalance, AccountMutasi, Amount, Direction, ParseError};
+use chrono::NaiveDate;
+
+// (date, pending, description, amount in minor units, direction)
+type GoldenTx = (&'static str, bool, &'static str, i64, Direction);
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn assert_transactions(mutasi: &AccountMutasi, golden: &[GoldenTx]) {
+    let parsed = mutasi
+        .transactions()
+        .iter()
+        .map(|tx| {
+            (
+                tx.date(),
+                tx.is_pending(),
+                tx.description().to_string(),
+                tx.amount(),
+                tx.direction(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let expected = golden
+        .iter()
+        .map(|(d, pending, desc, minor, dir)| {
+            (
+                date(d),
+                *pending,
+                desc.to_string(),
+                Amount::from_minor(*minor),
+                *dir,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(parsed, expected);
+}
+
+// (begin, credits, debits, end) in minor units
+fn assert_summary(mutasi: &AccountMutasi, golden: (i64, i64, i64, i64)) {
+    let summary = mutasi.summary();
+    assert_eq!(summary.currency(), "IDR");
+    assert_eq!(
+        (
+            summary.balance_begin().amount().minor(),
+            summary.total_credits().amount().minor(),
+            summary.total_debits().amount().minor(),
+            summary.balance_end().amount().minor(),
+        ),
+        golden
+    );
+    assert!(summary.is_consistent());
+}
+
+#[test]
+fn statement_account_info() {
+    let mutasi = AccountMutasi::new(include_str!("../fixtures/statement.html")).unwrap();
+    let info = mutasi.info();
+    assert_eq!(info.account_number(), "0123456789");
+    assert_eq!(info.owner_name(), "BUDI SANTOSO");
+    assert_eq!(info.period(), "01/06/2020 - 07/06/2020");
+    assert_eq!(info.currency(), "IDR");
+    let period = info.period_range().unwrap();
+    assert_eq!(period.start, date("2020-06-01"));
+    assert_eq!(period.end, date("2020-06-07"));
+}
+
+#[test]
+fn statement_empty_period() {
+    let mutasi = AccountMutasi::new(include_str!("../fixtures/statement_empty.html")).unwrap();
+    assert_transactions(&mutasi, &[]);
+    assert_summary(&mutasi, (250_000_000, 0, 0, 250_000_000));
+}
+
+#[test]
+fn statement_single_transaction() {
+    let mutasi = AccountMutasi::new(include_str!("../fixtures/statement_single.html")).unwrap();
+    assert_transactions(
+        &mutasi,
+        &[("2021-03-10", false, "BUNGA", 1_234_567, Direction::Credit)],
+    );
+    assert_summary(&mutasi, (100_000_000, 1_234_567, 0, 101_234_567));
+}
+
+#[test]
+fn statement_many_transactions_across_new_year() {
+    let mutasi = AccountMutasi::new(include_str!("../fixtures/statement_many.html")).unwrap();
+    assert_transactions(
+        &mutasi,
+        &[
+            (
+                "2020-12-28",
+                false,
+                "BIAYA ADM",
+                1_500_000,
+                Direction::Debit,
+            ),
+            (
+                "2020-12-29",
+                false,
+                "TRSF E-BANKING CR 2912/FTSCY/WS95051 ANDI WIJAYA",
+                15_012_300,
+                Direction::Credit,
+            ),
+            (
+                "2020-12-30",
+                false,
+                "KARTU DEBIT INDOMARET",
+                8_750_000,
+                Direction::Debit,
+            ),
+            ("2020-12-31", false, "BUNGA", 123_456, Direction::Credit),
+            ("2020-12-31", false, "PAJAK BUNGA", 24_691, Direction::Debit),
+            (
+                "2021-01-02",
+                false,
+                "SWITCHING CR TRF DARI BANK MANDIRI",
+                200_000_000,
+                Direction::Credit,
+            ),
+            (
+                "2021-01-04",
+                false,
+                "TARIKAN ATM 04/01",
+                50_000_000,
+                Direction::Debit,
+            ),
+            (
+                "2021-01-05",
+                false,
+                "TRSF E-BANKING DB 0501/FTSCY/WS95031 RINA",
+                7_500_000,
+                Direction::Debit,
+            ),
+        ],
+    );
+    assert_summary(&mutasi, (300_000_000, 215_135_756, 67_774_691, 447_361_065));
+
+    let credits = mutasi
+        .transactions()
+        .iter()
+        .filter(|tx| tx.direction() == Direction::Credit)
+        .map(|tx| tx.amount())
+        .sum::<Amount>();
+    assert_eq!(credits, mutasi.summary().total_credits().amount());
+}
+
+#[test]
+fn statement_pending_rows() {
+    let mutasi = AccountMutasi::new(include_str!("../fixtures/statement_pending.html")).unwrap();
+    assert_transactions(
+        &mutasi,
+        &[
+            (
+                "2021-07-01",
+                false,
+                "TRSF E-BANKING CR 0107/FTSCY/WS95051 DEWI LESTARI",
+                30_000_000,
+                Direction::Credit,
+            ),
+            // pending rows are dated at the end of the period
+            (
+                "2021-07-03",
+                true,
+                "KARTU DEBIT TOKOPEDIA",
+                12_500_000,
+                Direction::Debit,
+            ),
+        ],
+    );
+    assert_summary(&mutasi, (50_000_000, 30_000_000, 0, 80_000_000));
+}
+
+#[test]
+fn statement_multi_line_notes() {
+    let mutasi = AccountMutasi::new(include_str!("../fixtures/statement_multiline.html")).unwrap();
+    assert_transactions(
+        &mutasi,
+        &[
+            (
+                "2021-08-15",
+                false,
+                "TRSF E-BANKING DB 1508/FTSCY/WS95031 PEMBAYARAN INVOICE NO 123 CV SUMBER REJEKI",
+                275_000_000,
+                Direction::Debit,
+            ),
+            (
+                "2021-08-16",
+                false,
+                "SETORAN TUNAI CABANG KCU SUDIRMAN",
+                1_000_000_000,
+                Direction::Credit,
+            ),
+        ],
+    );
+    assert_summary(
+        &mutasi,
+        (125_000_000, 1_000_000_000, 275_000_000, 850_000_000),
+    );
+}
+
+#[test]
+fn balance_pages() {
+    let idr = AccountBalance::new(include_str!("../fixtures/balance.html")).unwrap();
+    assert_eq!(idr.account_number(), "0123456789");
+    assert_eq!(idr.currency(), "IDR");
+    assert_eq!(idr.balance().amount(), Amount::from_minor(1_234_567_890));
+
+    let usd = AccountBalance::new(include_str!("../fixtures/balance_usd.html")).unwrap();
+    assert_eq!(usd.account_number(), "9876543210");
+    assert_eq!(usd.currency(), "USD");
+    assert_eq!(usd.balance().to_string(), "USD 1,250.75");
+}
+
+#[test]
+fn maintenance_pages_are_reported() {
+    let maintenance = include_str!("../fixtures/maintenance.html");
+    assert!(matches!(
+        AccountMutasi::new(maintenance),
+        Err(ParseError::MaintenancePage { .. })
+    ));
+    assert!(matches!(
+        AccountBalance::new(maintenance),
+        Err(ParseError::MaintenancePage { .. })
+    ));
+}
+
+#[test]
+fn maintenance_in_a_note_is_not_a_maintenance_page() {
+    let mutasi =
+        AccountMutasi::new(include_str!("../fixtures/statement_maintenance_fee.html")).unwrap();
+    assert_transactions(
+        &mutasi,
+        &[(
+            "2021-03-10",
+            false,
+            "TRSF E-BANKING DB 1003/FTSCY/WS95031 IURAN PEMELIHARAAN IPL MAR",
+            1_234_567,
+            Direction::Debit,
+        )],
+    );
+}
+
+#[test]
+fn login_outcomes() {
+    let outcome = |page| LoginOutcome::from_resp(page);
+    assert_eq!(
+        outcome(include_str!("../fixtures/login_wrong_password.html")),
+        LoginOutcome::WrongCredentials
+    );
+    assert_eq!(
+        outcome(include_str!("../fixtures/login_already_logged_in.html")),
+        LoginOutcome::AlreadyLoggedIn
+    );
+    assert_eq!(
+        outcome(include_str!("../fixtures/login_blocked.html")),
+        LoginOutcome::Blocked
+    );
+    assert_eq!(
+        outcome(include_str!("../fixtures/menu.html")),
+        LoginOutcome::Success
+    );
+    // a notice about blocked ATM cards is no login error
+    assert_eq!(
+        outcome(include_str!("../fixtures/menu_notice.html")),
+        LoginOutcome::Success
+    );
+}
+
+#[test]
+fn unexpected_pages_are_reported() {
+    let menu = include_str!("../fixtures/menu.html");
+    match AccountMutasi::new(menu) {
+        Err(ParseError::MissingTable { table, snippet }) => {
+            assert_eq!(table, "account info");
+            assert!(snippet.contains("Informasi Rekening"));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(matches!(
+        AccountBalance::new(menu),
+        Err(ParseError::MissingTable {
+            table: "balance",
+            ..
+        })
+    ));
+    assert!(matches!(
+        AccountMutasi::new("<html><body>TIDAK ADA TRANSAKSI</body></html>"),
+        Err(ParseError::EmptyStatement { .. })
+    ));
+}